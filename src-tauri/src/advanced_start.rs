@@ -0,0 +1,412 @@
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use tauri::{AppHandle, Manager, Url, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::{
+    AppError, AppResult, AppState, ensure_base_dir_within_top_level, is_kiosk, lan::escape_html,
+    resolve_top_level_folder, resolve_top_level_path, restart_launch_backend,
+    validate_backend_args,
+};
+
+const WINDOW_LABEL: &str = "advanced-start";
+const WINDOW_TITLE: &str = "Advanced Start";
+/// The form submits to this scheme; the window catches the navigation
+/// instead of loading anything.
+const FORM_SCHEME: &str = "carta-advanced-start";
+const MAX_VERBOSITY: u8 = 5;
+
+/// The backend options the window has fields for, and the rest as typed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct BackendOptions {
+    omp_threads: Option<u32>,
+    verbosity: Option<u8>,
+    top_level_folder: Option<String>,
+    other: Vec<String>,
+}
+
+impl BackendOptions {
+    /// Values that do not parse stay in `other`, so nothing is lost.
+    fn parse(args: &[String]) -> Self {
+        let mut options = Self::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            if !matches!(
+                name,
+                "--omp_threads" | "-t" | "--verbosity" | "--top_level_folder"
+            ) {
+                options.other.push(arg.clone());
+                continue;
+            }
+            let Some(value) = inline.or_else(|| iter.next().cloned()) else {
+                options.other.push(arg.clone());
+                continue;
+            };
+            let known = match name {
+                "--verbosity" => value
+                    .parse()
+                    .ok()
+                    .filter(|level| *level <= MAX_VERBOSITY)
+                    .map(|level| options.verbosity = Some(level)),
+                "--top_level_folder" => {
+                    options.top_level_folder = Some(value.clone());
+                    Some(())
+                }
+                _ => value
+                    .parse()
+                    .ok()
+                    .filter(|threads| *threads > 0)
+                    .map(|threads| options.omp_threads = Some(threads)),
+            };
+            if known.is_none() {
+                options.other.push(format!("{}={}", name, value));
+            }
+        }
+        options
+    }
+
+    fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(threads) = self.omp_threads {
+            args.push(format!("--omp_threads={}", threads));
+        }
+        if let Some(level) = self.verbosity {
+            args.push(format!("--verbosity={}", level));
+        }
+        if let Some(folder) = &self.top_level_folder {
+            args.push(format!("--top_level_folder={}", folder));
+        }
+        args.extend(self.other.iter().cloned());
+        args
+    }
+
+    fn from_form(url: &Url) -> AppResult<Self> {
+        let field = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let omp_threads = field("omp_threads")
+            .map(|value| {
+                value
+                    .parse()
+                    .ok()
+                    .filter(|threads| *threads > 0)
+                    .ok_or_else(|| {
+                        AppError::InvalidBackendArgs(format!(
+                            "Threads must be a whole number above zero, not {}",
+                            value
+                        ))
+                    })
+            })
+            .transpose()?;
+        let verbosity = field("verbosity")
+            .map(|value| {
+                value
+                    .parse()
+                    .ok()
+                    .filter(|level| *level <= MAX_VERBOSITY)
+                    .ok_or_else(|| {
+                        AppError::InvalidBackendArgs(format!(
+                            "Log verbosity must be 0 to {}, not {}",
+                            MAX_VERBOSITY, value
+                        ))
+                    })
+            })
+            .transpose()?;
+        Ok(Self {
+            omp_threads,
+            verbosity,
+            top_level_folder: field("top_level_folder"),
+            other: split_args(&field("other").unwrap_or_default())?,
+        })
+    }
+}
+
+/// Shows the backend options the session started with, for editing. Applying
+/// them restarts the launch backend. Focuses the window if it is already open.
+pub(crate) fn show(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+    let state = app.state::<AppState>();
+    if is_kiosk(&state) {
+        return;
+    }
+    let options = BackendOptions::parse(&state.backend_args.lock().unwrap());
+    if let Err(err) = open_window(app, &options) {
+        eprintln!("Warning: failed to show advanced start options: {}", err);
+    }
+}
+
+fn open_window(app: &AppHandle, options: &BackendOptions) -> AppResult<()> {
+    let page = Url::parse(&format!(
+        "data:text/html;charset=utf-8,{}",
+        utf8_percent_encode(&options_page(options), NON_ALPHANUMERIC)
+    ))
+    .map_err(|err| AppError::Other(err.to_string()))?;
+    let handle = app.clone();
+    WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::External(page))
+        .title(WINDOW_TITLE)
+        .inner_size(480.0, 520.0)
+        .resizable(false)
+        .on_navigation(move |url| {
+            if url.scheme() != FORM_SCHEME {
+                return true;
+            }
+            submit(&handle, url);
+            false
+        })
+        .build()
+        .map_err(|err| AppError::Other(err.to_string()))?;
+    Ok(())
+}
+
+fn submit(app: &AppHandle, url: &Url) {
+    if url.host_str() != Some("apply") {
+        close_window(app);
+        return;
+    }
+    let result = BackendOptions::from_form(url).and_then(|options| apply(app, options.to_args()));
+    match result {
+        Ok(()) => {
+            close_window(app);
+            restart_launch_backend(app);
+        }
+        Err(err) => {
+            app.dialog()
+                .message(err.to_string())
+                .title("CARTA")
+                .kind(MessageDialogKind::Error)
+                .show(|_| {});
+        }
+    }
+}
+
+/// The navigation is still being decided while `submit` runs, so the window
+/// closes once it returns.
+fn close_window(app: &AppHandle) {
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+            let _ = window.close();
+        }
+    });
+}
+
+/// Keeps the launch backend's options for its next start. The session's
+/// starting folder has to stay inside the top-level folder, and the launcher
+/// picks the port itself.
+fn apply(app: &AppHandle, args: Vec<String>) -> AppResult<()> {
+    validate_backend_args(&args)?;
+    if let Some(arg) = args
+        .iter()
+        .find(|arg| *arg == "-p" || *arg == "--port" || arg.starts_with("--port="))
+    {
+        return Err(AppError::InvalidBackendArgs(format!(
+            "Backend option {} is set by CARTA",
+            arg
+        )));
+    }
+    let state = app.state::<AppState>();
+    let top_level_folder = resolve_top_level_folder(&args).unwrap_or_else(|| "/".to_string());
+    let top_level = resolve_top_level_path(&top_level_folder)?;
+    if ensure_base_dir_within_top_level(state.base_dir.clone(), &top_level) != state.base_dir {
+        return Err(AppError::InvalidBackendArgs(format!(
+            "The top-level folder {} does not contain the session's folder {}",
+            top_level.display(),
+            state.base_dir.display()
+        )));
+    }
+    *state.backend_args.lock().unwrap() = args;
+    Ok(())
+}
+
+/// Splits on whitespace, keeping text in single or double quotes together.
+/// Backslashes are kept as typed, so Windows paths need no escaping.
+fn split_args(text: &str) -> AppResult<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote = None;
+    for ch in text.chars() {
+        match quote {
+            Some(open) if ch == open => quote = None,
+            Some(_) => current.get_or_insert_default().push(ch),
+            None if ch == '"' || ch == '\'' => {
+                quote = Some(ch);
+                current.get_or_insert_default();
+            }
+            None if ch.is_whitespace() => args.extend(current.take()),
+            None => current.get_or_insert_default().push(ch),
+        }
+    }
+    if quote.is_some() {
+        return Err(AppError::InvalidBackendArgs(
+            "Other options have an unclosed quote".to_string(),
+        ));
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+/// The inverse of `split_args`, for showing the options in the form.
+fn join_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if !arg.is_empty()
+                && !arg.contains(|ch: char| ch.is_whitespace() || ch == '"' || ch == '\'')
+            {
+                arg.clone()
+            } else if arg.contains('"') {
+                format!("'{}'", arg)
+            } else {
+                format!("\"{}\"", arg)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn options_page(options: &BackendOptions) -> String {
+    let verbosity_options = std::iter::once((String::new(), "Default".to_string()))
+        .chain((0..=MAX_VERBOSITY).map(|level| (level.to_string(), level.to_string())))
+        .map(|(value, label)| {
+            let selected = options
+                .verbosity
+                .map(|level| level.to_string())
+                .unwrap_or_default()
+                == value;
+            format!(
+                r#"<option value="{}"{}>{}</option>"#,
+                value,
+                if selected { " selected" } else { "" },
+                label
+            )
+        })
+        .collect::<String>();
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 20px; color: #222; background: #fff; }}
+label {{ display: block; margin: 14px 0 4px; font-weight: 600; }}
+input, select {{ width: 100%; box-sizing: border-box; padding: 5px; font-size: 14px; }}
+.hint {{ color: #666; font-size: 12px; margin: 4px 0 0; }}
+.note {{ background: #fff3cd; color: #664d03; border: 1px solid #ffda6a; border-radius: 6px; padding: 10px 12px; margin-top: 18px; }}
+.buttons {{ text-align: right; margin-top: 18px; }}
+button {{ padding: 5px 14px; margin-left: 8px; font-size: 14px; }}
+@media (prefers-color-scheme: dark) {{ body {{ color: #eee; background: #222; }} .hint {{ color: #aaa; }} }}
+</style>
+</head>
+<body>
+<form action="{scheme}://apply" method="get">
+<label for="omp_threads">Threads</label>
+<input id="omp_threads" name="omp_threads" type="number" min="1" placeholder="All available" value="{omp_threads}">
+<label for="verbosity">Log verbosity</label>
+<select id="verbosity" name="verbosity">{verbosity_options}</select>
+<label for="top_level_folder">Top-level folder</label>
+<input id="top_level_folder" name="top_level_folder" placeholder="/" value="{top_level_folder}">
+<p class="hint">The backend cannot open files outside this folder.</p>
+<label for="other">Other backend options</label>
+<input id="other" name="other" value="{other}">
+<p class="hint">As on the command line, for example <code>--no_log --idle_timeout=600</code>.</p>
+<div class="note">Applying restarts the backend, which closes any open images.</div>
+<div class="buttons">
+<button type="submit" formaction="{scheme}://cancel">Cancel</button>
+<button type="submit">Apply and Restart</button>
+</div>
+</form>
+</body>
+</html>"#,
+        title = WINDOW_TITLE,
+        scheme = FORM_SCHEME,
+        omp_threads = options
+            .omp_threads
+            .map(|threads| threads.to_string())
+            .unwrap_or_default(),
+        top_level_folder = escape_html(options.top_level_folder.as_deref().unwrap_or_default()),
+        other = escape_html(&join_args(&options.other)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn backend_options_parse_known_flags_and_keep_the_rest() {
+        let options = BackendOptions::parse(&args(&[
+            "-t",
+            "4",
+            "--verbosity=2",
+            "--top_level_folder",
+            "/data",
+            "--no_log",
+            "--verbosity=loud",
+        ]));
+        assert_eq!(
+            options,
+            BackendOptions {
+                omp_threads: Some(4),
+                verbosity: Some(2),
+                top_level_folder: Some("/data".to_string()),
+                other: args(&["--no_log", "--verbosity=loud"]),
+            }
+        );
+        assert_eq!(
+            options.to_args(),
+            args(&[
+                "--omp_threads=4",
+                "--verbosity=2",
+                "--top_level_folder=/data",
+                "--no_log",
+                "--verbosity=loud",
+            ])
+        );
+        assert_eq!(BackendOptions::parse(&options.to_args()), options);
+    }
+
+    #[test]
+    fn backend_options_from_form_checks_numbers() {
+        let form = |query: &str| {
+            BackendOptions::from_form(
+                &Url::parse(&format!("{}://apply?{}", FORM_SCHEME, query)).unwrap(),
+            )
+        };
+        let options =
+            form("omp_threads=8&verbosity=&top_level_folder=%2Fdata&other=--no_log+%22a+b%22")
+                .unwrap();
+        assert_eq!(options.omp_threads, Some(8));
+        assert_eq!(options.verbosity, None);
+        assert_eq!(options.top_level_folder.as_deref(), Some("/data"));
+        assert_eq!(options.other, args(&["--no_log", "a b"]));
+        assert!(form("omp_threads=0").is_err());
+        assert!(form("verbosity=9").is_err());
+    }
+
+    #[test]
+    fn split_args_respects_quotes() {
+        assert_eq!(
+            split_args(r#"  --root "C:\My Data" --x='it"s' "" "#).unwrap(),
+            args(&["--root", r"C:\My Data", r#"--x=it"s"#, ""])
+        );
+        assert!(split_args("--root \"open").is_err());
+        assert_eq!(
+            split_args(&join_args(&args(&["a b", "c\"d", ""]))).unwrap(),
+            args(&["a b", "c\"d", ""])
+        );
+    }
+}
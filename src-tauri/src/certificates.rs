@@ -0,0 +1,400 @@
+use std::{borrow::Cow, collections::HashMap, sync::Mutex};
+
+use sha2::{Digest, Sha256};
+use tauri::{
+    AppHandle, Manager, Url, WebviewWindow,
+    http::{Request, Response, StatusCode, header::CONTENT_TYPE},
+};
+
+use crate::{AppState, custom_scheme_origin, lan::escape_html, update_settings};
+
+/// Serves the page a window shows instead of a site whose certificate the
+/// system does not trust, such as a controller with a self-signed one.
+pub(crate) const SCHEME: &str = "carta-certificate";
+const PAGE_PATH: &str = "/untrusted";
+/// The page's Trust button; the window catches the navigation.
+const TRUST_PATH: &str = "/trust";
+const HOST_PARAM: &str = "host";
+const FINGERPRINT_PARAM: &str = "fingerprint";
+/// Where the window was going, for once the certificate is trusted.
+const RETURN_PARAM: &str = "return";
+
+/// Certificates windows were refused, as fingerprints by host. Only these can
+/// be trusted, so a page cannot trust a certificate of its choosing by going
+/// to the Trust button's address itself.
+#[derive(Default)]
+pub(crate) struct Refused(Mutex<HashMap<String, String>>);
+
+impl Refused {
+    fn insert(&self, host: &str, fingerprint: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), fingerprint.to_string());
+    }
+
+    /// Whether `host` was refused `fingerprint`, which it then no longer is.
+    fn take(&self, host: &str, fingerprint: &str) -> bool {
+        let mut refused = self.0.lock().unwrap();
+        let matches = refused
+            .get(host)
+            .is_some_and(|refused| refused == fingerprint);
+        if matches {
+            refused.remove(host);
+        }
+        matches
+    }
+}
+
+/// The SHA-256 fingerprint of a DER certificate, as browsers show it.
+pub(crate) fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn is_trusted(app: &AppHandle, host: &str, fingerprint: &str) -> bool {
+    app.state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .trusted_certificates
+        .get(host)
+        .is_some_and(|trusted| trusted == fingerprint)
+}
+
+/// Sends `window` to the untrusted-certificate page for `url`. Navigating
+/// from inside the webview's own error handler is not safe, so it happens
+/// from a task.
+fn refuse(window: &WebviewWindow, url: &Url, fingerprint: &str) {
+    let Some(host) = url.host_str() else {
+        return;
+    };
+    eprintln!(
+        "Warning: untrusted certificate for {} (SHA-256 {})",
+        host, fingerprint
+    );
+    window
+        .state::<AppState>()
+        .refused_certificates
+        .insert(host, fingerprint);
+    let page = page_url(url, host, fingerprint);
+    let window = window.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = window.navigate(page) {
+            eprintln!(
+                "Warning: failed to show the certificate page in window {}: {}",
+                window.label(),
+                err
+            );
+        }
+    });
+}
+
+fn page_url(return_to: &Url, host: &str, fingerprint: &str) -> Url {
+    let mut url = Url::parse(&custom_scheme_origin(SCHEME))
+        .and_then(|origin| origin.join(PAGE_PATH))
+        .expect("valid certificate page URL");
+    url.query_pairs_mut()
+        .append_pair(HOST_PARAM, host)
+        .append_pair(FINGERPRINT_PARAM, fingerprint)
+        .append_pair(RETURN_PARAM, return_to.as_str());
+    url
+}
+
+fn is_certificate_url(url: &Url) -> bool {
+    url.as_str().starts_with(&custom_scheme_origin(SCHEME))
+}
+
+/// Host, fingerprint and return address from the page or its Trust button.
+fn params(url: &Url) -> Option<(String, String, Url)> {
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    Some((
+        param(HOST_PARAM)?,
+        param(FINGERPRINT_PARAM)?,
+        Url::parse(&param(RETURN_PARAM)?).ok()?,
+    ))
+}
+
+/// Handles the page's Trust button in the window `label`; true when `url`
+/// was it. The certificate is remembered for its host, and the window goes
+/// back to where it was, where the certificate is now accepted.
+pub(crate) fn handle_navigation(app: &AppHandle, label: &str, url: &Url) -> bool {
+    if !is_certificate_url(url) || url.path() != TRUST_PATH {
+        return false;
+    }
+    let Some((host, fingerprint, return_to)) = params(url) else {
+        return true;
+    };
+    if !app
+        .state::<AppState>()
+        .refused_certificates
+        .take(&host, &fingerprint)
+    {
+        return true;
+    }
+    update_settings(app, |settings| {
+        settings.trusted_certificates.insert(host, fingerprint);
+    });
+    let Some(window) = app.get_webview_window(label) else {
+        return true;
+    };
+    // The navigation is still being decided here.
+    tauri::async_runtime::spawn(async move {
+        let _ = window.navigate(return_to);
+    });
+    true
+}
+
+pub(crate) fn handle(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Cow<'static, [u8]>> {
+    let params = Url::parse(&request.uri().to_string())
+        .ok()
+        .filter(|url| url.path() == PAGE_PATH)
+        .and_then(|url| params(&url));
+    let Some((host, fingerprint, return_to)) = params else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Cow::Borrowed(&[][..]))
+            .unwrap_or_else(|_| Response::new(Cow::Borrowed(&[][..])));
+    };
+    let trusted = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .trusted_certificates
+        .get(&host)
+        .cloned();
+    Response::builder()
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Cow::Owned(
+            page(&host, &fingerprint, trusted.as_deref(), &return_to).into_bytes(),
+        ))
+        .unwrap_or_else(|_| Response::new(Cow::Borrowed(&[][..])))
+}
+
+/// `trusted` is a different certificate trusted for the host before.
+fn page(host: &str, fingerprint: &str, trusted: Option<&str>, return_to: &Url) -> String {
+    let changed = match trusted {
+        Some(trusted) => format!(
+            "<p class=\"warning\">This is not the certificate trusted for this site before \
+             (SHA-256 {}). Trust the new one only if the site's administrators changed it.</p>",
+            escape_html(trusted)
+        ),
+        None => String::new(),
+    };
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>CARTA</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 0; display: flex; align-items: center; justify-content: center; min-height: 100vh; color: #222; background: #fff; }}
+main {{ max-width: 640px; padding: 20px; text-align: center; }}
+code {{ display: block; margin: 10px 0; padding: 10px; background: #f4f4f4; font-size: 12px; word-break: break-all; }}
+.warning {{ color: #b00020; }}
+button {{ padding: 5px 14px; font-size: 14px; }}
+@media (prefers-color-scheme: dark) {{ body {{ color: #eee; background: #222; }} code {{ background: #333; }} .warning {{ color: #ff8a80; }} }}
+</style>
+</head>
+<body>
+<main>
+<h2>Untrusted certificate</h2>
+<p>The certificate of <strong>{host}</strong> is not trusted by this computer, for instance because it is self-signed or issued by an institutional authority that is not installed.</p>
+{changed}
+<p>Compare its SHA-256 fingerprint with the one the site's administrators publish:</p>
+<code>{fingerprint}</code>
+<form action="{trust}" method="get">
+<input type="hidden" name="{host_param}" value="{host}">
+<input type="hidden" name="{fingerprint_param}" value="{fingerprint}">
+<input type="hidden" name="{return_param}" value="{return_to}">
+<button>Trust This Certificate</button>
+</form>
+</main>
+</body>
+</html>"#,
+        host = escape_html(host),
+        fingerprint = escape_html(fingerprint),
+        trust = TRUST_PATH.trim_start_matches('/'),
+        return_to = escape_html(return_to.as_str()),
+        host_param = HOST_PARAM,
+        fingerprint_param = FINGERPRINT_PARAM,
+        return_param = RETURN_PARAM,
+    )
+}
+
+/// WebKitGTK reports the certificate of a refused page load; a trusted one is
+/// allowed for its host and the page loaded again.
+#[cfg(target_os = "linux")]
+pub(crate) fn watch(window: &WebviewWindow) {
+    use webkit2gtk::{WebContextExt, WebViewExt, gio::prelude::TlsCertificateExt, glib};
+
+    let target = window.clone();
+    let result = window.with_webview(move |webview| {
+        webview.inner().connect_load_failed_with_tls_errors(
+            move |webview, failing_uri, certificate, _errors| {
+                let Ok(url) = Url::parse(failing_uri) else {
+                    return false;
+                };
+                let (Some(host), Some(der)) = (url.host_str(), certificate.certificate()) else {
+                    return false;
+                };
+                let fingerprint = fingerprint(&der);
+                if !is_trusted(target.app_handle(), host, &fingerprint) {
+                    refuse(&target, &url, &fingerprint);
+                    return true;
+                }
+                let Some(context) = webview.context() else {
+                    return false;
+                };
+                context.allow_tls_certificate_for_host(certificate, host);
+                let (webview, uri) = (webview.clone(), failing_uri.to_string());
+                glib::idle_add_local_once(move || webview.load_uri(&uri));
+                true
+            },
+        );
+    });
+    if let Err(err) = result {
+        eprintln!("Warning: failed to watch for certificate errors: {}", err);
+    }
+}
+
+/// WebView2 asks about each certificate it would refuse; a trusted one is
+/// allowed for the rest of the session, and the request goes on.
+#[cfg(target_os = "windows")]
+pub(crate) fn watch(window: &WebviewWindow) {
+    use webview2_com::{
+        Microsoft::Web::WebView2::Win32::{
+            COREWEBVIEW2_SERVER_CERTIFICATE_ERROR_ACTION_ALWAYS_ALLOW,
+            COREWEBVIEW2_SERVER_CERTIFICATE_ERROR_ACTION_CANCEL, ICoreWebView2_14,
+        },
+        ServerCertificateErrorDetectedEventHandler, take_pwstr,
+    };
+    use windows::core::{Interface, PWSTR};
+
+    let target = window.clone();
+    let result = window.with_webview(move |webview| {
+        let handler =
+            ServerCertificateErrorDetectedEventHandler::create(Box::new(move |_, args| {
+                let Some(args) = args else {
+                    return Ok(());
+                };
+                let (mut uri, mut pem) = (PWSTR::null(), PWSTR::null());
+                // SAFETY: WebView2 calls this on the main thread with live
+                // arguments; `take_pwstr` frees the strings they return.
+                let (uri, pem) = unsafe {
+                    args.RequestUri(&mut uri)?;
+                    let uri = take_pwstr(uri);
+                    args.ServerCertificate()?.ToPemEncoding(&mut pem)?;
+                    (uri, take_pwstr(pem))
+                };
+                let (Ok(url), Some(der)) = (Url::parse(&uri), pem_der(&pem)) else {
+                    return Ok(());
+                };
+                let Some(host) = url.host_str() else {
+                    return Ok(());
+                };
+                let fingerprint = fingerprint(&der);
+                if is_trusted(target.app_handle(), host, &fingerprint) {
+                    // SAFETY: as above.
+                    return unsafe {
+                        args.SetAction(COREWEBVIEW2_SERVER_CERTIFICATE_ERROR_ACTION_ALWAYS_ALLOW)
+                    };
+                }
+                // SAFETY: as above.
+                unsafe { args.SetAction(COREWEBVIEW2_SERVER_CERTIFICATE_ERROR_ACTION_CANCEL)? };
+                refuse(&target, &url, &fingerprint);
+                Ok(())
+            }));
+        let mut token = 0;
+        // SAFETY: runs on the main thread, which owns the webview.
+        let result = unsafe {
+            webview
+                .controller()
+                .CoreWebView2()
+                .and_then(|core| core.cast::<ICoreWebView2_14>())
+                .and_then(|core| core.add_ServerCertificateErrorDetected(&handler, &mut token))
+        };
+        if let Err(err) = result {
+            eprintln!("Warning: failed to watch for certificate errors: {}", err);
+        }
+    });
+    if let Err(err) = result {
+        eprintln!("Warning: failed to watch for certificate errors: {}", err);
+    }
+}
+
+/// The first certificate in PEM text, as DER.
+#[cfg(target_os = "windows")]
+fn pem_der(pem: &str) -> Option<Vec<u8>> {
+    use base64::Engine;
+
+    let body = pem
+        .split("-----BEGIN CERTIFICATE-----")
+        .nth(1)?
+        .split("-----END CERTIFICATE-----")
+        .next()?;
+    let body: String = body.chars().filter(|ch| !ch.is_whitespace()).collect();
+    base64::engine::general_purpose::STANDARD.decode(body).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_is_colon_separated_sha256() {
+        assert_eq!(
+            fingerprint(b"abc"),
+            "BA:78:16:BF:8F:01:CF:EA:41:41:40:DE:5D:AE:22:23:\
+             B0:03:61:A3:96:17:7A:9C:B4:10:FF:61:F2:00:15:AD"
+        );
+    }
+
+    #[test]
+    fn only_refused_certificates_can_be_trusted() {
+        let refused = Refused::default();
+        refused.insert("carta.example.edu", "AA:BB");
+        assert!(!refused.take("carta.example.edu", "CC:DD"));
+        assert!(!refused.take("other.example.edu", "AA:BB"));
+        assert!(refused.take("carta.example.edu", "AA:BB"));
+        assert!(!refused.take("carta.example.edu", "AA:BB"));
+    }
+
+    #[test]
+    fn page_url_carries_host_fingerprint_and_return() {
+        let window = Url::parse("https://carta.example.edu/dashboard?x=1").unwrap();
+        let page = page_url(&window, "carta.example.edu", "AA:BB");
+        assert!(is_certificate_url(&page));
+        assert_eq!(page.path(), PAGE_PATH);
+        assert_eq!(
+            params(&page),
+            Some(("carta.example.edu".to_string(), "AA:BB".to_string(), window))
+        );
+    }
+
+    #[test]
+    fn page_escapes_its_values_and_warns_of_changes() {
+        let return_to = Url::parse("https://carta.example.edu/?a=1&b=2").unwrap();
+        let html = page("<host>", "AA:BB", None, &return_to);
+        assert!(html.contains(r#"name="host" value="&lt;host&gt;""#));
+        assert!(html.contains(r#"value="https://carta.example.edu/?a=1&amp;b=2""#));
+        assert!(!html.contains("not the certificate trusted"));
+        assert!(page("host", "AA:BB", Some("CC:DD"), &return_to).contains("CC:DD"));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn pem_der_decodes_the_first_certificate() {
+        let pem = "-----BEGIN CERTIFICATE-----\r\nYWJj\r\n-----END CERTIFICATE-----\r\n";
+        assert_eq!(pem_der(pem).as_deref(), Some(&b"abc"[..]));
+        assert_eq!(pem_der("garbage"), None);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{borrow::Cow, sync::atomic::Ordering};
+
+use tauri::{
+    AppHandle, Manager, Url,
+    http::{Request, Response, StatusCode, header::CONTENT_TYPE},
+};
+
+use crate::{
+    AppState, custom_scheme_origin, frontend_protocol, is_session_window, lan::escape_html,
+    restart_launch_backend, supervisor,
+};
+
+/// Serves the page windows show while the launch backend is down.
+pub(crate) const SCHEME: &str = "carta-status";
+const PAGE_PATH: &str = "/connection-lost";
+/// The page's Retry button; the window catches the navigation.
+const RETRY_PATH: &str = "/retry";
+/// Where the window was, for when the backend is back.
+const RETURN_PARAM: &str = "return";
+const LOG_LINES: usize = 30;
+
+/// Sends the windows on the launch backend to the connection-lost page
+/// instead of leaving them on the webview's own error page.
+pub(crate) fn show(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    for (label, window) in app.webview_windows() {
+        if !is_session_window(&label) {
+            continue;
+        }
+        let Ok(url) = window.url() else {
+            continue;
+        };
+        if frontend_protocol::backend_port(&url) != Some(state.backend_port()) {
+            continue;
+        }
+        if let Err(err) = window.navigate(page_url(&url)) {
+            eprintln!(
+                "Warning: failed to show the connection-lost page in window {}: {}",
+                label, err
+            );
+        }
+    }
+}
+
+fn page_url(return_to: &Url) -> Url {
+    let mut url = Url::parse(&custom_scheme_origin(SCHEME))
+        .and_then(|origin| origin.join(PAGE_PATH))
+        .expect("valid status page URL");
+    url.query_pairs_mut()
+        .append_pair(RETURN_PARAM, return_to.as_str());
+    url
+}
+
+fn is_status_url(url: &Url) -> bool {
+    url.as_str().starts_with(&custom_scheme_origin(SCHEME))
+}
+
+/// The page a window on the connection-lost page showed before.
+pub(crate) fn return_url(url: &Url) -> Option<Url> {
+    if !is_status_url(url) {
+        return None;
+    }
+    url.query_pairs()
+        .find(|(name, _)| name == RETURN_PARAM)
+        .and_then(|(_, value)| Url::parse(&value).ok())
+}
+
+/// Handles the page's Retry button; true when `url` was it.
+pub(crate) fn handle_navigation(app: &AppHandle, url: &Url) -> bool {
+    if !is_status_url(url) || url.path() != RETRY_PATH {
+        return false;
+    }
+    // The navigation is still being decided here, and windows navigate again.
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || retry(&handle));
+    true
+}
+
+/// Reloads when the backend is already back, or starts it again unless the
+/// supervisor is about to.
+fn retry(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if state.backend_restarting.load(Ordering::Relaxed) {
+        return;
+    }
+    let running = state
+        .backend
+        .lock()
+        .unwrap()
+        .as_mut()
+        .is_some_and(|child| matches!(child.try_wait(), Ok(None)));
+    if running {
+        supervisor::reload_windows(app, state.backend_port());
+    } else {
+        restart_launch_backend(app);
+    }
+}
+
+pub(crate) fn handle(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Cow<'static, [u8]>> {
+    if request.uri().path() != PAGE_PATH {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Cow::Borrowed(&[][..]))
+            .unwrap_or_else(|_| Response::new(Cow::Borrowed(&[][..])));
+    }
+    let log = app.state::<AppState>().backend_log.tail(LOG_LINES);
+    let log = log
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Response::builder()
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Cow::Owned(page(&log).into_bytes()))
+        .unwrap_or_else(|_| Response::new(Cow::Borrowed(&[][..])))
+}
+
+fn page(log: &str) -> String {
+    let log = if log.is_empty() {
+        "The backend wrote no output.".to_string()
+    } else {
+        escape_html(log)
+    };
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>CARTA</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 0; display: flex; align-items: center; justify-content: center; min-height: 100vh; color: #222; background: #fff; }}
+main {{ max-width: 640px; padding: 20px; text-align: center; }}
+.buttons {{ margin: 18px 0; }}
+button, summary {{ padding: 5px 14px; margin: 0 4px; font-size: 14px; }}
+details {{ display: inline; }}
+summary {{ display: inline-block; cursor: pointer; border: 1px solid #888; border-radius: 4px; list-style: none; }}
+pre {{ text-align: left; max-height: 40vh; overflow: auto; background: #f4f4f4; padding: 10px; font-size: 12px; white-space: pre-wrap; }}
+@media (prefers-color-scheme: dark) {{ body {{ color: #eee; background: #222; }} pre {{ background: #333; }} }}
+</style>
+</head>
+<body>
+<main>
+<h2>Backend connection lost</h2>
+<p>The CARTA backend stopped. Images that were open need to be loaded again once it is back.</p>
+<div class="buttons">
+<form action="{retry}" method="get" style="display: inline"><button>Retry</button></form>
+<details><summary>View Logs</summary><pre>{log}</pre></details>
+</div>
+</main>
+</body>
+</html>"#,
+        retry = RETRY_PATH.trim_start_matches('/'),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_url_keeps_the_window_page_to_return_to() {
+        let window = Url::parse("http://localhost:3002/?token=abc&file=m51.fits").unwrap();
+        let page = page_url(&window);
+        assert!(is_status_url(&page));
+        assert_eq!(page.path(), PAGE_PATH);
+        assert_eq!(return_url(&page), Some(window.clone()));
+        assert_eq!(return_url(&window), None);
+    }
+
+    #[test]
+    fn page_escapes_backend_output() {
+        let page = page("Error: <file> not found");
+        assert!(page.contains("Error: &lt;file&gt; not found"));
+        assert!(page.contains(r#"action="retry""#));
+    }
+}
@@ -0,0 +1,99 @@
+use tauri::Url;
+
+/// A `carta_controller` deployment's address, from `--controller` or the
+/// `controller_url` setting.
+pub(crate) fn parse_url(value: &str) -> Result<Url, String> {
+    let url = Url::parse(value.trim())
+        .map_err(|err| format!("Invalid controller URL: {} ({})", value, err))?;
+    match url.scheme() {
+        "http" | "https" if url.host_str().is_some() => Ok(url),
+        _ => Err(format!(
+            "Invalid controller URL: {} (use http:// or https://)",
+            value
+        )),
+    }
+}
+
+/// Whether a controller window follows a navigation itself: pages on the
+/// controller, and on the identity providers in the
+/// `controller_sign_in_hosts` setting that signing in goes through before it
+/// is sent back, so the login cookies end up in the window. Other links open
+/// in the browser as in any window.
+pub(crate) fn keeps_navigation(controller: &Url, sign_in_hosts: &[String], url: &Url) -> bool {
+    if url.origin() == controller.origin() {
+        return true;
+    }
+    url.scheme() == "https"
+        && url.host_str().is_some_and(|host| {
+            sign_in_hosts
+                .iter()
+                .any(|allowed| is_host_or_subdomain(host, allowed))
+        })
+}
+
+fn is_host_or_subdomain(host: &str, allowed: &str) -> bool {
+    let allowed = allowed.trim().trim_start_matches('.').to_ascii_lowercase();
+    !allowed.is_empty()
+        && (host == allowed
+            || host
+                .strip_suffix(&allowed)
+                .is_some_and(|rest| rest.ends_with('.')))
+}
+
+/// Whether a popup from a controller window opens in the app: sign-in popups
+/// start on the controller and report back to the page that opened them.
+pub(crate) fn keeps_popup(controller: &Url, url: &Url) -> bool {
+    url.origin() == controller.origin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url_accepts_web_addresses_only() {
+        assert_eq!(
+            parse_url(" https://carta.example.edu/ ").unwrap().as_str(),
+            "https://carta.example.edu/"
+        );
+        assert!(parse_url("http://localhost:8000").is_ok());
+        assert!(parse_url("carta.example.edu").is_err());
+        assert!(parse_url("file:///srv/carta").is_err());
+    }
+
+    #[test]
+    fn popups_stay_in_the_app_on_the_controller_only() {
+        let controller = Url::parse("https://carta.example.edu/").unwrap();
+        let url = |value: &str| Url::parse(value).unwrap();
+        assert!(keeps_popup(
+            &controller,
+            &url("https://carta.example.edu/api/auth/login")
+        ));
+        assert!(!keeps_popup(
+            &controller,
+            &url("https://carta.readthedocs.io/")
+        ));
+        assert!(!keeps_popup(&controller, &url("http://carta.example.edu/")));
+    }
+
+    #[test]
+    fn navigation_stays_on_the_controller_and_sign_in_hosts() {
+        let controller = Url::parse("https://carta.example.edu/").unwrap();
+        let sign_in_hosts = vec!["login.example.edu".to_string(), ".okta.com".to_string()];
+        let keeps = |value: &str| {
+            keeps_navigation(&controller, &sign_in_hosts, &Url::parse(value).unwrap())
+        };
+        assert!(keeps("https://carta.example.edu/dashboard"));
+        assert!(keeps("https://login.example.edu/sso"));
+        assert!(keeps("https://example.okta.com/oauth2/authorize"));
+        assert!(!keeps("https://evillogin.example.edu/"));
+        assert!(!keeps("http://login.example.edu/sso"));
+        assert!(!keeps("https://carta.readthedocs.io/"));
+        assert!(!keeps("mailto:help@example.edu"));
+        assert!(!keeps_navigation(
+            &controller,
+            &[],
+            &Url::parse("https://login.example.edu/sso").unwrap()
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use tauri::{AppHandle, Emitter, Manager, Runtime, Url, Webview, webview::DownloadEvent};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::AppState;
+
+const EVENT_DOWNLOAD_PROGRESS: &str = "download-progress";
+const STAGING_DIR: &str = "downloads";
+const DEFAULT_FILE_NAME: &str = "download";
+
+/// Downloads in flight, keyed by URL. macOS never reports where a finished
+/// download went, so the destination chosen at request time is kept here.
+#[derive(Default)]
+pub(crate) struct Downloads {
+    pending: Mutex<HashMap<String, PendingDownload>>,
+}
+
+struct PendingDownload {
+    path: PathBuf,
+    /// Saved to a staging file first; ask for the final location once done.
+    ask: bool,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum DownloadStatus {
+    Started,
+    Finished,
+    Failed,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct DownloadProgress {
+    url: String,
+    status: DownloadStatus,
+    path: Option<PathBuf>,
+}
+
+/// Handles frontend downloads (image exports, region files, catalogs) the same
+/// way on every platform: into the configured downloads folder, or through a
+/// save dialog when none is configured. Emits `download-progress` events.
+pub(crate) fn handle<R: Runtime>(webview: &Webview<R>, event: DownloadEvent<'_>) -> bool {
+    let app = webview.app_handle();
+    let state = app.state::<AppState>();
+    match event {
+        DownloadEvent::Requested { url, destination } => {
+            let name = file_name(&url, destination);
+            let download_dir = state.settings.lock().unwrap().download_dir.clone();
+            let (dir, ask) = match download_dir {
+                Some(dir) => (dir, false),
+                None => match app.path().app_cache_dir() {
+                    Ok(cache) => (
+                        cache
+                            .join(STAGING_DIR)
+                            .join(uuid::Uuid::new_v4().to_string()),
+                        true,
+                    ),
+                    Err(err) => {
+                        eprintln!("Warning: no staging directory for download: {}", err);
+                        return false;
+                    }
+                },
+            };
+            if let Err(err) = fs::create_dir_all(&dir) {
+                eprintln!("Warning: failed to create {}: {}", dir.display(), err);
+                return false;
+            }
+            let path = unique_path(&dir, &name);
+            *destination = path.clone();
+            state
+                .downloads
+                .pending
+                .lock()
+                .unwrap()
+                .insert(url.to_string(), PendingDownload { path, ask });
+            emit(webview, &url, DownloadStatus::Started, None);
+            true
+        }
+        DownloadEvent::Finished { url, success, .. } => {
+            let pending = state.downloads.pending.lock().unwrap().remove(url.as_str());
+            let Some(pending) = pending else {
+                return true;
+            };
+            if !success {
+                if pending.ask {
+                    remove_staging(&pending.path);
+                }
+                emit(webview, &url, DownloadStatus::Failed, None);
+            } else if pending.ask {
+                ask_save_location(app, webview.clone(), url, pending.path);
+            } else {
+                reveal(&pending.path);
+                emit(webview, &url, DownloadStatus::Finished, Some(pending.path));
+            }
+            true
+        }
+        _ => true,
+    }
+}
+
+fn ask_save_location<R: Runtime>(
+    app: &AppHandle<R>,
+    webview: Webview<R>,
+    url: Url,
+    staged: PathBuf,
+) {
+    let name = staged
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| DEFAULT_FILE_NAME.to_string());
+    let mut dialog = app.dialog().file().set_file_name(name);
+    if let Ok(dir) = app.path().download_dir() {
+        dialog = dialog.set_directory(dir);
+    }
+    dialog.save_file(move |target| {
+        let Some(target) = target.and_then(|target| target.into_path().ok()) else {
+            remove_staging(&staged);
+            emit(&webview, &url, DownloadStatus::Failed, None);
+            return;
+        };
+        match move_file(&staged, &target) {
+            Ok(()) => {
+                remove_staging(&staged);
+                reveal(&target);
+                emit(&webview, &url, DownloadStatus::Finished, Some(target));
+            }
+            Err(err) => {
+                eprintln!("Warning: failed to save {}: {}", target.display(), err);
+                remove_staging(&staged);
+                emit(&webview, &url, DownloadStatus::Failed, None);
+            }
+        }
+    });
+}
+
+fn emit<R: Runtime>(
+    webview: &Webview<R>,
+    url: &Url,
+    status: DownloadStatus,
+    path: Option<PathBuf>,
+) {
+    let payload = DownloadProgress {
+        url: url.to_string(),
+        status,
+        path,
+    };
+    let _ = webview.emit(EVENT_DOWNLOAD_PROGRESS, payload);
+}
+
+fn reveal(path: &Path) {
+    if let Err(err) = tauri_plugin_opener::reveal_item_in_dir(path) {
+        eprintln!("Warning: failed to reveal {}: {}", path.display(), err);
+    }
+}
+
+/// Renames, falling back to copy + delete across filesystems.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+/// Removes a staged file and its per-download directory.
+fn remove_staging(path: &Path) {
+    let _ = fs::remove_file(path);
+    if let Some(dir) = path.parent() {
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+/// The webview's suggested name when it has one, else the last URL segment.
+fn file_name(url: &Url, suggested: &Path) -> String {
+    suggested
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .or_else(|| {
+            url.path_segments()?
+                .next_back()
+                .filter(|segment| !segment.is_empty())
+                .map(ToString::to_string)
+        })
+        .unwrap_or_else(|| DEFAULT_FILE_NAME.to_string())
+}
+
+/// `dir/name`, or `dir/name (n).ext` if that file already exists.
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let path = Path::new(name);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| name.to_string());
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_path_numbers_existing_files() {
+        let dir = std::env::temp_dir().join(format!("carta-downloads-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(unique_path(&dir, "image.png"), dir.join("image.png"));
+        fs::write(dir.join("image.png"), b"").unwrap();
+        assert_eq!(unique_path(&dir, "image.png"), dir.join("image (1).png"));
+        fs::write(dir.join("image (1).png"), b"").unwrap();
+        assert_eq!(unique_path(&dir, "image.png"), dir.join("image (2).png"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn file_name_prefers_suggestion_then_url() {
+        let url = Url::parse("http://localhost:3002/export/regions.crtf").unwrap();
+        assert_eq!(file_name(&url, Path::new("/tmp/m51.png")), "m51.png");
+        assert_eq!(file_name(&url, Path::new("")), "regions.crtf");
+        let blob = Url::parse("blob:http://localhost:3002/").unwrap();
+        assert_eq!(file_name(&blob, Path::new("")), DEFAULT_FILE_NAME);
+    }
+}
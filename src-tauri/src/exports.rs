@@ -0,0 +1,84 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use tauri::{Manager, WebviewWindow};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::{AppState, write_file_atomic};
+
+/// A choice in the save dialog's file type list, e.g. "PNG image" for `png`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub(crate) struct FileFilter {
+    name: String,
+    extensions: Vec<String>,
+}
+
+/// Paths chosen in the save dialog, which the frontend may then write once.
+/// Nothing else can be written, so a page cannot overwrite files on its own.
+#[derive(Default)]
+pub(crate) struct SaveTargets(Mutex<HashSet<PathBuf>>);
+
+impl SaveTargets {
+    fn allow(&self, path: PathBuf) {
+        self.0.lock().unwrap().insert(path);
+    }
+
+    fn take(&self, path: &Path) -> bool {
+        self.0.lock().unwrap().remove(path)
+    }
+}
+
+/// Asks where to save an export; `None` when the dialog is cancelled. Blocks
+/// until then, so is not called on the main thread.
+pub(crate) fn ask_save_path(
+    window: &WebviewWindow,
+    file_name: Option<String>,
+    filters: &[FileFilter],
+) -> Option<PathBuf> {
+    let mut dialog = window.app_handle().dialog().file().set_parent(window);
+    if let Some(file_name) = file_name {
+        dialog = dialog.set_file_name(file_name);
+    }
+    for filter in filters {
+        let extensions: Vec<&str> = filter.extensions.iter().map(String::as_str).collect();
+        dialog = dialog.add_filter(&filter.name, &extensions);
+    }
+    let path = dialog.blocking_save_file()?.into_path().ok()?;
+    window.state::<AppState>().save_targets.allow(path.clone());
+    Some(path)
+}
+
+/// Writes an export to a path the user chose in `ask_save_path`.
+pub(crate) fn write(targets: &SaveTargets, path: &Path, contents: &[u8]) -> Result<(), String> {
+    if !targets.take(path) {
+        return Err(format!(
+            "{} was not chosen in the save dialog",
+            path.display()
+        ));
+    }
+    write_file_atomic(path, contents)
+        .map_err(|err| format!("Failed to save {}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_needs_a_path_from_the_dialog() {
+        let dir = std::env::temp_dir().join(format!("carta-export-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("m51.png");
+        let targets = SaveTargets::default();
+        assert!(write(&targets, &path, b"png").is_err());
+        assert!(!path.exists());
+
+        targets.allow(path.clone());
+        write(&targets, &path, b"png").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"png");
+        assert!(write(&targets, &path, b"again").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    ffi::OsString,
+    os::windows::{ffi::OsStringExt, process::CommandExt},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use windows::{
+    Win32::UI::Shell::{
+        ASSOCF_NOTRUNCATE, ASSOCSTR_EXECUTABLE, AssocQueryStringW, SHCNE_ASSOCCHANGED,
+        SHCNF_IDLIST, SHChangeNotify,
+    },
+    core::{HSTRING, PWSTR, w},
+};
+
+use crate::{AppError, AppResult, CREATE_NO_WINDOW, process::ProcessRunner};
+
+/// The image types of `bundle.fileAssociations` that Explorer knows by their
+/// last extension; `.fits.gz` is a `.gz` to it, and CASA and MIRIAD images
+/// are folders.
+const EXTENSIONS: &[&str] = &["fits", "fit", "fts", "fz", "hdf5", "h5"];
+const PROG_ID: &str = "CARTA.Image";
+const CLASSES_KEY: &str = r"HKCU\Software\Classes";
+/// Explorer's record of the app picked under "Open with", which wins over
+/// `CLASSES_KEY`.
+const FILE_EXTS_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\FileExts";
+
+/// The extensions that Explorer opens with something other than this
+/// CARTA: another app, an older install elsewhere, or nothing.
+pub(crate) fn unowned_extensions() -> Vec<&'static str> {
+    let Ok(exe) = std::env::current_exe() else {
+        return Vec::new();
+    };
+    EXTENSIONS
+        .iter()
+        .copied()
+        .filter(|extension| !handler(extension).is_some_and(|handler| same_file(&handler, &exe)))
+        .collect()
+}
+
+/// Makes this CARTA open the extensions from `unowned_extensions`. The
+/// files then reach it as a path argument, which a running CARTA takes
+/// over in a new window.
+pub(crate) fn register(processes: &dyn ProcessRunner) -> AppResult<()> {
+    let extensions = unowned_extensions();
+    if extensions.is_empty() {
+        return Ok(());
+    }
+    associate(processes, &std::env::current_exe()?, &extensions)?;
+    // SAFETY: the association event takes no items.
+    unsafe { SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, None, None) };
+
+    let remaining = unowned_extensions();
+    if remaining.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::Other(format!(
+        "Windows still opens .{} with another app. Right-click such a file, choose \
+         Open with > Choose another app, and pick CARTA with \"Always\".",
+        remaining.join(", .")
+    )))
+}
+
+/// Writes the registry keys that have `exe` open `extensions`, and forgets
+/// the "Open with" choices that would win over them.
+fn associate(processes: &dyn ProcessRunner, exe: &Path, extensions: &[&str]) -> AppResult<()> {
+    let prog_id = format!(r"{}\{}", CLASSES_KEY, PROG_ID);
+    reg_set(processes, &prog_id, "Astronomical image")?;
+    reg_set(
+        processes,
+        &format!(r"{}\DefaultIcon", prog_id),
+        &format!("\"{}\",0", exe.display()),
+    )?;
+    reg_set(
+        processes,
+        &format!(r"{}\shell\open\command", prog_id),
+        &format!("\"{}\" \"%1\"", exe.display()),
+    )?;
+    for extension in extensions {
+        let key = format!(r"{}\.{}", CLASSES_KEY, extension);
+        reg_set(processes, &key, PROG_ID)?;
+        reg(
+            processes,
+            &[
+                "add",
+                &format!(r"{}\OpenWithProgids", key),
+                "/v",
+                PROG_ID,
+                "/t",
+                "REG_NONE",
+                "/f",
+            ],
+        )?;
+        // Fails when there is no choice to forget.
+        let _ = reg(
+            processes,
+            &[
+                "delete",
+                &format!(r"{}\.{}\UserChoice", FILE_EXTS_KEY, extension),
+                "/f",
+            ],
+        );
+    }
+    Ok(())
+}
+
+/// The program Explorer runs to open `extension`, after "Open with" choices.
+fn handler(extension: &str) -> Option<PathBuf> {
+    let mut buffer = [0u16; 1024];
+    let mut len = buffer.len() as u32;
+    // SAFETY: `buffer` holds `len` characters, which is updated to the
+    // length written, with its terminating null.
+    unsafe {
+        AssocQueryStringW(
+            ASSOCF_NOTRUNCATE,
+            ASSOCSTR_EXECUTABLE,
+            &HSTRING::from(format!(".{}", extension)),
+            w!("open"),
+            Some(PWSTR(buffer.as_mut_ptr())),
+            &mut len,
+        )
+        .ok()
+        .ok()?;
+    }
+    let len = (len as usize).saturating_sub(1).min(buffer.len());
+    Some(PathBuf::from(OsString::from_wide(&buffer[..len])))
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.as_os_str().eq_ignore_ascii_case(b.as_os_str()),
+    }
+}
+
+/// Sets the default value of `key`.
+fn reg_set(processes: &dyn ProcessRunner, key: &str, value: &str) -> AppResult<()> {
+    reg(
+        processes,
+        &["add", key, "/ve", "/t", "REG_SZ", "/f", "/d", value],
+    )
+}
+
+fn reg(processes: &dyn ProcessRunner, args: &[&str]) -> AppResult<()> {
+    let mut cmd = Command::new("reg.exe");
+    cmd.args(args).creation_flags(CREATE_NO_WINDOW);
+    let status = processes
+        .status(&mut cmd)
+        .map_err(|err| AppError::Other(format!("Failed to run reg.exe: {}", err)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::Other(format!("reg.exe exited with {}", status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::FakeRunner;
+
+    #[test]
+    fn associate_points_the_extensions_at_exe() {
+        let processes = FakeRunner::default();
+        let exe = Path::new(r"C:\CARTA\carta.exe");
+        associate(&processes, exe, &["fits"]).unwrap();
+        assert_eq!(
+            processes.commands(),
+            [
+                r"reg.exe add 'HKCU\Software\Classes\CARTA.Image' /ve /t REG_SZ /f /d 'Astronomical image'",
+                r#"reg.exe add 'HKCU\Software\Classes\CARTA.Image\DefaultIcon' /ve /t REG_SZ /f /d '"C:\CARTA\carta.exe",0'"#,
+                r#"reg.exe add 'HKCU\Software\Classes\CARTA.Image\shell\open\command' /ve /t REG_SZ /f /d '"C:\CARTA\carta.exe" "%1"'"#,
+                r"reg.exe add 'HKCU\Software\Classes\.fits' /ve /t REG_SZ /f /d CARTA.Image",
+                r"reg.exe add 'HKCU\Software\Classes\.fits\OpenWithProgids' /v CARTA.Image /t REG_NONE /f",
+                r"reg.exe delete 'HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\FileExts\.fits\UserChoice' /f",
+            ]
+        );
+    }
+
+    #[test]
+    fn associate_stops_at_a_failed_write_but_not_a_missing_choice() {
+        let processes = FakeRunner::with_results(&[(1, "")]);
+        assert!(associate(&processes, Path::new("carta.exe"), &["fits"]).is_err());
+        assert_eq!(processes.commands().len(), 1);
+
+        let processes =
+            FakeRunner::with_results(&[(0, ""), (0, ""), (0, ""), (0, ""), (0, ""), (1, "")]);
+        assert!(associate(&processes, Path::new("carta.exe"), &["fits"]).is_ok());
+    }
+}
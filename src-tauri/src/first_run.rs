@@ -0,0 +1,325 @@
+use std::path::PathBuf;
+#[cfg(target_os = "linux")]
+use std::{path::Path, process::Command};
+
+use tauri::{AppHandle, Manager};
+#[cfg(target_os = "windows")]
+use tauri_plugin_dialog::MessageDialogResult;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{
+    AppState, home_dir, is_kiosk, resolve_backend_binary, resolve_resource_dir, update_settings,
+};
+
+const TITLE: &str = "CARTA Setup";
+#[cfg(target_os = "linux")]
+const DESKTOP_FILE: &str = "carta-appimage.desktop";
+/// As in the packages' desktop entry template.
+#[cfg(target_os = "linux")]
+const IMAGE_MIME_TYPES: &[&str] = &["application/fits", "image/fits", "application/x-hdf5"];
+
+/// One prerequisite and what was found for it.
+struct Check {
+    name: &'static str,
+    ok: bool,
+    detail: String,
+}
+
+/// Shows the setup wizard once, after the first session has started.
+pub(crate) fn start_if_needed(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if state.settings.lock().unwrap().setup_complete || is_kiosk(&state) {
+        return;
+    }
+    start(app);
+}
+
+/// Walks through the prerequisites and first-run choices with native dialogs,
+/// on a background thread since each dialog waits for an answer. Choices
+/// apply from the next launch.
+pub(crate) fn start(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        run(&app);
+        update_settings(&app, |settings| settings.setup_complete = true);
+    });
+}
+
+fn run(app: &AppHandle) {
+    let checks = check_prerequisites(app);
+    let all_ok = checks.iter().all(|check| check.ok);
+    let summary = checks
+        .iter()
+        .map(|check| {
+            format!(
+                "{} {}: {}",
+                if check.ok { "✓" } else { "✗" },
+                check.name,
+                check.detail
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let proceed = app
+        .dialog()
+        .message(format!(
+            "Welcome to CARTA. A few questions will set it up for this computer.\n\n{}",
+            summary
+        ))
+        .title(TITLE)
+        .kind(if all_ok {
+            MessageDialogKind::Info
+        } else {
+            MessageDialogKind::Warning
+        })
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Continue".to_string(),
+            "Skip Setup".to_string(),
+        ))
+        .blocking_show();
+    if !proceed {
+        return;
+    }
+
+    choose_data_folder(app);
+    #[cfg(target_os = "windows")]
+    choose_wsl_distro(app);
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    offer_file_associations(app);
+
+    app.dialog()
+        .message("Setup is complete. Your choices apply the next time CARTA starts.")
+        .title(TITLE)
+        .kind(MessageDialogKind::Info)
+        .blocking_show();
+}
+
+fn check_prerequisites(app: &AppHandle) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    checks.push(
+        match crate::wsl_distros(&*app.state::<AppState>().processes) {
+            Ok(distros) if !distros.is_empty() => Check {
+                name: "WSL",
+                ok: true,
+                detail: distros.join(", "),
+            },
+            Ok(_) => Check {
+                name: "WSL",
+                ok: false,
+                detail: "no Linux distribution installed; run `wsl --install`".to_string(),
+            },
+            Err(err) => Check {
+                name: "WSL",
+                ok: false,
+                detail: err.to_string(),
+            },
+        },
+    );
+
+    checks.push(match tauri::webview_version() {
+        Ok(version) => Check {
+            name: "Web view",
+            ok: true,
+            detail: version,
+        },
+        Err(err) => Check {
+            name: "Web view",
+            ok: false,
+            detail: err.to_string(),
+        },
+    });
+
+    let backend = resolve_resource_dir(app)
+        .ok_or(crate::AppError::ResourceDirNotFound)
+        .and_then(|dir| resolve_backend_binary(&dir, crate::backend_choice(app)));
+    checks.push(match backend {
+        Ok((path, bundled)) => Check {
+            name: "Backend",
+            ok: true,
+            detail: if bundled {
+                "bundled".to_string()
+            } else {
+                path.display().to_string()
+            },
+        },
+        Err(err) => Check {
+            name: "Backend",
+            ok: false,
+            detail: err.to_string(),
+        },
+    });
+
+    checks
+}
+
+fn choose_data_folder(app: &AppHandle) {
+    let current = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .default_data_dir
+        .clone()
+        .or_else(home_dir);
+    let current = current
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_else(|| "your home folder".to_string());
+    let choose = app
+        .dialog()
+        .message(format!(
+            "Which folder should CARTA show when it is opened from the desktop \
+             rather than with a file?\n\nCurrently: {}",
+            current
+        ))
+        .title(TITLE)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Choose Folder…".to_string(),
+            "Keep".to_string(),
+        ))
+        .blocking_show();
+    if choose {
+        pick_data_folder(app);
+    }
+}
+
+/// Asks for the `default_data_dir` and saves it; `None` when cancelled.
+pub(crate) fn pick_data_folder(app: &AppHandle) -> Option<PathBuf> {
+    let folder = app
+        .dialog()
+        .file()
+        .set_title("Default Data Folder")
+        .blocking_pick_folder()?
+        .into_path()
+        .ok()?;
+    update_settings(app, |settings| {
+        settings.default_data_dir = Some(folder.clone())
+    });
+    Some(folder)
+}
+
+/// Offers each installed distribution in turn; the dialogs have at most three
+/// buttons, so there is no single list to pick from.
+#[cfg(target_os = "windows")]
+fn choose_wsl_distro(app: &AppHandle) {
+    let Ok(distros) = crate::wsl_distros(&*app.state::<AppState>().processes) else {
+        return;
+    };
+    if distros.len() < 2 {
+        return;
+    }
+    for distro in &distros {
+        let use_label = format!("Use {}", distro);
+        let result = app
+            .dialog()
+            .message(format!(
+                "Several WSL distributions are installed. The backend needs one with \
+                 CARTA's dependencies.\n\nUse {} for CARTA?",
+                distro
+            ))
+            .title(TITLE)
+            .buttons(MessageDialogButtons::YesNoCancelCustom(
+                use_label.clone(),
+                "Next".to_string(),
+                "Use Default".to_string(),
+            ))
+            .blocking_show_with_result();
+        // Custom labels come back as `Custom` on some platforms and as
+        // `Yes`/`No` on others.
+        let choice = match result {
+            MessageDialogResult::Yes => use_label.clone(),
+            MessageDialogResult::No => "Next".to_string(),
+            MessageDialogResult::Custom(label) => label,
+            _ => String::new(),
+        };
+        if choice == "Next" {
+            continue;
+        }
+        let distro = (choice == use_label).then(|| distro.clone());
+        crate::set_wsl_distro(distro.clone());
+        update_settings(app, |settings| settings.wsl_distro = distro);
+        return;
+    }
+}
+
+/// Packages register their file types on install; an AppImage has to do it
+/// from inside, and on Windows another app or an older install may have
+/// taken them over since.
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn offer_file_associations(app: &AppHandle) {
+    #[cfg(target_os = "linux")]
+    let Some(appimage) = std::env::var_os("APPIMAGE") else {
+        return;
+    };
+    #[cfg(target_os = "windows")]
+    if crate::file_associations::unowned_extensions().is_empty() {
+        return;
+    }
+    let register = app
+        .dialog()
+        .message("Open FITS and HDF5 images with CARTA from your file manager?")
+        .title(TITLE)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Register".to_string(),
+            "Not Now".to_string(),
+        ))
+        .blocking_show();
+    if !register {
+        return;
+    }
+    #[cfg(target_os = "linux")]
+    let result = register_appimage_file_types(Path::new(&appimage));
+    #[cfg(target_os = "windows")]
+    let result = crate::file_associations::register(&*app.state::<crate::AppState>().processes);
+    if let Err(err) = result {
+        eprintln!("Warning: failed to register file types: {}", err);
+        app.dialog()
+            .message(format!("Could not register file types: {}", err))
+            .title(TITLE)
+            .kind(MessageDialogKind::Warning)
+            .blocking_show();
+    }
+}
+
+/// Installs a desktop entry for the AppImage and makes it the default for the
+/// image types. Folders are listed so CARTA shows up under "Open With" for
+/// them, but it is not made their default.
+#[cfg(target_os = "linux")]
+fn register_appimage_file_types(appimage: &Path) -> crate::AppResult<()> {
+    let applications = std::env::var_os("XDG_DATA_HOME")
+        .map(std::path::PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(".local").join("share")))
+        .ok_or_else(|| crate::AppError::from("HOME directory not found"))?
+        .join("applications");
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=CARTA\n\
+         Comment=Cube Analysis and Rendering Tool for Astronomy\n\
+         Categories=Science;Astronomy;\n\
+         Terminal=false\n\
+         Exec=\"{appimage}\" %F\n\
+         MimeType={mime_types};inode/directory;\n\
+         Actions=new-window;\n\
+         \n\
+         [Desktop Action new-window]\n\
+         Name=New Window\n\
+         Exec=\"{appimage}\" --new-window\n",
+        appimage = appimage.display(),
+        mime_types = IMAGE_MIME_TYPES.join(";")
+    );
+    crate::write_file_atomic(&applications.join(DESKTOP_FILE), entry.as_bytes())?;
+    // Both are optional desktop utilities; without them the entry still
+    // appears under "Open With".
+    let _ = Command::new("update-desktop-database")
+        .arg(&applications)
+        .status();
+    let _ = Command::new("xdg-mime")
+        .arg("default")
+        .arg(DESKTOP_FILE)
+        .args(IMAGE_MIME_TYPES)
+        .status();
+    Ok(())
+}
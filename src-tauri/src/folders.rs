@@ -0,0 +1,449 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    process::Child,
+    sync::{Arc, Mutex},
+};
+
+use tauri::{AppHandle, Manager, WebviewWindow};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use tokio::sync::oneshot;
+
+use crate::{
+    AppError, AppResult, AppState, backend_window_url, build_window_url, create_window,
+    frontend_protocol, is_kiosk, is_path_within_top_level, is_read_only, is_session_window,
+    new_window_label, pick_backend_port, relative_backend_path, spawn_backend_process,
+    supervisor::BackendLog, top_level_path, wait_for_port,
+};
+
+/// An extra backend started for "New Window with Folder…", rooted at a folder
+/// other than the launch directory. It stops once none of its windows is
+/// left.
+pub(crate) struct FolderBackend {
+    base_dir: PathBuf,
+    /// The launch backend's, unless `base_dir` is outside it; then `base_dir`.
+    top_level: PathBuf,
+    port: u16,
+    token: String,
+    /// Whether it was started with `--read_only_mode`.
+    read_only: bool,
+    process: Mutex<Option<Child>>,
+    log: Arc<BackendLog>,
+}
+
+impl FolderBackend {
+    fn window_url(&self, frontend_from_app: bool) -> String {
+        backend_window_url(frontend_from_app, self.port, Some(&self.token))
+    }
+
+    fn is_running(&self) -> bool {
+        self.process
+            .lock()
+            .unwrap()
+            .as_mut()
+            .is_some_and(|child| matches!(child.try_wait(), Ok(None)))
+    }
+
+    fn shutdown(&self) {
+        if let Some(mut child) = self.process.lock().unwrap().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct FolderBackends(Mutex<Vec<Arc<FolderBackend>>>);
+
+impl FolderBackends {
+    /// The folder served by the running backend on `port`.
+    pub(crate) fn base_dir_for_port(&self, port: u16) -> Option<PathBuf> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|backend| backend.port == port)
+            .map(|backend| backend.base_dir.clone())
+    }
+
+    fn top_level_for_port(&self, port: u16) -> Option<PathBuf> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|backend| backend.port == port)
+            .map(|backend| backend.top_level.clone())
+    }
+
+    fn log_for_port(&self, port: u16) -> Option<Arc<BackendLog>> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|backend| backend.port == port)
+            .map(|backend| backend.log.clone())
+    }
+
+    pub(crate) fn ports(&self) -> Vec<u16> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|backend| backend.port)
+            .collect()
+    }
+
+    /// Stops the backends no session window is on any more, not counting
+    /// `closed`, which is going away.
+    pub(crate) fn stop_unused(&self, app: &AppHandle, closed: &str) {
+        let in_use: HashSet<u16> = app
+            .webview_windows()
+            .into_iter()
+            .filter(|(label, _)| label != closed && is_session_window(label))
+            .filter_map(|(_, window)| frontend_protocol::backend_port(&window.url().ok()?))
+            .collect();
+        let unused: Vec<Arc<FolderBackend>> = {
+            let mut backends = self.0.lock().unwrap();
+            let (unused, kept) = backends
+                .drain(..)
+                .partition(|backend| !in_use.contains(&backend.port));
+            *backends = kept;
+            unused
+        };
+        for backend in unused {
+            backend.shutdown();
+        }
+    }
+
+    pub(crate) fn shutdown_all(&self) {
+        for backend in self.0.lock().unwrap().drain(..) {
+            backend.shutdown();
+        }
+    }
+}
+
+/// Asks for a folder, then opens a window against a backend rooted there.
+pub(crate) fn new_window_with_folder(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    // Controller sessions pick their folders in the controller.
+    if is_kiosk(&state) || state.controller.is_some() {
+        return;
+    }
+    let app = app.clone();
+    app.dialog()
+        .file()
+        .set_title("New Window with Folder")
+        .set_directory(&state.base_dir)
+        .pick_folder(move |folder| {
+            let Some(folder) = folder.and_then(|folder| folder.into_path().ok()) else {
+                return;
+            };
+            // Starting a backend can take a while; keep it off the UI thread.
+            let handle = app.clone();
+            app.state::<AppState>().tasks.spawn(async move {
+                let app = handle;
+                if let Err(err) = open_folder_window(&app, &folder).await {
+                    eprintln!("Warning: {}", err);
+                    app.dialog()
+                        .message(err.to_string())
+                        .title("CARTA")
+                        .kind(MessageDialogKind::Error)
+                        .show(|_| {});
+                }
+            });
+        });
+}
+
+async fn open_folder_window(app: &AppHandle, folder: &Path) -> AppResult<()> {
+    let url = folder_window_url(app, folder).await?;
+    create_window(
+        app,
+        &app.state::<AppState>(),
+        new_window_label(),
+        Some(&url),
+    )
+    .map_err(|err| AppError::Other(err.to_string()))?;
+    Ok(())
+}
+
+/// The URL of a window on `folder`, starting a backend for it if needed.
+pub(crate) async fn folder_window_url(app: &AppHandle, folder: &Path) -> AppResult<String> {
+    let state = app.state::<AppState>();
+    Ok(if folder == state.base_dir {
+        state.window_url()
+    } else {
+        folder_backend(app, &state, folder.to_path_buf())
+            .await?
+            .window_url(state.frontend_from_app)
+    })
+}
+
+/// The URL of a window with `files` open, on a backend rooted at the first
+/// one's folder, for files outside the launch backend's top-level folder.
+pub(crate) async fn files_window_url(app: &AppHandle, files: &[PathBuf]) -> AppResult<String> {
+    let state = app.state::<AppState>();
+    let folder = files
+        .first()
+        .and_then(|file| file.parent())
+        .ok_or(AppError::InputNotFound)?;
+    let backend = folder_backend(app, &state, folder.to_path_buf()).await?;
+    let base_url = backend.window_url(state.frontend_from_app);
+    Ok(build_window_url(&base_url, files, &backend.top_level).unwrap_or(base_url))
+}
+
+/// A file picked in the frontend's open dialog.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PickedFile {
+    /// As the frontend loads it: under the top-level folder of the backend
+    /// that serves it.
+    path: String,
+    /// The window's backend cannot reach the file, so it opened in a new
+    /// window on a backend rooted at its folder.
+    opened_in_new_window: bool,
+}
+
+/// Asks for a file to open in `window`. One outside the top-level folder of
+/// the window's backend opens in a new window instead, on a backend rooted
+/// at the file's folder.
+pub(crate) async fn pick_file(
+    app: &AppHandle,
+    window: &WebviewWindow,
+) -> AppResult<Option<PickedFile>> {
+    let state = app.state::<AppState>();
+    let mut dialog = app.dialog().file().set_parent(window);
+    if let Some(dir) = window_folder_dir(app, window) {
+        dialog = dialog.set_directory(dir);
+    }
+    let Some(file) = dialog
+        .blocking_pick_file()
+        .and_then(|file| file.into_path().ok())
+    else {
+        return Ok(None);
+    };
+    if let Some(top_level) = window_top_level(app, window)
+        && let Some(path) = relative_backend_path(&file, &top_level)
+    {
+        return Ok(Some(PickedFile {
+            path,
+            opened_in_new_window: false,
+        }));
+    }
+
+    let folder = file.parent().ok_or(AppError::InputNotFound)?.to_path_buf();
+    let backend = folder_backend(app, &state, folder).await?;
+    let path = relative_backend_path(&file, &backend.top_level)
+        .ok_or_else(|| AppError::Other(format!("Cannot open {}", file.display())))?;
+    let base_url = backend.window_url(state.frontend_from_app);
+    let url = build_window_url(&base_url, &[file], &backend.top_level).unwrap_or(base_url);
+    create_window(app, &state, new_window_label(), Some(&url))
+        .map_err(|err| AppError::Other(err.to_string()))?;
+    Ok(Some(PickedFile {
+        path,
+        opened_in_new_window: true,
+    }))
+}
+
+/// The top-level folder of the backend `window` is on.
+pub(crate) fn window_top_level(app: &AppHandle, window: &WebviewWindow) -> Option<PathBuf> {
+    let state = app.state::<AppState>();
+    let port = frontend_protocol::backend_port(&window.url().ok()?)?;
+    if port == state.backend_port() {
+        top_level_path(&state).ok()
+    } else {
+        state.folder_backends.top_level_for_port(port)
+    }
+}
+
+/// The output of the backend `window` is on; the launch backend's for a
+/// window on none.
+pub(crate) fn window_log(app: &AppHandle, window: &WebviewWindow) -> Arc<BackendLog> {
+    let state = app.state::<AppState>();
+    window
+        .url()
+        .ok()
+        .and_then(|url| frontend_protocol::backend_port(&url))
+        .filter(|&port| port != state.backend_port())
+        .and_then(|port| state.folder_backends.log_for_port(port))
+        .unwrap_or_else(|| state.backend_log.clone())
+}
+
+fn window_folder_dir(app: &AppHandle, window: &WebviewWindow) -> Option<PathBuf> {
+    let state = app.state::<AppState>();
+    let port = frontend_protocol::backend_port(&window.url().ok()?)?;
+    if port == state.backend_port() {
+        Some(state.base_dir.clone())
+    } else {
+        state.folder_backends.base_dir_for_port(port)
+    }
+}
+
+/// Replaces every folder backend with one started with the current options,
+/// such as after read-only mode was switched, and reloads its windows there.
+/// Backends no window is on any more are only stopped.
+pub(crate) async fn restart_folder_backends(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let backends: Vec<Arc<FolderBackend>> =
+        state.folder_backends.0.lock().unwrap().drain(..).collect();
+    for backend in backends {
+        let windows: Vec<WebviewWindow> = app
+            .webview_windows()
+            .into_iter()
+            .filter(|(label, _)| is_session_window(label))
+            .map(|(_, window)| window)
+            .filter(|window| {
+                window
+                    .url()
+                    .ok()
+                    .and_then(|url| frontend_protocol::backend_port(&url))
+                    == Some(backend.port)
+            })
+            .collect();
+        backend.shutdown();
+        if windows.is_empty() {
+            continue;
+        }
+        let url = match folder_backend(app, &state, backend.base_dir.clone()).await {
+            Ok(restarted) => restarted.window_url(state.frontend_from_app),
+            Err(err) => {
+                eprintln!(
+                    "Warning: failed to restart the backend for {}: {}",
+                    backend.base_dir.display(),
+                    err
+                );
+                continue;
+            }
+        };
+        let Ok(url) = tauri::Url::parse(&url) else {
+            continue;
+        };
+        for window in windows {
+            let _ = window.navigate(url.clone());
+        }
+    }
+}
+
+/// Reuses a running backend for `base_dir` in the current read-only mode, or
+/// starts a new one.
+async fn folder_backend(
+    app: &AppHandle,
+    state: &AppState,
+    base_dir: PathBuf,
+) -> AppResult<Arc<FolderBackend>> {
+    {
+        let mut backends = state.folder_backends.0.lock().unwrap();
+        backends.retain(|backend| backend.is_running());
+        let read_only = is_read_only(state);
+        if let Some(backend) = backends
+            .iter()
+            .find(|backend| backend.base_dir == base_dir && backend.read_only == read_only)
+        {
+            return Ok(backend.clone());
+        }
+    }
+
+    // A folder outside the launch backend's top-level folder gets its own.
+    let top_level = top_level_path(state)?;
+    let top_level = if is_path_within_top_level(&base_dir, &top_level) {
+        top_level
+    } else {
+        base_dir.clone()
+    };
+    let port = pick_backend_port(&*state.processes).ok_or(AppError::NoFreePort)?;
+    let token = uuid::Uuid::new_v4().to_string();
+    let log = Arc::new(BackendLog::default());
+    let read_only = is_read_only(state);
+    let child = spawn_on_main_thread(
+        app,
+        log.clone(),
+        port,
+        token.clone(),
+        base_dir.clone(),
+        top_level.clone(),
+    )
+    .await?;
+    let backend = Arc::new(FolderBackend {
+        base_dir,
+        top_level,
+        port,
+        token,
+        read_only,
+        process: Mutex::new(Some(child)),
+        log,
+    });
+    if let Err(err) = wait_for_port(&backend.process, port, state.backend_timeout).await {
+        backend.shutdown();
+        return Err(err);
+    }
+    state
+        .folder_backends
+        .0
+        .lock()
+        .unwrap()
+        .push(backend.clone());
+    Ok(backend)
+}
+
+/// Spawns from the main thread: on Linux the backend's parent-death signal
+/// fires when the spawning thread exits, so it must outlive the backend.
+async fn spawn_on_main_thread(
+    app: &AppHandle,
+    log: Arc<BackendLog>,
+    port: u16,
+    token: String,
+    base_dir: PathBuf,
+    top_level: PathBuf,
+) -> AppResult<Child> {
+    let (tx, rx) = oneshot::channel();
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        let state = handle.state::<AppState>();
+        let backend_args = with_top_level(&state.backend_args.lock().unwrap(), &top_level);
+        let _ = tx.send(spawn_backend_process(
+            &handle,
+            &state,
+            log,
+            port,
+            &token,
+            &base_dir,
+            &backend_args,
+        ));
+    })
+    .map_err(|err| AppError::Other(err.to_string()))?;
+    rx.await
+        .map_err(|_| AppError::from("Backend spawn was cancelled."))?
+}
+
+/// `args` with the top-level folder set to `top_level`.
+fn with_top_level(args: &[String], top_level: &Path) -> Vec<String> {
+    let mut result = Vec::with_capacity(args.len() + 1);
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--top_level_folder" {
+            iter.next();
+        } else if !arg.starts_with("--top_level_folder=") {
+            result.push(arg.clone());
+        }
+    }
+    result.push(format!("--top_level_folder={}", top_level.display()));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_top_level_replaces_the_folder() {
+        let args = [
+            "--verbosity=4".to_string(),
+            "--top_level_folder".to_string(),
+            "/data".to_string(),
+            "--top_level_folder=/old".to_string(),
+        ];
+        assert_eq!(
+            with_top_level(&args, Path::new("/scratch/run 2")),
+            ["--verbosity=4", "--top_level_folder=/scratch/run 2"]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use tauri::{
+    AppHandle, Url,
+    http::{Request, Response, StatusCode, header::CONTENT_TYPE},
+};
+
+use crate::{backend_host, custom_scheme_origin, resolve_frontend_path, resolve_resource_dir};
+
+/// Serves the bundled frontend to the webviews, so backends started with
+/// `--no_frontend` only answer its WebSocket.
+pub(crate) const SCHEME: &str = "carta-frontend";
+/// The frontend's own parameter for a backend other than the page's origin.
+pub(crate) const SOCKET_URL_PARAM: &str = "socketUrl";
+const INDEX_FILE: &str = "index.html";
+
+/// The first page of a window on the backend at `port`.
+pub(crate) fn window_url(port: u16, token: Option<&str>) -> String {
+    let mut url = Url::parse(&custom_scheme_origin(SCHEME)).expect("valid frontend origin");
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair(
+            SOCKET_URL_PARAM,
+            &format!("ws://{}:{}", backend_host(), port),
+        );
+        if let Some(token) = token {
+            query.append_pair("token", token);
+        }
+    }
+    url.to_string()
+}
+
+/// The backend a window talks to, whichever of them served its page.
+pub(crate) fn backend_port(url: &Url) -> Option<u16> {
+    match url.query_pairs().find(|(name, _)| name == SOCKET_URL_PARAM) {
+        Some((_, socket_url)) => Url::parse(&socket_url).ok()?.port(),
+        None => url.port(),
+    }
+}
+
+/// `url` pointed at the backend on `port` instead, in whichever way
+/// `backend_port` found its backend.
+pub(crate) fn with_backend_port(url: &Url, port: u16) -> Url {
+    let mut url = url.clone();
+    if backend_port(&url).is_none() {
+        return url;
+    }
+    let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    if pairs.iter().any(|(name, _)| name == SOCKET_URL_PARAM) {
+        let mut query = url.query_pairs_mut();
+        query.clear();
+        for (name, value) in &pairs {
+            if name == SOCKET_URL_PARAM {
+                query.append_pair(name, &format!("ws://{}:{}", backend_host(), port));
+            } else {
+                query.append_pair(name, value);
+            }
+        }
+    } else {
+        let _ = url.set_port(Some(port));
+    }
+    url
+}
+
+pub(crate) fn handle(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Cow<'static, [u8]>> {
+    let frontend_dir = resolve_resource_dir(app)
+        .and_then(|resource_dir| resolve_frontend_path(&resource_dir).ok());
+    let contents = frontend_dir.and_then(|dir| {
+        let path = file_path(&dir, request.uri().path())?;
+        fs::read(&path).ok().map(|contents| (path, contents))
+    });
+    match contents {
+        Some((path, contents)) => Response::builder()
+            .header(CONTENT_TYPE, content_type(&path))
+            .body(Cow::Owned(contents)),
+        None => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Cow::Borrowed(&[][..])),
+    }
+    .unwrap_or_else(|_| Response::new(Cow::Borrowed(&[][..])))
+}
+
+/// The file under `frontend_dir` for a request path; `None` for paths that
+/// would leave it.
+fn file_path(frontend_dir: &Path, request_path: &str) -> Option<PathBuf> {
+    let relative = request_path.trim_start_matches('/');
+    let relative = if relative.is_empty() {
+        INDEX_FILE
+    } else {
+        relative
+    };
+    let mut path = frontend_dir.to_path_buf();
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript",
+        Some("css") => "text/css",
+        Some("json" | "map") => "application/json",
+        Some("wasm") => "application/wasm",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_url_points_the_socket_at_the_backend() {
+        let url = Url::parse(&window_url(3002, Some("abc"))).unwrap();
+        assert_eq!(
+            url.query(),
+            Some("socketUrl=ws%3A%2F%2Flocalhost%3A3002&token=abc")
+        );
+        assert_eq!(backend_port(&url), Some(3002));
+        let url = Url::parse(&window_url(4000, None)).unwrap();
+        assert_eq!(url.query(), Some("socketUrl=ws%3A%2F%2Flocalhost%3A4000"));
+        assert_eq!(backend_port(&url), Some(4000));
+
+        let served = Url::parse("http://localhost:3002/?token=abc").unwrap();
+        assert_eq!(backend_port(&served), Some(3002));
+    }
+
+    #[test]
+    fn with_backend_port_moves_either_kind_of_url() {
+        let url = Url::parse(&window_url(3002, Some("abc"))).unwrap();
+        assert_eq!(
+            with_backend_port(&url, 3005).as_str(),
+            window_url(3005, Some("abc"))
+        );
+        let served = Url::parse("http://localhost:3002/?token=abc&file=m51.fits").unwrap();
+        assert_eq!(
+            with_backend_port(&served, 3005).as_str(),
+            "http://localhost:3005/?token=abc&file=m51.fits"
+        );
+        let page = Url::parse("about:blank").unwrap();
+        assert_eq!(with_backend_port(&page, 3005), page);
+    }
+
+    #[test]
+    fn file_path_stays_in_frontend_folder() {
+        let dir = Path::new("/bundle/frontend");
+        assert_eq!(file_path(dir, "/"), Some(dir.join("index.html")));
+        assert_eq!(
+            file_path(dir, "/static/js/main.js"),
+            Some(dir.join("static").join("js").join("main.js"))
+        );
+        assert_eq!(file_path(dir, "/../settings.json"), None);
+        assert_eq!(file_path(dir, "/static/../../secret"), None);
+    }
+
+    #[test]
+    fn content_type_follows_extension() {
+        assert_eq!(
+            content_type(Path::new("index.html")),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(
+            content_type(Path::new("carta_computation.WASM")),
+            "application/wasm"
+        );
+        assert_eq!(
+            content_type(Path::new("LICENSE")),
+            "application/octet-stream"
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::str::FromStr;
+
+use tauri::{AppHandle, Manager, Wry, plugin::TauriPlugin};
+use tauri_plugin_global_shortcut::{Builder, GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::{AppState, open_new_window, update_settings};
+
+/// Opens a window for the `new_window_shortcut` setting, even while another
+/// app has the focus.
+pub(crate) fn plugin() -> TauriPlugin<Wry> {
+    Builder::new()
+        .with_handler(|app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                open_new_window(app, &app.state::<AppState>());
+            }
+        })
+        .build()
+}
+
+/// Registers the setting's shortcut, if any, in place of the one before. One
+/// another app holds only gets a warning.
+pub(crate) fn register_from_settings(app: &AppHandle) {
+    let _ = app.global_shortcut().unregister_all();
+    let shortcut = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .new_window_shortcut
+        .clone();
+    if let Some(shortcut) = shortcut
+        && let Err(err) = register(app, &shortcut)
+    {
+        eprintln!("Warning: {}", err);
+    }
+}
+
+/// Replaces the registered shortcut with `shortcut`, e.g. `CmdOrCtrl+Alt+N`,
+/// or removes it. The setting only changes when the shortcut could be
+/// registered.
+pub(crate) fn set(app: &AppHandle, shortcut: Option<String>) -> Result<(), String> {
+    if let Some(shortcut) = &shortcut {
+        Shortcut::from_str(shortcut)
+            .map_err(|err| format!("Invalid shortcut {}: {}", shortcut, err))?;
+    }
+    let _ = app.global_shortcut().unregister_all();
+    if let Some(shortcut) = &shortcut
+        && let Err(err) = register(app, shortcut)
+    {
+        register_from_settings(app);
+        return Err(err);
+    }
+    update_settings(app, |settings| settings.new_window_shortcut = shortcut);
+    Ok(())
+}
+
+fn register(app: &AppHandle, shortcut: &str) -> Result<(), String> {
+    app.global_shortcut()
+        .register(shortcut)
+        .map_err(|err| format!("Failed to register the shortcut {}: {}", shortcut, err))
+}
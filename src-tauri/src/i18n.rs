@@ -0,0 +1,235 @@
+/// Languages with translations for native UI strings (menus, dialogs).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Language {
+    English,
+    TraditionalChinese,
+    SimplifiedChinese,
+    Japanese,
+}
+
+impl Language {
+    /// Parses a BCP 47 / POSIX style locale tag such as `en-US`, `zh_TW`,
+    /// `zh-Hant-HK` or `ja_JP.UTF-8`.
+    pub(crate) fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.split('.').next().unwrap_or(tag).replace('_', "-");
+        let mut parts = tag.split('-').map(str::to_ascii_lowercase);
+        let language = parts.next()?;
+        let rest: Vec<String> = parts.collect();
+        let has = |part: &str| rest.iter().any(|p| p == part);
+
+        match language.as_str() {
+            "en" => Some(Self::English),
+            "ja" => Some(Self::Japanese),
+            "zh" if has("hant") || has("tw") || has("hk") || has("mo") => {
+                Some(Self::TraditionalChinese)
+            }
+            "zh" => Some(Self::SimplifiedChinese),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn tag(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::TraditionalChinese => "zh-TW",
+            Self::SimplifiedChinese => "zh-CN",
+            Self::Japanese => "ja",
+        }
+    }
+
+    /// Picks the UI language: the preference override if it names a supported
+    /// language, otherwise the first supported system locale, otherwise English.
+    pub(crate) fn resolve(preference: Option<&str>) -> Self {
+        preference
+            .and_then(Self::from_tag)
+            .or_else(|| sys_locale::get_locales().find_map(|tag| Self::from_tag(&tag)))
+            .unwrap_or(Self::English)
+    }
+}
+
+// Native app-menu entries only exist on macOS; elsewhere the frontend draws the menu.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Message {
+    NewWindow,
+    NewWindowWithFolder,
+    DuplicateWindow,
+    SaveScreenshot,
+    ToggleFullscreen,
+    ToggleDevtools,
+    AlwaysOpenDevtools,
+    PreventSleep,
+    ReadOnlySession,
+    CloseWindow,
+    CloseAllWindows,
+    ShowWindows,
+    View,
+    PresentationMode,
+    ShowTabBar,
+    MergeAllWindows,
+    NextWindow,
+    PreviousWindow,
+    Help,
+    OpenLogFolder,
+    CopyAuthToken,
+    CopyBackendCommand,
+    AdvancedStart,
+    Quit,
+}
+
+pub(crate) fn tr(language: Language, message: Message) -> &'static str {
+    use Language::*;
+    use Message::*;
+
+    match (message, language) {
+        (NewWindow, English) => "New CARTA Window",
+        (NewWindow, TraditionalChinese) => "新增 CARTA 視窗",
+        (NewWindow, SimplifiedChinese) => "新建 CARTA 窗口",
+        (NewWindow, Japanese) => "新規 CARTA ウインドウ",
+
+        (NewWindowWithFolder, English) => "New Window with Folder…",
+        (NewWindowWithFolder, TraditionalChinese) => "以資料夾開啟新視窗…",
+        (NewWindowWithFolder, SimplifiedChinese) => "以文件夹新建窗口…",
+        (NewWindowWithFolder, Japanese) => "フォルダを指定して新規ウインドウ…",
+
+        (DuplicateWindow, English) => "Duplicate Window",
+        (DuplicateWindow, TraditionalChinese) => "複製視窗",
+        (DuplicateWindow, SimplifiedChinese) => "复制窗口",
+        (DuplicateWindow, Japanese) => "ウインドウを複製",
+
+        (SaveScreenshot, English) => "Save Window Screenshot…",
+        (SaveScreenshot, TraditionalChinese) => "儲存視窗截圖…",
+        (SaveScreenshot, SimplifiedChinese) => "保存窗口截图…",
+        (SaveScreenshot, Japanese) => "ウインドウのスクリーンショットを保存…",
+
+        (ToggleFullscreen, English) => "Toggle Fullscreen",
+        (ToggleFullscreen, TraditionalChinese) => "切換全螢幕",
+        (ToggleFullscreen, SimplifiedChinese) => "切换全屏",
+        (ToggleFullscreen, Japanese) => "フルスクリーン切り替え",
+
+        (ToggleDevtools, English) => "Toggle DevTools",
+        (ToggleDevtools, TraditionalChinese) => "切換開發者工具",
+        (ToggleDevtools, SimplifiedChinese) => "切换开发者工具",
+        (ToggleDevtools, Japanese) => "開発者ツール切り替え",
+
+        (AlwaysOpenDevtools, English) => "Always Open DevTools",
+        (AlwaysOpenDevtools, TraditionalChinese) => "總是開啟開發者工具",
+        (AlwaysOpenDevtools, SimplifiedChinese) => "总是打开开发者工具",
+        (AlwaysOpenDevtools, Japanese) => "常に開発者ツールを開く",
+
+        (PreventSleep, English) => "Prevent Sleep",
+        (PreventSleep, TraditionalChinese) => "防止系統睡眠",
+        (PreventSleep, SimplifiedChinese) => "防止系统睡眠",
+        (PreventSleep, Japanese) => "スリープを防止",
+
+        (ReadOnlySession, English) => "Read-Only Session",
+        (ReadOnlySession, TraditionalChinese) => "唯讀工作階段",
+        (ReadOnlySession, SimplifiedChinese) => "只读会话",
+        (ReadOnlySession, Japanese) => "読み取り専用セッション",
+
+        (CloseWindow, English) => "Close Window",
+        (CloseWindow, TraditionalChinese) => "關閉視窗",
+        (CloseWindow, SimplifiedChinese) => "关闭窗口",
+        (CloseWindow, Japanese) => "ウインドウを閉じる",
+
+        (CloseAllWindows, English) => "Close All Windows",
+        (CloseAllWindows, TraditionalChinese) => "關閉所有視窗",
+        (CloseAllWindows, SimplifiedChinese) => "关闭所有窗口",
+        (CloseAllWindows, Japanese) => "すべてのウインドウを閉じる",
+
+        (ShowWindows, English) => "Show CARTA",
+        (ShowWindows, TraditionalChinese) => "顯示 CARTA",
+        (ShowWindows, SimplifiedChinese) => "显示 CARTA",
+        (ShowWindows, Japanese) => "CARTA を表示",
+
+        (View, English) => "View",
+        (View, TraditionalChinese) => "顯示方式",
+        (View, SimplifiedChinese) => "显示",
+        (View, Japanese) => "表示",
+
+        (PresentationMode, English) => "Presentation Mode",
+        (PresentationMode, TraditionalChinese) => "簡報模式",
+        (PresentationMode, SimplifiedChinese) => "演示模式",
+        (PresentationMode, Japanese) => "プレゼンテーションモード",
+
+        (ShowTabBar, English) => "Show Tab Bar",
+        (ShowTabBar, TraditionalChinese) => "顯示標籤頁列",
+        (ShowTabBar, SimplifiedChinese) => "显示标签页栏",
+        (ShowTabBar, Japanese) => "タブバーを表示",
+
+        (MergeAllWindows, English) => "Merge All Windows",
+        (MergeAllWindows, TraditionalChinese) => "合併所有視窗",
+        (MergeAllWindows, SimplifiedChinese) => "合并所有窗口",
+        (MergeAllWindows, Japanese) => "すべてのウインドウを結合",
+
+        (NextWindow, English) => "Next Window",
+        (NextWindow, TraditionalChinese) => "下一個視窗",
+        (NextWindow, SimplifiedChinese) => "下一个窗口",
+        (NextWindow, Japanese) => "次のウインドウ",
+
+        (PreviousWindow, English) => "Previous Window",
+        (PreviousWindow, TraditionalChinese) => "上一個視窗",
+        (PreviousWindow, SimplifiedChinese) => "上一个窗口",
+        (PreviousWindow, Japanese) => "前のウインドウ",
+
+        (Help, English) => "Help",
+        (Help, TraditionalChinese) => "輔助說明",
+        (Help, SimplifiedChinese) => "帮助",
+        (Help, Japanese) => "ヘルプ",
+
+        (OpenLogFolder, English) => "Open Log Folder",
+        (OpenLogFolder, TraditionalChinese) => "開啟記錄檔資料夾",
+        (OpenLogFolder, SimplifiedChinese) => "打开日志文件夹",
+        (OpenLogFolder, Japanese) => "ログフォルダを開く",
+
+        (CopyAuthToken, English) => "Copy Auth Token",
+        (CopyAuthToken, TraditionalChinese) => "拷貝認證權杖",
+        (CopyAuthToken, SimplifiedChinese) => "复制认证令牌",
+        (CopyAuthToken, Japanese) => "認証トークンをコピー",
+
+        (CopyBackendCommand, English) => "Copy Backend Command Line",
+        (CopyBackendCommand, TraditionalChinese) => "拷貝後端命令列",
+        (CopyBackendCommand, SimplifiedChinese) => "复制后端命令行",
+        (CopyBackendCommand, Japanese) => "バックエンドのコマンドラインをコピー",
+
+        (AdvancedStart, English) => "Advanced Start…",
+        (AdvancedStart, TraditionalChinese) => "進階啟動…",
+        (AdvancedStart, SimplifiedChinese) => "高级启动…",
+        (AdvancedStart, Japanese) => "詳細な起動オプション…",
+
+        (Quit, English) => "Quit CARTA",
+        (Quit, TraditionalChinese) => "結束 CARTA",
+        (Quit, SimplifiedChinese) => "退出 CARTA",
+        (Quit, Japanese) => "CARTA を終了",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_from_tag_handles_regions_and_scripts() {
+        assert_eq!(Language::from_tag("en-US"), Some(Language::English));
+        assert_eq!(Language::from_tag("ja_JP.UTF-8"), Some(Language::Japanese));
+        assert_eq!(
+            Language::from_tag("zh_TW"),
+            Some(Language::TraditionalChinese)
+        );
+        assert_eq!(
+            Language::from_tag("zh-Hant-HK"),
+            Some(Language::TraditionalChinese)
+        );
+        assert_eq!(
+            Language::from_tag("zh-CN"),
+            Some(Language::SimplifiedChinese)
+        );
+        assert_eq!(Language::from_tag("de-DE"), None);
+    }
+
+    #[test]
+    fn language_resolve_prefers_supported_override() {
+        assert_eq!(Language::resolve(Some("ja")), Language::Japanese);
+        assert_eq!(Language::resolve(Some("zh-TW")).tag(), "zh-TW");
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, SocketAddr, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use tauri::{AppHandle, Manager};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader as AsyncBufReader},
+    net::{TcpListener, TcpStream as AsyncTcpStream},
+};
+
+use crate::{
+    AppState, build_window_url, create_window, folders, is_kiosk, new_window_label, top_level_path,
+    write_private_file_atomic,
+};
+
+/// Where the running instance says how to reach it, in the app config folder.
+const INSTANCE_FILE: &str = "instance.json";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Requests are a few paths; anything longer is not from CARTA.
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+/// A client that connects and stays silent is dropped after this long.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const REPLY_OK: &str = "ok";
+
+/// The instance file this launch wrote, and the port in it.
+static LISTENING: OnceLock<(PathBuf, u16)> = OnceLock::new();
+
+/// How to reach the running instance. The token keeps other local users, who
+/// cannot read the file, from opening windows in the session.
+#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct InstanceInfo {
+    port: u16,
+    token: String,
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct NewWindowRequest {
+    token: String,
+    /// Absolute paths; empty for a window without a file.
+    files: Vec<PathBuf>,
+    /// Without files, bring a window forward instead of opening one.
+    #[serde(default)]
+    focus: bool,
+}
+
+/// For `--new-window` and the `second_instance` setting: asks the running
+/// CARTA to open a window on `files`, or with `focus` and no files to show
+/// one it has. False when none answers, and this launch should start its own
+/// session.
+pub(crate) fn forward_new_window(config_dir: &Path, files: &[PathBuf], focus: bool) -> bool {
+    let Some(info) = fs::read(config_dir.join(INSTANCE_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_slice::<InstanceInfo>(&contents).ok())
+    else {
+        return false;
+    };
+    let request = NewWindowRequest {
+        token: info.token,
+        files: files.to_vec(),
+        focus,
+    };
+    match send(info.port, &request) {
+        Ok(true) => true,
+        Ok(false) => {
+            eprintln!("Warning: the running CARTA refused the new window");
+            false
+        }
+        // A stale file from an instance that did not quit cleanly.
+        Err(_) => false,
+    }
+}
+
+fn send(port: u16, request: &NewWindowRequest) -> std::io::Result<bool> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut line = serde_json::to_string(request).map_err(std::io::Error::other)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply.trim() == REPLY_OK)
+}
+
+/// Accepts `--new-window` requests from later launches for the rest of the
+/// session. The most recently started instance is the one they reach.
+pub(crate) async fn listen(app: AppHandle) {
+    let Ok(config_dir) = app.path().app_config_dir() else {
+        return;
+    };
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Warning: failed to listen for new windows: {}", err);
+            return;
+        }
+    };
+    let Ok(port) = listener.local_addr().map(|address| address.port()) else {
+        return;
+    };
+    let info = InstanceInfo {
+        port,
+        token: uuid::Uuid::new_v4().to_string(),
+    };
+    let path = config_dir.join(INSTANCE_FILE);
+    let result = serde_json::to_vec(&info)
+        .map_err(std::io::Error::other)
+        .and_then(|payload| write_private_file_atomic(&path, &payload));
+    if let Err(err) = result {
+        eprintln!("Warning: failed to save {}: {}", path.display(), err);
+        return;
+    }
+    let _ = LISTENING.set((path, port));
+
+    serve(listener, info.token, move |request| {
+        let handle = app.clone();
+        let _ = app.run_on_main_thread(move || {
+            if request.focus && request.files.is_empty() {
+                focus_window(&handle);
+            } else {
+                open_new_window(&handle, &request.files);
+            }
+        });
+    })
+    .await;
+}
+
+/// Reads each connection in a task of its own, so a client that never sends
+/// its request holds up no one but itself.
+async fn serve(
+    listener: TcpListener,
+    token: String,
+    handle: impl Fn(NewWindowRequest) + Send + Sync + 'static,
+) {
+    let token = Arc::new(token);
+    let handle = Arc::new(handle);
+    while let Ok((stream, _)) = listener.accept().await {
+        let (token, handle) = (token.clone(), handle.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Ok(Some(request)) =
+                tokio::time::timeout(REQUEST_TIMEOUT, read_request(stream, &token)).await
+            {
+                handle(request);
+            }
+        });
+    }
+}
+
+async fn read_request(mut stream: AsyncTcpStream, token: &str) -> Option<NewWindowRequest> {
+    let (reader, mut writer) = stream.split();
+    let mut line = String::new();
+    AsyncBufReader::new(reader.take(MAX_REQUEST_BYTES))
+        .read_line(&mut line)
+        .await
+        .ok()?;
+    let request = serde_json::from_str::<NewWindowRequest>(&line)
+        .ok()
+        .filter(|request| request.token == token);
+    let reply = if request.is_some() {
+        REPLY_OK
+    } else {
+        "denied"
+    };
+    let _ = writer.write_all(format!("{}\n", reply).as_bytes()).await;
+    request
+}
+
+/// Shows the most recently opened window, or opens one if there is none.
+fn focus_window(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let labels = state.window_order.lock().unwrap().clone();
+    let Some(window) = labels
+        .iter()
+        .rev()
+        .find_map(|label| app.get_webview_window(label))
+    else {
+        open_new_window(app, &[]);
+        return;
+    };
+    let _ = window.show();
+    let _ = window.unminimize();
+    let _ = window.set_focus();
+}
+
+/// Like the File > New Window menu item, with the files opened in it. Files
+/// outside the top-level folder open on a backend rooted at their folder,
+/// as from the frontend's open dialog.
+fn open_new_window(app: &AppHandle, files: &[PathBuf]) {
+    let state = app.state::<AppState>();
+    if is_kiosk(&state) {
+        return;
+    }
+    let url = top_level_path(&state)
+        .ok()
+        .and_then(|top_level| build_window_url(&state.window_url(), files, &top_level));
+    // Controller sessions pick their folders in the controller.
+    if url.is_none() && !files.is_empty() && state.controller.is_none() {
+        // Starting a backend can take a while; keep it off the UI thread.
+        let (handle, files) = (app.clone(), files.to_vec());
+        state.tasks.spawn(async move {
+            match folders::files_window_url(&handle, &files).await {
+                Ok(url) => show_new_window(&handle, Some(&url)),
+                Err(err) => eprintln!("Warning: failed to open a new window: {}", err),
+            }
+        });
+        return;
+    }
+    show_new_window(app, url.as_deref());
+}
+
+fn show_new_window(app: &AppHandle, url: Option<&str>) {
+    let state = app.state::<AppState>();
+    match create_window(app, &state, new_window_label(), url) {
+        Ok(window) => {
+            let _ = window.set_focus();
+        }
+        Err(err) => eprintln!("Warning: failed to open a new window: {}", err),
+    }
+}
+
+/// Removes the instance file on exit, unless a later launch has taken it over.
+pub(crate) fn release() {
+    let Some((path, port)) = LISTENING.get() else {
+        return;
+    };
+    let ours = fs::read(path)
+        .ok()
+        .and_then(|contents| serde_json::from_slice::<InstanceInfo>(&contents).ok())
+        .is_some_and(|info| info.port == *port);
+    if ours {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_window_request_reaches_listener_with_token() {
+        let listener =
+            tauri::async_runtime::block_on(TcpListener::bind((Ipv4Addr::LOCALHOST, 0))).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            tauri::async_runtime::block_on(async {
+                let mut received = Vec::new();
+                for _ in 0..2 {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    received.push(read_request(stream, "secret").await.map(|r| r.files));
+                }
+                received
+            })
+        });
+
+        let files = vec![PathBuf::from("/data/m51.fits")];
+        let request = |token: &str| NewWindowRequest {
+            token: token.to_string(),
+            files: files.clone(),
+            focus: false,
+        };
+        assert!(send(port, &request("secret")).unwrap());
+        assert!(!send(port, &request("guess")).unwrap());
+        assert_eq!(server.join().unwrap(), [Some(files.clone()), None]);
+    }
+
+    #[test]
+    fn idle_connection_does_not_block_later_requests() {
+        let listener =
+            tauri::async_runtime::block_on(TcpListener::bind((Ipv4Addr::LOCALHOST, 0))).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = std::sync::mpsc::channel();
+        tauri::async_runtime::spawn(serve(listener, "secret".to_string(), move |request| {
+            let _ = tx.send(request.files);
+        }));
+
+        // Connects and never sends anything, like a crashed second instance.
+        let _idle = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        let files = vec![PathBuf::from("/data/m51.fits")];
+        let request = NewWindowRequest {
+            token: "secret".to_string(),
+            files: files.clone(),
+            focus: false,
+        };
+        assert!(send(port, &request).unwrap());
+        assert_eq!(rx.recv_timeout(CONNECT_TIMEOUT).unwrap(), files);
+    }
+
+    #[test]
+    fn forward_new_window_needs_a_running_instance() {
+        let dir = std::env::temp_dir().join(format!("carta-instance-{}", uuid::Uuid::new_v4()));
+        assert!(!forward_new_window(&dir, &[], false));
+
+        // Nothing listens on the port of a crashed instance.
+        let port = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let info = InstanceInfo {
+            port,
+            token: "secret".to_string(),
+        };
+        write_private_file_atomic(
+            &dir.join(INSTANCE_FILE),
+            &serde_json::to_vec(&info).unwrap(),
+        )
+        .unwrap();
+        assert!(!forward_new_window(&dir, &[], false));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+use windows::{
+    Win32::{
+        Storage::EnhancedStorage::PKEY_Title,
+        System::Com::{
+            CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED, CoCreateInstance, CoInitializeEx,
+            CoUninitialize, StructuredStorage::PROPVARIANT,
+        },
+        UI::Shell::{
+            Common::{IObjectArray, IObjectCollection},
+            DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW,
+            PropertiesSystem::IPropertyStore,
+            ShellLink,
+        },
+    },
+    core::{HSTRING, Interface},
+};
+
+use crate::{AppError, AppResult, AppState};
+
+const RECENT_CATEGORY: &str = "Recent";
+const NEW_WINDOW_ARG: &str = "--new-window";
+
+/// Fills the taskbar Jump List with a "New Window" task and the recently
+/// opened images. Both open a window in the running CARTA, or start it.
+pub(crate) fn update(app: &AppHandle) {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(err) => {
+            eprintln!("Warning: failed to update the Jump List: {}", err);
+            return;
+        }
+    };
+    let recent_files = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .recent_files
+        .clone();
+    // COM wants a thread of its own here; the list is small, so this is quick.
+    std::thread::spawn(move || {
+        if let Err(err) = build(&exe, &recent_files) {
+            eprintln!("Warning: failed to update the Jump List: {}", err);
+        }
+    });
+}
+
+fn build(exe: &Path, recent_files: &[PathBuf]) -> AppResult<()> {
+    // SAFETY: initializes COM for this thread only, which the thread owns.
+    unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) }
+        .ok()
+        .map_err(com_error)?;
+    let result = build_list(exe, recent_files);
+    // SAFETY: balances the successful `CoInitializeEx` above, once every COM
+    // object made on this thread has been released.
+    unsafe { CoUninitialize() };
+    result
+}
+
+fn build_list(exe: &Path, recent_files: &[PathBuf]) -> AppResult<()> {
+    // SAFETY: COM calls on objects created here, with COM initialized on this
+    // thread by `build`.
+    unsafe {
+        let list: ICustomDestinationList =
+            CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER).map_err(com_error)?;
+        let mut min_slots = 0;
+        // Items the user removed from the list must not be added back.
+        let removed: IObjectArray = list.BeginList(&mut min_slots).map_err(com_error)?;
+        let removed_paths = removed_arguments(&removed);
+
+        let files: IObjectCollection =
+            CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)
+                .map_err(com_error)?;
+        for file in recent_files
+            .iter()
+            .filter(|file| file.exists())
+            .take(min_slots as usize)
+        {
+            let arguments = format!("{} {}", NEW_WINDOW_ARG, quote_argument(file));
+            if removed_paths.contains(&arguments) {
+                continue;
+            }
+            let title = file
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| file.display().to_string());
+            let link = shell_link(exe, &arguments, &title, &file.display().to_string())?;
+            files.AddObject(&link).map_err(com_error)?;
+        }
+        let files: IObjectArray = files.cast().map_err(com_error)?;
+        if files.GetCount().map_err(com_error)? > 0 {
+            list.AppendCategory(&HSTRING::from(RECENT_CATEGORY), &files)
+                .map_err(com_error)?;
+        }
+
+        let tasks: IObjectCollection =
+            CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)
+                .map_err(com_error)?;
+        let new_window = shell_link(exe, NEW_WINDOW_ARG, "New Window", "Open a new CARTA window")?;
+        tasks.AddObject(&new_window).map_err(com_error)?;
+        let tasks: IObjectArray = tasks.cast().map_err(com_error)?;
+        list.AddUserTasks(&tasks).map_err(com_error)?;
+
+        list.CommitList().map_err(com_error)
+    }
+}
+
+/// A link to CARTA with `arguments`, shown as `title`.
+///
+/// # Safety
+///
+/// COM must be initialized on the calling thread.
+unsafe fn shell_link(
+    exe: &Path,
+    arguments: &str,
+    title: &str,
+    description: &str,
+) -> AppResult<IShellLinkW> {
+    // SAFETY: COM is initialized, as the caller guarantees.
+    unsafe {
+        let link: IShellLinkW =
+            CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER).map_err(com_error)?;
+        let exe = HSTRING::from(exe);
+        link.SetPath(&exe).map_err(com_error)?;
+        link.SetArguments(&HSTRING::from(arguments))
+            .map_err(com_error)?;
+        link.SetIconLocation(&exe, 0).map_err(com_error)?;
+        link.SetDescription(&HSTRING::from(description))
+            .map_err(com_error)?;
+        let properties: IPropertyStore = link.cast().map_err(com_error)?;
+        properties
+            .SetValue(&PKEY_Title, &PROPVARIANT::from(title))
+            .map_err(com_error)?;
+        properties.Commit().map_err(com_error)?;
+        Ok(link)
+    }
+}
+
+/// The arguments of the links the user removed, which identify their files.
+///
+/// # Safety
+///
+/// COM must be initialized on the calling thread.
+unsafe fn removed_arguments(removed: &IObjectArray) -> Vec<String> {
+    // SAFETY: COM is initialized, as the caller guarantees.
+    unsafe {
+        let count = removed.GetCount().unwrap_or(0);
+        (0..count)
+            .filter_map(|index| removed.GetAt::<IShellLinkW>(index).ok())
+            .filter_map(|link| {
+                let mut buffer = [0u16; 1024];
+                link.GetArguments(&mut buffer).ok()?;
+                let len = buffer
+                    .iter()
+                    .position(|&ch| ch == 0)
+                    .unwrap_or(buffer.len());
+                Some(String::from_utf16_lossy(&buffer[..len]))
+            })
+            .collect()
+    }
+}
+
+/// One command-line argument. A trailing separator would escape the closing
+/// quote, and folders open the same without it.
+fn quote_argument(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    format!("\"{}\"", path.trim_end_matches(['\\', '/']))
+}
+
+fn com_error(err: windows::core::Error) -> AppError {
+    AppError::Other(err.to_string())
+}
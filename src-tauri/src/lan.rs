@@ -0,0 +1,158 @@
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use qrcode::{QrCode, render::svg};
+use tauri::{AppHandle, Manager, Url, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::{AppError, AppResult, AppState};
+
+pub(crate) const SHARE_WINDOW_LABEL: &str = "lan-share";
+/// Makes the backend accept connections from other machines.
+pub(crate) const BIND_ALL_ARG: &str = "--host=0.0.0.0";
+const SHARE_WINDOW_TITLE: &str = "Share on Local Network";
+const QR_SIZE: u32 = 240;
+
+/// Shows the session's LAN URL as text and a QR code, with a warning about who
+/// can use it. Focuses the window if it is already open.
+pub(crate) fn show_share_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(SHARE_WINDOW_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+    let state = app.state::<AppState>();
+    let Some(ip) = lan_address() else {
+        app.dialog()
+            .message(
+                "CARTA is shared on the local network, but no network address was found. \
+                 Check that this computer is connected to a network.",
+            )
+            .title("CARTA")
+            .kind(MessageDialogKind::Warning)
+            .show(|_| {});
+        return;
+    };
+    let url = share_url(ip, state.backend_port(), &state.backend_token);
+    if let Err(err) = open_share_window(app, &url) {
+        eprintln!("Warning: failed to show sharing details: {}", err);
+        app.dialog()
+            .message(format!(
+                "CARTA is shared on the local network at:\n\n{}\n\nAnyone with this link can open files in your session.",
+                url
+            ))
+            .title("CARTA")
+            .kind(MessageDialogKind::Warning)
+            .show(|_| {});
+    }
+}
+
+fn open_share_window(app: &AppHandle, url: &str) -> AppResult<()> {
+    let qr = QrCode::new(url.as_bytes())
+        .map_err(|err| AppError::Other(err.to_string()))?
+        .render::<svg::Color>()
+        .min_dimensions(QR_SIZE, QR_SIZE)
+        .quiet_zone(false)
+        .build();
+    let html = share_page(url, &qr);
+    let page = Url::parse(&format!(
+        "data:text/html;charset=utf-8,{}",
+        utf8_percent_encode(&html, NON_ALPHANUMERIC)
+    ))
+    .map_err(|err| AppError::Other(err.to_string()))?;
+    WebviewWindowBuilder::new(app, SHARE_WINDOW_LABEL, WebviewUrl::External(page))
+        .title(SHARE_WINDOW_TITLE)
+        .inner_size(420.0, 600.0)
+        .resizable(false)
+        .build()
+        .map_err(|err| AppError::Other(err.to_string()))?;
+    Ok(())
+}
+
+fn share_page(url: &str, qr_svg: &str) -> String {
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 20px; text-align: center; color: #222; background: #fff; }}
+.warning {{ background: #fff3cd; color: #664d03; border: 1px solid #ffda6a; border-radius: 6px; padding: 10px 12px; text-align: left; }}
+.qr {{ background: #fff; display: inline-block; padding: 12px; margin: 16px 0 8px; }}
+code {{ user-select: all; word-break: break-all; font-size: 13px; }}
+@media (prefers-color-scheme: dark) {{ body {{ color: #eee; background: #222; }} }}
+</style>
+</head>
+<body>
+<div class="warning">
+<strong>Anyone who has this link can use your CARTA session.</strong>
+They can browse and open any file the backend can read, from any device on this network.
+Only share it on networks you trust, and quit CARTA to stop sharing.{note}
+</div>
+<div class="qr">{qr_svg}</div>
+<p>Scan the code or open this link on another device:</p>
+<p><code>{url}</code></p>
+</body>
+</html>"#,
+        title = SHARE_WINDOW_TITLE,
+        note = platform_note(),
+        url = escape_html(url),
+    )
+}
+
+/// WSL2's default NAT networking only forwards localhost to the backend.
+#[cfg(target_os = "windows")]
+fn platform_note() -> &'static str {
+    "<p>Other devices can only connect when WSL uses mirrored networking \
+     (<code>networkingMode=mirrored</code> in <code>.wslconfig</code>).</p>"
+}
+
+#[cfg(not(target_os = "windows"))]
+fn platform_note() -> &'static str {
+    ""
+}
+
+/// The address this machine uses to reach other hosts. Connecting a UDP socket
+/// only picks a route; nothing is sent.
+fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+fn share_url(ip: IpAddr, port: u16, token: &str) -> String {
+    format!("http://{}/?token={}", SocketAddr::new(ip, port), token)
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_url_includes_address_port_and_token() {
+        assert_eq!(
+            share_url("192.168.1.20".parse().unwrap(), 3002, "abc"),
+            "http://192.168.1.20:3002/?token=abc"
+        );
+        assert_eq!(
+            share_url("fe80::1".parse().unwrap(), 3002, "abc"),
+            "http://[fe80::1]:3002/?token=abc"
+        );
+    }
+
+    #[test]
+    fn share_page_escapes_url() {
+        let page = share_page("http://10.0.0.2:3002/?token=a&b", "<svg/>");
+        assert!(page.contains("<code>http://10.0.0.2:3002/?token=a&amp;b</code>"));
+        assert!(page.contains("<svg/>"));
+    }
+}
@@ -1,64 +1,247 @@
+mod advanced_start;
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+mod certificates;
+mod connection_lost;
+mod controller;
+mod downloads;
+mod exports;
+#[cfg(target_os = "windows")]
+mod file_associations;
+mod first_run;
+mod folders;
+mod frontend_protocol;
+mod global_shortcut;
+mod i18n;
+mod instance;
+#[cfg(target_os = "windows")]
+mod jump_list;
+mod lan;
+mod monitor;
+mod power;
+mod preflight;
+mod process;
+mod proxy;
+mod quit;
+mod screenshot;
+#[cfg(target_os = "macos")]
+mod services;
+mod session;
+mod settings;
+mod settings_transfer;
+mod smoke;
+mod supervisor;
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod system_backend;
+mod telemetry;
+mod trace;
+mod tray;
+mod updates;
+#[cfg(target_os = "windows")]
+mod wsl_setup;
+
 use std::{
-    error::Error,
+    collections::{BTreeMap, HashMap, HashSet},
     fmt, fs,
-    io::{self, BufRead, BufReader},
-    net::{SocketAddr, TcpStream},
+    io::{self, IsTerminal, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
     path::{Path, PathBuf},
     process::{Child, Command, Stdio},
-    sync::Mutex,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU16, Ordering},
+    },
     time::{Duration, Instant},
 };
 
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use std::os::unix::process::CommandExt;
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+#[cfg(target_os = "macos")]
+use tauri::menu::{CheckMenuItem, MenuBuilder, MenuItem, SubmenuBuilder};
 use tauri::{
-    AppHandle, Manager, PhysicalPosition, PhysicalSize, RunEvent, Runtime, WebviewUrl,
+    AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, RunEvent, Runtime, WebviewUrl,
     WebviewWindow, WebviewWindowBuilder, Window, WindowEvent,
-    menu::{MenuBuilder, MenuItem, SubmenuBuilder},
+    webview::PageLoadEvent,
+    window::{ProgressBarState, ProgressBarStatus},
+};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use tokio::io::{AsyncBufReadExt, AsyncRead};
+
+use i18n::Language;
+#[cfg(target_os = "macos")]
+use i18n::{Message, tr};
+use process::{ProcessRunner, SystemRunner};
+use settings::{
+    BackendChoice, BackendLimits, BackendTimeouts, DisplayBackend, LastWindowClose, LaunchProfile,
+    ProxySettings, RestartSettings, SETTINGS_FILE, SecondInstance, Settings, StartWindowState,
+    ThemePreference, WslShell,
 };
+use supervisor::OutputStream;
+use trace::trace;
 
 const DEFAULT_WINDOW_WIDTH: u32 = 1920;
 const DEFAULT_WINDOW_HEIGHT: u32 = 1080;
 const MIN_WINDOW_WIDTH: u32 = 400;
 const MIN_WINDOW_HEIGHT: u32 = 300;
+/// Range for `--scale-factor` and its setting.
+const MIN_SCALE_FACTOR: f64 = 0.5;
+const MAX_SCALE_FACTOR: f64 = 4.0;
 const WINDOW_OFFSET: i32 = 25;
 const WINDOW_STATE_FILE: &str = "window-state.json";
+const WINDOW_STATE_SAVE_DEBOUNCE_MS: u64 = 500;
+const WINDOW_STATE_VERSION: u64 = 2;
 const WINDOW_TITLE: &str = "CARTA";
+const WINDOW_LABEL_PREFIX: &str = "carta-";
+#[cfg(not(target_os = "macos"))]
+const PROFILES_DIR: &str = "profiles";
+/// Shared by all windows so macOS can group them as tabs.
+#[cfg(target_os = "macos")]
+const WINDOW_TABBING_ID: &str = "carta";
 
 const BACKEND_DIR: &str = "backend";
 const FRONTEND_DIR: &str = "frontend";
 const SYMLINK_BASE: &str = "/tmp";
 const SYMLINK_NAME: &str = "carta-etc";
 
+/// Variables with this prefix are passed on to backends in WSL.
+const ENV_PREFIX: &str = "CARTA_";
 const ENV_AUTH_TOKEN: &str = "CARTA_AUTH_TOKEN";
+/// Shown instead of the real token by `--print-backend-command`.
+const AUTH_TOKEN_PLACEHOLDER: &str = "<token>";
+/// Backend flag disabling token checks; only passed with `--insecure-no-auth`.
+const NO_AUTH_ARG: &str = "--debug_no_auth";
+/// Added per spawn from `AppState::read_only`, never kept in `backend_args`.
+const READ_ONLY_ARG: &str = "--read_only_mode";
+/// `nice` level for a backend limited to low priority.
+const BACKEND_NICENESS: i32 = 10;
+/// Replaces `--frontend_folder` when the app serves the frontend.
+const NO_FRONTEND_ARG: &str = "--no_frontend";
 const ENV_CASAPATH: &str = "CASAPATH";
+/// Packager overrides for the bundled resource locations.
+const ENV_BACKEND_DIR: &str = "CARTA_BACKEND_DIR";
+const ENV_FRONTEND_DIR: &str = "CARTA_FRONTEND_DIR";
+const ENV_CASA_DATA: &str = "CARTA_CASA_DATA";
 const BACKEND_FILENAME: &str = "carta_backend";
-#[cfg(target_os = "windows")]
 const ENV_WSL_DISTRO: &str = "CARTA_WSL_DISTRO";
+/// Ports `portpicker` offers before giving up on one free inside WSL too.
+#[cfg(target_os = "windows")]
+const PORT_PICK_ATTEMPTS: usize = 10;
 
 const BACKEND_TIMEOUT_SECS: u64 = 60;
 const CONNECT_TIMEOUT_MS: u64 = 250;
 const CONNECT_RETRY_MS: u64 = 100;
+/// Tries at starting the launch backend, with a pause doubling from
+/// `BACKEND_RETRY_DELAY_MS` between them.
+const BACKEND_START_ATTEMPTS: u32 = 3;
+const BACKEND_RETRY_DELAY_MS: u64 = 1000;
 
 const MENU_NEW_WINDOW: &str = "new_window";
+#[cfg(target_os = "macos")]
+const MENU_DUPLICATE_WINDOW: &str = "duplicate_window";
+#[cfg(target_os = "macos")]
+const MENU_NEW_WINDOW_WITH_FOLDER: &str = "new_window_with_folder";
+#[cfg(target_os = "macos")]
 const MENU_TOGGLE_FULLSCREEN: &str = "toggle_fullscreen";
+#[cfg(target_os = "macos")]
 const MENU_TOGGLE_DEVTOOLS: &str = "toggle_devtools";
+#[cfg(target_os = "macos")]
+const MENU_ALWAYS_OPEN_DEVTOOLS: &str = "always_open_devtools";
+#[cfg(target_os = "macos")]
+const MENU_PREVENT_SLEEP: &str = "prevent_sleep";
+#[cfg(target_os = "macos")]
+const MENU_SHOW_TAB_BAR: &str = "show_tab_bar";
+#[cfg(target_os = "macos")]
+const MENU_MERGE_ALL_WINDOWS: &str = "merge_all_windows";
+#[cfg(target_os = "macos")]
+const MENU_NEXT_WINDOW: &str = "next_window";
+#[cfg(target_os = "macos")]
+const MENU_PREVIOUS_WINDOW: &str = "previous_window";
+#[cfg(target_os = "macos")]
+const MENU_KIOSK_MODE: &str = "kiosk_mode";
+#[cfg(target_os = "macos")]
+const MENU_SAVE_SCREENSHOT: &str = "save_screenshot";
+#[cfg(target_os = "macos")]
+const MENU_READ_ONLY: &str = "read_only";
+#[cfg(target_os = "macos")]
+const MENU_OPEN_LOG_FOLDER: &str = "open_log_folder";
+pub(crate) const MENU_COPY_AUTH_TOKEN: &str = "copy_auth_token";
+#[cfg(target_os = "macos")]
+const MENU_COPY_BACKEND_COMMAND: &str = "copy_backend_command";
+#[cfg(target_os = "macos")]
+const MENU_ADVANCED_START: &str = "advanced_start";
+#[cfg(target_os = "macos")]
+const MENU_CLOSE_ALL_WINDOWS: &str = "close_all_windows";
+#[cfg(target_os = "macos")]
+const MENU_QUIT: &str = "quit";
+
+/// Characters escaped in file paths passed to the frontend; `/` separates
+/// folders and the rest are unreserved.
+const URL_PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// The frontend's query parameter for a layout preset.
+const LAYOUT_PARAM: &str = "layout";
+
+const EVENT_KIOSK_MODE_CHANGED: &str = "kiosk-mode-changed";
+const EVENT_READ_ONLY_CHANGED: &str = "read-only-changed";
 
 #[derive(Debug, Default)]
 struct CliArgs {
     input_path: Option<String>,
     extra_args: Vec<String>,
     inspect: bool,
+    kiosk: bool,
+    new_window: bool,
+    share_lan: bool,
+    insecure_no_auth: bool,
+    read_only: bool,
     help: bool,
     version: bool,
+    /// `--version` prints JSON, for tooling.
+    json: bool,
+    print_backend_command: bool,
+    verbose: bool,
+    smoke_test: Option<smoke::SmokeTest>,
     port: Option<u16>,
     port_error: Option<String>,
+    profile: Option<String>,
+    profile_error: Option<String>,
+    data_profile: Option<String>,
+    data_profile_error: Option<String>,
+    layout: Option<String>,
+    layout_error: Option<String>,
+    backend_timeout: Option<u64>,
+    backend_timeout_error: Option<String>,
+    env: Vec<(String, String)>,
+    env_error: Option<String>,
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    wsl_distro: Option<String>,
+    wsl_distro_error: Option<String>,
+    controller: Option<tauri::Url>,
+    controller_error: Option<String>,
+    disable_gpu: bool,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    display_backend: Option<DisplayBackend>,
+    window_state: Option<StartWindowState>,
+    monitor: Option<usize>,
+    monitor_error: Option<String>,
+    scale_factor: Option<f64>,
+    scale_factor_error: Option<String>,
+    bind_address: Option<IpAddr>,
+    bind_address_error: Option<String>,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 struct WindowBounds {
     width: u32,
     height: u32,
@@ -68,6 +251,18 @@ struct WindowBounds {
     devtools_open: bool,
 }
 
+impl Default for WindowBounds {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WINDOW_WIDTH,
+            height: DEFAULT_WINDOW_HEIGHT,
+            x: 0,
+            y: 0,
+            devtools_open: false,
+        }
+    }
+}
+
 impl WindowBounds {
     fn new(
         pos: PhysicalPosition<i32>,
@@ -101,40 +296,337 @@ impl WindowBounds {
     }
 }
 
-#[derive(Debug)]
-struct AppError(String);
+/// On-disk layout of `window-state.json`. Bump `WINDOW_STATE_VERSION` and add a
+/// step to `migrate_window_state` whenever the shape changes.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct WindowState {
+    version: u64,
+    /// The last saved bounds, for monitor setups without bounds of their own.
+    bounds: WindowBounds,
+    /// Bounds by `monitor_setup_key`, so docking and undocking a laptop each
+    /// bring back the layout last used with those monitors.
+    setups: BTreeMap<String, WindowBounds>,
+}
+
+impl WindowState {
+    fn new(bounds: WindowBounds) -> Self {
+        Self {
+            version: WINDOW_STATE_VERSION,
+            bounds,
+            setups: BTreeMap::new(),
+        }
+    }
 
-impl fmt::Display for AppError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+    fn bounds_for(self, setup: Option<&str>) -> WindowBounds {
+        let Self {
+            bounds, mut setups, ..
+        } = self;
+        setup
+            .and_then(|setup| setups.remove(setup))
+            .unwrap_or(bounds)
     }
 }
 
-impl Error for AppError {}
+/// Names the connected monitors by their resolutions, whatever order the
+/// system lists them in.
+fn monitor_setup_key(sizes: impl IntoIterator<Item = PhysicalSize<u32>>) -> Option<String> {
+    let mut sizes: Vec<String> = sizes
+        .into_iter()
+        .map(|size| format!("{}x{}", size.width, size.height))
+        .collect();
+    sizes.sort();
+    (!sizes.is_empty()).then(|| sizes.join(","))
+}
+
+fn current_monitor_setup(app: &AppHandle) -> Option<String> {
+    let monitors = app.available_monitors().ok()?;
+    monitor_setup_key(monitors.iter().map(|monitor| *monitor.size()))
+}
+
+/// Launcher failures. The variants callers need to tell apart carry their
+/// context; one-off failures are `Other`.
+#[derive(Debug, thiserror::Error)]
+enum AppError {
+    #[error("resource directory not found")]
+    ResourceDirNotFound,
+    #[error("{}", not_found_message(.resource, .path, .set_by))]
+    ResourceNotFound {
+        resource: Resource,
+        path: PathBuf,
+        /// The override variable that pointed at `path`, if any.
+        set_by: Option<&'static str>,
+    },
+    #[error("Requested file or directory does not exist")]
+    InputNotFound,
+    #[error("Requested path is neither a file nor a directory")]
+    InputNotFileOrDir,
+    #[error("{0}")]
+    InvalidBackendArgs(String),
+    #[error("{0} Reinstall CARTA to repair its installation.")]
+    BrokenBundle(String),
+    #[error("No free port available.")]
+    NoFreePort,
+    #[error("Backend process exited unexpectedly with status: {0}")]
+    BackendExited(std::process::ExitStatus),
+    #[error(
+        "Backend not ready on port {port} after {secs}s{}",
+        .detail.as_ref().map(|detail| format!(" ({})", detail)).unwrap_or_default()
+    )]
+    BackendTimeout {
+        port: u16,
+        secs: u64,
+        /// The last connection error.
+        detail: Option<String>,
+    },
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    #[error("Failed to convert {0} to WSL format")]
+    WslConversionFailed(&'static str),
+    #[error("symlink path {0} already exists")]
+    SymlinkConflict(String),
+    #[error("failed to create symlink {0} for etc path with spaces")]
+    SymlinkFailed(String),
+    #[allow(dead_code)]
+    #[error("unsupported platform")]
+    UnsupportedPlatform,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Other(String),
+}
 
 impl From<&str> for AppError {
     fn from(s: &str) -> Self {
-        Self(s.to_string())
+        Self::Other(s.to_string())
+    }
+}
+
+impl AppError {
+    /// The exit code this failure implies whatever stage it happened in.
+    fn exit_code(&self) -> Option<ExitCode> {
+        match self {
+            Self::ResourceDirNotFound | Self::ResourceNotFound { .. } | Self::BrokenBundle(_) => {
+                Some(ExitCode::BackendMissing)
+            }
+            Self::InputNotFound | Self::InputNotFileOrDir => Some(ExitCode::InvalidInput),
+            Self::InvalidBackendArgs(_) => Some(ExitCode::Usage),
+            Self::NoFreePort => Some(ExitCode::PortUnavailable),
+            Self::BackendExited(_) | Self::BackendTimeout { .. } => Some(ExitCode::BackendFailed),
+            _ => None,
+        }
+    }
+
+    /// Whether starting the backend again may get past this: spawning it
+    /// timed out or found its port taken, or WSL failed while still waking
+    /// up. A missing or unrunnable executable and a backend that rejects its
+    /// arguments fail the same way every time.
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Io(err) => matches!(
+                err.kind(),
+                io::ErrorKind::TimedOut | io::ErrorKind::AddrInUse | io::ErrorKind::Interrupted
+            ),
+            Self::BackendExited(status) => is_wsl_failure(status),
+            _ => false,
+        }
     }
 }
 
-impl From<io::Error> for AppError {
-    fn from(err: io::Error) -> Self {
-        Self(err.to_string())
+/// Whether `wsl.exe` failed itself rather than passing on the backend's exit
+/// status. Its own failures are HRESULTs, which have the sign bit set.
+fn is_wsl_failure(status: &std::process::ExitStatus) -> bool {
+    cfg!(target_os = "windows") && status.code().is_some_and(|code| code < 0)
+}
+
+/// Resources the backend runs from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resource {
+    Backend,
+    Frontend,
+    CasaData,
+}
+
+/// Names the override variable when one is in effect, and otherwise the
+/// bundled location.
+fn not_found_message(resource: &Resource, path: &Path, set_by: &Option<&'static str>) -> String {
+    match set_by {
+        Some(var) => format!("{} not found (set by {})", path.display(), var),
+        None => format!(
+            "{} not found",
+            match resource {
+                Resource::Backend => "backend/bin/carta_backend binary",
+                Resource::Frontend => "frontend directory",
+                Resource::CasaData => "backend/etc directory",
+            }
+        ),
     }
 }
 
 type AppResult<T> = Result<T, AppError>;
 
+/// Process exit codes, so scripts launching CARTA can tell failures apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitCode {
+    /// Anything not covered below, e.g. the app itself failing to start.
+    Failure = 1,
+    /// Unknown or conflicting command-line options.
+    Usage = 2,
+    /// The file or folder to open, or the top-level folder, is not usable.
+    InvalidInput = 3,
+    /// No backend binary, or other resources it needs, could be found.
+    BackendMissing = 4,
+    /// The backend was found but exited or never became ready.
+    BackendFailed = 5,
+    /// The requested port is taken, or no free port could be found.
+    PortUnavailable = 6,
+}
+
+impl ExitCode {
+    fn exit(self, message: impl fmt::Display) -> ! {
+        eprintln!("{}", message);
+        telemetry::record_failure(self);
+        if ERROR_DIALOGS.load(Ordering::Relaxed) {
+            show_fatal_error(&message.to_string());
+        }
+        std::process::exit(self as i32)
+    }
+}
+
+/// Whether fatal errors are also shown in a dialog; see `wants_error_dialogs`.
+static ERROR_DIALOGS: AtomicBool = AtomicBool::new(false);
+
+/// A launch from the desktop has no terminal to show stderr in. Modes meant
+/// for scripts never open dialogs, which would block them.
+fn wants_error_dialogs(cli: &CliArgs) -> bool {
+    let scripted = cli.help || cli.version || cli.print_backend_command || cli.smoke_test.is_some();
+    #[cfg(target_os = "linux")]
+    let has_display =
+        std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some();
+    #[cfg(not(target_os = "linux"))]
+    let has_display = true;
+    !scripted && has_display && !io::stderr().is_terminal()
+}
+
+/// How this launch uses a running instance. `--new-window` always opens a
+/// window there; otherwise the `second_instance` setting applies, except to
+/// scripted runs and launches with options for a session of their own.
+/// `file_args` are the further positional arguments taken as files, as from
+/// a multi-selection, which the running instance opens too.
+fn second_instance_action(
+    cli: &CliArgs,
+    file_args: &[&str],
+    setting: SecondInstance,
+) -> SecondInstance {
+    if cli.new_window {
+        return SecondInstance::NewWindow;
+    }
+    let own_session = cli.help
+        || cli.version
+        || cli.print_backend_command
+        || cli.smoke_test.is_some()
+        || cli.port.is_some()
+        || cli.profile.is_some()
+        || cli.data_profile.is_some()
+        || cli.controller.is_some()
+        || cli.kiosk
+        || cli.share_lan
+        || cli.insecure_no_auth
+        || cli.read_only
+        || cli
+            .extra_args
+            .iter()
+            .any(|arg| !file_args.contains(&arg.as_str()));
+    if own_session {
+        SecondInstance::Separate
+    } else {
+        setting
+    }
+}
+
+/// Uses the dialog library directly, since the app may not exist yet.
+fn show_fatal_error(message: &str) {
+    rfd::MessageDialog::new()
+        .set_title("CARTA")
+        .set_description(format!("CARTA could not start.\n\n{}", message))
+        .set_level(rfd::MessageLevel::Error)
+        .set_buttons(rfd::MessageButtons::Ok)
+        .show();
+}
+
 struct AppState {
     backend: Mutex<Option<Child>>,
-    backend_port: u16,
+    /// Changes only when a restart finds the port taken; see `free_launch_port`.
+    backend_port: AtomicU16,
     backend_token: String,
-    window_url: String,
+    /// Arguments the backend was started with, reused for restarts.
+    base_dir: PathBuf,
+    /// Edited in Advanced Start, which restarts the backend with them.
+    backend_args: Mutex<Vec<String>>,
+    /// Recent output of the launch and folder backends.
+    backend_log: Arc<supervisor::BackendLog>,
+    /// The supervisor is restarting the launch backend after a crash.
+    backend_restarting: AtomicBool,
+    /// Windows whose progress is running or paused.
+    busy_windows: Mutex<HashSet<String>>,
+    tasks: supervisor::BackgroundTasks,
+    /// Starts backends and runs their `--help`/`--version`.
+    processes: Box<dyn ProcessRunner>,
+    folder_backends: folders::FolderBackends,
+    downloads: downloads::Downloads,
+    save_targets: exports::SaveTargets,
+    /// Resolved from settings once at startup; see `proxy::Proxy`.
+    proxy: Mutex<Option<proxy::Proxy>>,
+    /// The launch backend's first page; follows `backend_port`.
+    window_url: Mutex<String>,
+    /// The `carta_controller` deployment windows sign in to, from
+    /// `--controller` or settings; no backend is started then.
+    controller: Option<tauri::Url>,
+    /// Windows load the frontend from `frontend_protocol` and backends start
+    /// without theirs; from the saved preference, fixed at startup.
+    frontend_from_app: bool,
+    /// How long a starting backend may take to accept connections.
+    backend_timeout: Duration,
+    /// From `--env`, set for every backend after the inherited variables.
+    backend_env: Vec<(String, String)>,
     inspect: bool,
+    /// Presentation/kiosk mode, from `--kiosk` or the View menu.
+    kiosk: AtomicBool,
+    /// The backend listens on all interfaces, from `--share-lan`.
+    share_lan: bool,
+    /// Token authentication is off, from `--insecure-no-auth`.
+    no_auth: bool,
+    /// Backends start with `--read_only_mode`; from `--read-only` or the menu.
+    read_only: AtomicBool,
+    /// Webview data profile from `--data-profile`; `None` uses the shared one.
+    data_profile: Option<String>,
+    /// From `--disable-gpu` or the saved preference, fixed at startup.
+    disable_gpu: AtomicBool,
+    /// From `--scale-factor` or the saved preference, fixed at startup.
+    scale_factor: Option<f64>,
     window_order: Mutex<Vec<String>>,
+    settings: Mutex<Settings>,
+    sleep_inhibitor: power::SleepInhibitor,
+    /// Certificates the untrusted-certificate page may be asked to trust.
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    refused_certificates: certificates::Refused,
+    resource_monitor: monitor::ResourceMonitor,
+    /// Save deadlines by window label.
+    pending_bounds_save: Mutex<HashMap<String, Instant>>,
+    /// Files opened with the app before the launch backend was ready;
+    /// `None` once it is.
     #[cfg(target_os = "macos")]
-    top_level_path: PathBuf,
+    deferred_opened_urls: Mutex<Option<Vec<tauri::Url>>>,
+    #[cfg(target_os = "windows")]
+    backend_job: Mutex<Option<BackendJob>>,
+}
+
+impl AppState {
+    fn backend_port(&self) -> u16 {
+        self.backend_port.load(Ordering::Relaxed)
+    }
+
+    fn window_url(&self) -> String {
+        self.window_url.lock().unwrap().clone()
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -231,11 +723,13 @@ fn validate_backend_args(args: &[String]) -> AppResult<()> {
                 .unwrap_or((arg.as_str(), false));
 
             let Some(kind) = backend_option_kind(name) else {
-                return Err(AppError(unknown_backend_option_message(name)));
+                return Err(AppError::InvalidBackendArgs(
+                    unknown_backend_option_message(name),
+                ));
             };
 
             if has_inline_value && kind == OptionValueKind::None {
-                return Err(AppError(format!(
+                return Err(AppError::InvalidBackendArgs(format!(
                     "Backend option {} does not take a value",
                     name
                 )));
@@ -243,13 +737,13 @@ fn validate_backend_args(args: &[String]) -> AppResult<()> {
 
             if kind == OptionValueKind::Required && !has_inline_value {
                 let Some(value) = args.get(i + 1) else {
-                    return Err(AppError(format!(
+                    return Err(AppError::InvalidBackendArgs(format!(
                         "Backend option {} requires a value",
                         name
                     )));
                 };
                 if value.starts_with('-') {
-                    return Err(AppError(format!(
+                    return Err(AppError::InvalidBackendArgs(format!(
                         "Backend option {} requires a value",
                         name
                     )));
@@ -275,6 +769,142 @@ where
     let mut result = CliArgs::default();
     let mut iter = args.into_iter().peekable();
 
+    fn parse_profile(value: &str, result: &mut CliArgs) -> bool {
+        if is_valid_profile_name(value) {
+            result.profile = Some(value.to_string());
+            true
+        } else {
+            result.profile_error.replace(format!(
+                "Invalid profile name: {} (use letters, digits, '-' and '_')",
+                value
+            ));
+            false
+        }
+    }
+
+    fn parse_data_profile(value: &str, result: &mut CliArgs) -> bool {
+        if is_valid_profile_name(value) {
+            result.data_profile = Some(value.to_string());
+            true
+        } else {
+            result.data_profile_error.replace(format!(
+                "Invalid data profile name: {} (use letters, digits, '-' and '_')",
+                value
+            ));
+            false
+        }
+    }
+
+    fn parse_layout(value: &str, result: &mut CliArgs) -> bool {
+        if value.trim().is_empty() {
+            result.layout_error = Some("Missing value for --layout".to_string());
+            false
+        } else {
+            result.layout = Some(value.to_string());
+            true
+        }
+    }
+
+    fn parse_backend_timeout(value: &str, result: &mut CliArgs) -> bool {
+        match value.parse::<u64>() {
+            Ok(secs) if secs > 0 => {
+                result.backend_timeout = Some(secs);
+                true
+            }
+            _ => {
+                result
+                    .backend_timeout_error
+                    .replace(format!("Invalid backend timeout: {}", value));
+                false
+            }
+        }
+    }
+
+    fn parse_monitor(value: &str, result: &mut CliArgs) -> bool {
+        match value.parse::<usize>() {
+            Ok(index) => {
+                result.monitor = Some(index);
+                true
+            }
+            Err(_) => {
+                result.monitor_error = Some(format!("Invalid monitor index: {}", value));
+                false
+            }
+        }
+    }
+
+    fn parse_scale_factor(value: &str, result: &mut CliArgs) -> bool {
+        match value.parse::<f64>() {
+            Ok(factor) if (MIN_SCALE_FACTOR..=MAX_SCALE_FACTOR).contains(&factor) => {
+                result.scale_factor = Some(factor);
+                true
+            }
+            _ => {
+                result.scale_factor_error = Some(format!("Invalid scale factor: {}", value));
+                false
+            }
+        }
+    }
+
+    fn parse_bind_address(value: &str, result: &mut CliArgs) -> bool {
+        match value.trim().parse::<IpAddr>() {
+            Ok(address) if !address.is_unspecified() => {
+                result.bind_address = Some(address);
+                true
+            }
+            Ok(_) => {
+                result.bind_address_error = Some(format!(
+                    "Invalid bind address: {} (use --share-lan for all interfaces)",
+                    value
+                ));
+                false
+            }
+            Err(_) => {
+                result.bind_address_error = Some(format!("Invalid bind address: {}", value));
+                false
+            }
+        }
+    }
+
+    fn parse_wsl_distro(value: &str, result: &mut CliArgs) -> bool {
+        if value.trim().is_empty() {
+            result.wsl_distro_error = Some("Missing value for --wsl-distro".to_string());
+            false
+        } else {
+            result.wsl_distro = Some(value.trim().to_string());
+            true
+        }
+    }
+
+    fn parse_controller(value: &str, result: &mut CliArgs) -> bool {
+        match controller::parse_url(value) {
+            Ok(url) => {
+                result.controller = Some(url);
+                true
+            }
+            Err(err) => {
+                result.controller_error = Some(err);
+                false
+            }
+        }
+    }
+
+    fn parse_env(value: &str, result: &mut CliArgs) -> bool {
+        match value.split_once('=') {
+            Some((name, value)) if is_env_name(name) => {
+                result.env.push((name.to_string(), value.to_string()));
+                true
+            }
+            _ => {
+                result.env_error.replace(format!(
+                    "Invalid environment variable: {} (use KEY=VALUE)",
+                    value
+                ));
+                false
+            }
+        }
+    }
+
     fn parse_port(value: &str, result: &mut CliArgs) -> bool {
         match value.parse::<u16>() {
             Ok(port) => {
@@ -303,8 +933,178 @@ where
                 break;
             }
             "--inspect" => result.inspect = true,
+            "--kiosk" => result.kiosk = true,
+            "--new-window" => result.new_window = true,
+            "--share-lan" => result.share_lan = true,
+            "--insecure-no-auth" => result.insecure_no_auth = true,
+            "--read-only" => result.read_only = true,
+            "--disable-gpu" => result.disable_gpu = true,
+            "--x11" => result.display_backend = Some(DisplayBackend::X11),
+            "--wayland" => result.display_backend = Some(DisplayBackend::Wayland),
+            "--maximized" => result.window_state = Some(StartWindowState::Maximized),
+            "--minimized" => result.window_state = Some(StartWindowState::Minimized),
+            "--fullscreen" => result.window_state = Some(StartWindowState::Fullscreen),
+            "--profile" => {
+                let Some(value) = iter.next() else {
+                    result.profile_error = Some("Missing value for --profile".to_string());
+                    break;
+                };
+                if !parse_profile(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--profile=") => {
+                let value = s.trim_start_matches("--profile=");
+                if !parse_profile(value, &mut result) {
+                    break;
+                }
+            }
+            "--data-profile" => {
+                let Some(value) = iter.next() else {
+                    result.data_profile_error =
+                        Some("Missing value for --data-profile".to_string());
+                    break;
+                };
+                if !parse_data_profile(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--data-profile=") => {
+                let value = s.trim_start_matches("--data-profile=");
+                if !parse_data_profile(value, &mut result) {
+                    break;
+                }
+            }
+            "--layout" => {
+                let Some(value) = iter.next() else {
+                    result.layout_error = Some("Missing value for --layout".to_string());
+                    break;
+                };
+                if !parse_layout(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--layout=") => {
+                let value = s.trim_start_matches("--layout=");
+                if !parse_layout(value, &mut result) {
+                    break;
+                }
+            }
+            "--backend-timeout" => {
+                let Some(value) = iter.next() else {
+                    result.backend_timeout_error =
+                        Some("Missing value for --backend-timeout".to_string());
+                    break;
+                };
+                if !parse_backend_timeout(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--backend-timeout=") => {
+                let value = s.trim_start_matches("--backend-timeout=");
+                if !parse_backend_timeout(value, &mut result) {
+                    break;
+                }
+            }
+            "--monitor" => {
+                let Some(value) = iter.next() else {
+                    result.monitor_error = Some("Missing value for --monitor".to_string());
+                    break;
+                };
+                if !parse_monitor(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--monitor=") => {
+                let value = s.trim_start_matches("--monitor=");
+                if !parse_monitor(value, &mut result) {
+                    break;
+                }
+            }
+            "--scale-factor" => {
+                let Some(value) = iter.next() else {
+                    result.scale_factor_error =
+                        Some("Missing value for --scale-factor".to_string());
+                    break;
+                };
+                if !parse_scale_factor(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--scale-factor=") => {
+                let value = s.trim_start_matches("--scale-factor=");
+                if !parse_scale_factor(value, &mut result) {
+                    break;
+                }
+            }
+            "--bind-address" => {
+                let Some(value) = iter.next() else {
+                    result.bind_address_error =
+                        Some("Missing value for --bind-address".to_string());
+                    break;
+                };
+                if !parse_bind_address(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--bind-address=") => {
+                let value = s.trim_start_matches("--bind-address=");
+                if !parse_bind_address(value, &mut result) {
+                    break;
+                }
+            }
+            "--wsl-distro" => {
+                let Some(value) = iter.next() else {
+                    result.wsl_distro_error = Some("Missing value for --wsl-distro".to_string());
+                    break;
+                };
+                if !parse_wsl_distro(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--wsl-distro=") => {
+                let value = s.trim_start_matches("--wsl-distro=");
+                if !parse_wsl_distro(value, &mut result) {
+                    break;
+                }
+            }
+            "--controller" => {
+                let Some(value) = iter.next() else {
+                    result.controller_error = Some("Missing value for --controller".to_string());
+                    break;
+                };
+                if !parse_controller(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--controller=") => {
+                let value = s.trim_start_matches("--controller=");
+                if !parse_controller(value, &mut result) {
+                    break;
+                }
+            }
+            "--env" => {
+                let Some(value) = iter.next() else {
+                    result.env_error = Some("Missing value for --env".to_string());
+                    break;
+                };
+                if !parse_env(&value, &mut result) {
+                    break;
+                }
+            }
+            s if s.starts_with("--env=") => {
+                let value = s.trim_start_matches("--env=");
+                if !parse_env(value, &mut result) {
+                    break;
+                }
+            }
             "--help" | "-h" => result.help = true,
             "--version" | "-v" => result.version = true,
+            "--json" => result.json = true,
+            "--print-backend-command" => result.print_backend_command = true,
+            "--verbose" => result.verbose = true,
+            "--smoke-test" => result.smoke_test = Some(smoke::SmokeTest::Backend),
+            "--smoke-test-webview" => result.smoke_test = Some(smoke::SmokeTest::Webview),
             "--port" | "-p" => {
                 let Some(value) = iter.next() else {
                     result.port_error = Some("Missing value for --port".to_string());
@@ -347,25 +1147,49 @@ where
     result
 }
 
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|ch: char| ch.is_ascii_digit())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
 fn parse_cli_args() -> CliArgs {
-    parse_cli_args_from(std::env::args().skip(1))
+    parse_cli_args_from(launcher_args())
 }
 
-fn resolve_base_directory(input_path: Option<&str>) -> AppResult<PathBuf> {
-    let cwd = std::env::current_dir()?;
+/// The command-line arguments. `std::env::args` panics on one that is not
+/// Unicode, such as a file name in a legacy encoding; that one is reported
+/// as not found instead.
+fn launcher_args() -> impl Iterator<Item = String> {
+    std::env::args_os().skip(1).map(|arg| {
+        arg.into_string()
+            .unwrap_or_else(|arg| arg.to_string_lossy().into_owned())
+    })
+}
 
+/// `default_dir` replaces the home folder for desktop launches, which also
+/// start in the home folder on some Linux desktops. From a terminal, the home
+/// folder is where the user chose to be.
+fn resolve_base_directory(
+    cwd: PathBuf,
+    input_path: Option<&str>,
+    default_dir: Option<&Path>,
+    desktop_launch: bool,
+) -> AppResult<PathBuf> {
     if let Some(path) = input_path {
         #[cfg(target_os = "windows")]
         {
             if is_wsl_path_str(path) {
-                if wsl_test_path(path, "-f")? {
+                if wsl_test_path(&SystemRunner, path, "-f")? {
                     let parent = PathBuf::from(wsl_parent_path(path));
                     return Ok(parent);
                 }
-                if wsl_test_path(path, "-d")? {
+                if wsl_test_path(&SystemRunner, path, "-d")? {
                     return Ok(PathBuf::from(path));
                 }
-                return Err("Requested file or directory does not exist".into());
+                return Err(AppError::InputNotFound);
             }
         }
 
@@ -374,18 +1198,22 @@ fn resolve_base_directory(input_path: Option<&str>) -> AppResult<PathBuf> {
         } else {
             cwd.join(path)
         };
-        let metadata = fs::metadata(&candidate)
-            .map_err(|_| AppError::from("Requested file or directory does not exist"))?;
+        let metadata = fs::metadata(&candidate).map_err(|_| AppError::InputNotFound)?;
 
         if metadata.is_file() {
             Ok(candidate.parent().unwrap_or(&candidate).to_path_buf())
         } else if metadata.is_dir() {
             Ok(candidate)
         } else {
-            Err("Requested path is neither a file nor a directory".into())
+            Err(AppError::InputNotFileOrDir)
+        }
+    } else if should_default_to_home(&cwd)
+        || (desktop_launch && home_dir().as_deref() == Some(cwd.as_path()))
+    {
+        match default_dir.filter(|dir| dir.is_dir()) {
+            Some(dir) => Ok(dir.to_path_buf()),
+            None => home_dir().ok_or_else(|| "HOME directory not found".into()),
         }
-    } else if should_default_to_home(&cwd) {
-        home_dir().ok_or_else(|| "HOME directory not found".into())
     } else {
         Ok(cwd)
     }
@@ -402,10 +1230,10 @@ fn resolve_input_file_path(
     #[cfg(target_os = "windows")]
     {
         if is_wsl_path_str(path) {
-            if wsl_test_path(path, "-f")? {
+            if wsl_test_path(&SystemRunner, path, "-f")? {
                 return Ok(Some(PathBuf::from(path)));
             }
-            if wsl_test_path(path, "-d")? && is_image_directory(Path::new(path)) {
+            if wsl_test_path(&SystemRunner, path, "-d")? && is_image_directory(Path::new(path)) {
                 return Ok(Some(PathBuf::from(path)));
             }
             return Ok(None);
@@ -420,8 +1248,7 @@ fn resolve_input_file_path(
         std::env::current_dir()?.join(path)
     };
 
-    let metadata = fs::metadata(&candidate)
-        .map_err(|_| AppError::from("Requested file or directory does not exist"))?;
+    let metadata = fs::metadata(&candidate).map_err(|_| AppError::InputNotFound)?;
 
     if metadata.is_file() || (metadata.is_dir() && is_image_directory(&candidate)) {
         Ok(Some(candidate))
@@ -510,6 +1337,13 @@ fn is_path_within_top_level(base: &Path, top_level: &Path) -> bool {
     base.strip_prefix(top_level).is_ok()
 }
 
+/// The top-level folder the launch backend's current arguments give.
+fn top_level_path(state: &AppState) -> AppResult<PathBuf> {
+    let top_level_folder = resolve_top_level_folder(&state.backend_args.lock().unwrap())
+        .unwrap_or_else(|| "/".to_string());
+    resolve_top_level_path(&top_level_folder)
+}
+
 fn ensure_base_dir_within_top_level(base_dir: PathBuf, top_level: &Path) -> PathBuf {
     if is_path_within_top_level(&base_dir, top_level) {
         base_dir
@@ -550,8 +1384,10 @@ fn handle_opened_urls(app: &AppHandle, state: &AppState, urls: Vec<tauri::Url>)
         return;
     }
 
-    let Some(window_url) = build_window_url(&state.window_url, &input_files, &state.top_level_path)
-    else {
+    let Ok(top_level) = top_level_path(state) else {
+        return;
+    };
+    let Some(window_url) = build_window_url(&state.window_url(), &input_files, &top_level) else {
         return;
     };
     let Ok(target_url) = tauri::Url::parse(&window_url) else {
@@ -586,8 +1422,39 @@ fn handle_opened_urls(app: &AppHandle, state: &AppState, urls: Vec<tauri::Url>)
     let _ = create_window(app, state, new_window_label(), Some(&window_url));
 }
 
-fn build_window_url(base_url: &str, input_files: &[PathBuf], top_level: &Path) -> Option<String> {
-    let files: Vec<_> = input_files
+/// Asks the frontend to start with the widget layout preset `layout`.
+fn with_layout(window_url: &str, layout: &str) -> String {
+    match tauri::Url::parse(window_url) {
+        Ok(mut url) => {
+            url.query_pairs_mut().append_pair(LAYOUT_PARAM, layout);
+            url.to_string()
+        }
+        Err(_) => window_url.to_string(),
+    }
+}
+
+/// Where pages of a protocol registered with `register_uri_scheme_protocol`
+/// live. WebView2 only loads custom schemes as a subdomain of `localhost`.
+fn custom_scheme_origin(scheme: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("http://{}.localhost/", scheme)
+    } else {
+        format!("{}://localhost/", scheme)
+    }
+}
+
+/// The first page of a window on the backend at `port`, served by the
+/// backend or, with `frontend_from_app`, by the app.
+fn backend_window_url(frontend_from_app: bool, port: u16, token: Option<&str>) -> String {
+    match token {
+        _ if frontend_from_app => frontend_protocol::window_url(port, token),
+        Some(token) => format!("http://{}:{}/?token={}", backend_host(), port, token),
+        None => format!("http://{}:{}/", backend_host(), port),
+    }
+}
+
+fn build_window_url(base_url: &str, input_files: &[PathBuf], top_level: &Path) -> Option<String> {
+    let files: Vec<_> = input_files
         .iter()
         .filter_map(|f| relative_url_path(f, top_level))
         .collect();
@@ -600,10 +1467,19 @@ fn build_window_url(base_url: &str, input_files: &[PathBuf], top_level: &Path) -
 }
 
 fn relative_url_path(input_file: &Path, top_level: &Path) -> Option<String> {
+    let file_path = relative_backend_path(input_file, top_level)?;
+    // Percent-encoded so names with `&`, `#` or `+` survive in the query.
+    Some(utf8_percent_encode(&file_path, URL_PATH_ENCODE_SET).to_string())
+}
+
+/// `input_file` as the backend names it: relative to its top-level folder,
+/// with `/` separators. `None` for files outside it and for names that are
+/// not UTF-8, which the frontend cannot carry without changing them.
+fn relative_backend_path(input_file: &Path, top_level: &Path) -> Option<String> {
     #[cfg(target_os = "windows")]
     let relative = {
-        let input_wsl = to_wsl_path_str(&input_file.to_string_lossy()).ok()?;
-        let top_wsl = to_wsl_path_str(&top_level.to_string_lossy()).ok()?;
+        let input_wsl = to_wsl_path_str(input_file.to_str()?).ok()?;
+        let top_wsl = to_wsl_path_str(top_level.to_str()?).ok()?;
         PathBuf::from(input_wsl)
             .strip_prefix(&top_wsl)
             .ok()?
@@ -613,17 +1489,10 @@ fn relative_url_path(input_file: &Path, top_level: &Path) -> Option<String> {
     #[cfg(not(target_os = "windows"))]
     let relative = input_file.strip_prefix(top_level).ok()?.to_path_buf();
 
-    let file_path = url_path_from_fs(&relative);
-    (!file_path.is_empty()).then_some(file_path)
-}
-
-fn url_path_from_fs(path: &Path) -> String {
-    let raw = path.to_string_lossy();
+    let file_path = relative.to_str()?;
     #[cfg(target_os = "windows")]
-    return raw.replace('\\', "/");
-
-    #[cfg(not(target_os = "windows"))]
-    return raw.into_owned();
+    let file_path = &file_path.replace('\\', "/");
+    (!file_path.is_empty()).then(|| file_path.to_string())
 }
 
 fn should_default_to_home(cwd: &Path) -> bool {
@@ -687,15 +1556,37 @@ fn win_to_wsl_path(win_path: &str) -> Option<String> {
     if path.len() < 2 {
         return None;
     }
-    let chars: Vec<char> = path.chars().collect();
-    if chars.get(1) != Some(&':') {
+    let bytes = path.as_bytes();
+    // Both bytes are ASCII, so slicing after them stays on a char boundary.
+    if !bytes[0].is_ascii_alphabetic() || bytes[1] != b':' {
         return None;
     }
-    let drive = chars[0].to_ascii_lowercase();
+    let drive = (bytes[0] as char).to_ascii_lowercase();
     let rest = &path[2..].replace('\\', "/");
     Some(format!("/mnt/{}{}", drive, rest))
 }
 
+/// The reverse of `win_to_wsl_path`. Paths outside `/mnt/<drive>` go through
+/// the `\\wsl.localhost` share of `distro`, so need to know it.
+#[cfg(target_os = "windows")]
+fn wsl_to_win_path(wsl_path: &str, distro: Option<&str>) -> Option<String> {
+    if let Some(rest) = wsl_path.strip_prefix("/mnt/") {
+        let (drive, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        if drive.len() == 1 && drive.as_bytes()[0].is_ascii_alphabetic() {
+            return Some(format!(
+                "{}:\\{}",
+                drive.to_ascii_uppercase(),
+                rest.replace('/', "\\")
+            ));
+        }
+    }
+    Some(format!(
+        r"\\wsl.localhost\{}{}",
+        distro?,
+        wsl_path.replace('/', "\\")
+    ))
+}
+
 #[cfg(target_os = "windows")]
 fn is_wsl_path_str(path: &str) -> bool {
     path.starts_with('/')
@@ -706,7 +1597,7 @@ fn to_wsl_path_str(path: &str) -> AppResult<String> {
     if is_wsl_path_str(path) {
         Ok(path.to_string())
     } else {
-        win_to_wsl_path(path).ok_or_else(|| AppError::from("Failed to convert path to WSL format"))
+        win_to_wsl_path(path).ok_or(AppError::WslConversionFailed("path"))
     }
 }
 
@@ -739,12 +1630,55 @@ fn normalize_wsl_arg_path(value: &str) -> AppResult<String> {
     to_wsl_path_str(&abs_path.to_string_lossy())
 }
 
+/// The distribution chosen at startup, from `--wsl-distro`, `CARTA_WSL_DISTRO`
+/// or the settings in that order, or later in the first-run picker.
+#[cfg(target_os = "windows")]
+static WSL_DISTRO: Mutex<Option<String>> = Mutex::new(None);
+
 #[cfg(target_os = "windows")]
 fn wsl_distro() -> Option<String> {
-    std::env::var(ENV_WSL_DISTRO)
-        .ok()
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
+    WSL_DISTRO.lock().unwrap().clone().or_else(|| {
+        std::env::var(ENV_WSL_DISTRO)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    })
+}
+
+/// Used for every later `wsl.exe` call, including backend restarts.
+#[cfg(target_os = "windows")]
+pub(crate) fn set_wsl_distro(distro: Option<String>) {
+    *WSL_DISTRO.lock().unwrap() = distro;
+}
+
+/// Installed distributions, as `wsl.exe` lists them (default first).
+#[cfg(target_os = "windows")]
+fn wsl_distros(processes: &dyn ProcessRunner) -> AppResult<Vec<String>> {
+    let mut cmd = Command::new("wsl.exe");
+    cmd.args(["--list", "--quiet"])
+        .creation_flags(CREATE_NO_WINDOW);
+    let output = processes
+        .output(&mut cmd)
+        .map_err(|err| AppError::Other(format!("Failed to run wsl.exe: {}", err)))?;
+    if !output.status.success() {
+        return Err("WSL is not installed or not enabled".into());
+    }
+    Ok(decode_wsl_output(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(ToString::to_string)
+        .collect())
+}
+
+/// `wsl.exe` writes its own messages as UTF-16LE.
+#[cfg(any(target_os = "windows", test))]
+fn decode_wsl_output(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units).replace('\0', "")
 }
 
 #[cfg(target_os = "windows")]
@@ -754,27 +1688,64 @@ fn add_wsl_distro(cmd: &mut Command) {
     }
 }
 
+/// The shell settings, from the startup settings.
+#[cfg(target_os = "windows")]
+static WSL_SHELL: std::sync::OnceLock<WslShell> = std::sync::OnceLock::new();
+
 #[cfg(target_os = "windows")]
-fn wsl_bash_command(command: &str) -> Command {
+fn wsl_shell_command(processes: &dyn ProcessRunner, command: &str) -> Command {
+    let shell = WSL_SHELL.get().cloned().unwrap_or_default();
+    let program = shell
+        .program
+        .clone()
+        .unwrap_or_else(|| detected_wsl_shell(processes));
     let mut cmd = Command::new("wsl.exe");
     add_wsl_distro(&mut cmd);
-    cmd.arg("--").arg("bash").arg("-lc").arg(command);
+    // `--exec` skips the user's default shell, which may not be POSIX.
+    cmd.arg("--exec").args(shell.args(&program, command));
     cmd
 }
 
+/// bash when the distribution has it, else the `sh` every distribution has.
+#[cfg(target_os = "windows")]
+fn detected_wsl_shell(processes: &dyn ProcessRunner) -> String {
+    static DETECTED: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    DETECTED
+        .get_or_init(|| {
+            let shell = detect_wsl_shell(processes);
+            trace!("WSL shell: {}", shell);
+            shell.to_string()
+        })
+        .clone()
+}
+
+#[cfg(target_os = "windows")]
+fn detect_wsl_shell(processes: &dyn ProcessRunner) -> &'static str {
+    let mut cmd = Command::new("wsl.exe");
+    add_wsl_distro(&mut cmd);
+    cmd.args(["--exec", "sh", "-c", "command -v bash"])
+        .creation_flags(CREATE_NO_WINDOW);
+    let has_bash = processes
+        .output(&mut cmd)
+        .is_ok_and(|output| output.status.success());
+    if has_bash { "bash" } else { "sh" }
+}
+
 #[cfg(all(target_os = "windows", test))]
-fn wsl_bash_output(command: &str) -> AppResult<std::process::Output> {
-    let output = wsl_bash_command(command)
+fn wsl_shell_output(command: &str) -> AppResult<std::process::Output> {
+    let output = wsl_shell_command(&process::SystemRunner, command)
         .output()
-        .map_err(|err| AppError(format!("Failed to run wsl.exe bash command: {}", err)))?;
+        .map_err(|err| AppError::Other(format!("Failed to run wsl.exe bash command: {}", err)))?;
     if !output.status.success() {
         let detail = String::from_utf8_lossy(&output.stderr);
-        return Err(AppError(format!("WSL command failed: {}", detail.trim())));
+        return Err(AppError::Other(format!(
+            "WSL command failed: {}",
+            detail.trim()
+        )));
     }
     Ok(output)
 }
 
-#[cfg(target_os = "windows")]
 fn bash_escape(value: &str) -> String {
     if value.is_empty() {
         return "''".to_string();
@@ -792,12 +1763,26 @@ fn bash_escape(value: &str) -> String {
     escaped
 }
 
+/// Quotes `value` for bash only when it needs it, to keep printed commands
+/// readable.
+fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "-_./:=,+@%".contains(ch));
+    if plain {
+        value.to_string()
+    } else {
+        bash_escape(value)
+    }
+}
+
 #[cfg(target_os = "windows")]
-fn wsl_test_path(path: &str, flag: &str) -> AppResult<bool> {
+fn wsl_test_path(processes: &dyn ProcessRunner, path: &str, flag: &str) -> AppResult<bool> {
     let command = format!("test {} {}", flag, bash_escape(path));
-    let output = wsl_bash_command(&command)
-        .output()
-        .map_err(|err| AppError(format!("Failed to run wsl.exe bash command: {}", err)))?;
+    let output = processes
+        .output(&mut wsl_shell_command(processes, &command))
+        .map_err(|err| AppError::Other(format!("Failed to run wsl.exe bash command: {}", err)))?;
     Ok(output.status.success())
 }
 
@@ -837,46 +1822,175 @@ fn normalize_backend_args_for_wsl(extra_args: &[String]) -> AppResult<Vec<String
 }
 
 fn resolve_resource_dir(app: &AppHandle) -> Option<PathBuf> {
-    app.path().resource_dir().ok()
+    let resource_dir = app.path().resource_dir().ok();
+    trace!("resource directory: {:?}", resource_dir);
+    resource_dir
+}
+
+/// The location named by `var`, when set and non-empty.
+fn resource_override(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `vars` are the overrides that could have pointed at `path`.
+fn missing_resource(resource: Resource, path: &Path, vars: &[&'static str]) -> AppError {
+    AppError::ResourceNotFound {
+        resource,
+        path: path.to_path_buf(),
+        set_by: vars
+            .iter()
+            .find(|var| resource_override(var).is_some())
+            .copied(),
+    }
+}
+
+/// `backend/` in the resources, or `CARTA_BACKEND_DIR` with the same layout
+/// (`bin/`, `etc/`, `libs/`).
+fn backend_root(resource_dir: &Path) -> PathBuf {
+    resource_override(ENV_BACKEND_DIR).unwrap_or_else(|| resource_dir.join(BACKEND_DIR))
 }
 
 fn resolve_backend_path(resource_dir: &Path) -> AppResult<PathBuf> {
-    let candidate = resource_dir
-        .join(BACKEND_DIR)
+    let candidate = backend_root(resource_dir)
         .join("bin")
         .join(BACKEND_FILENAME);
-    candidate
-        .exists()
-        .then_some(candidate)
-        .ok_or_else(|| AppError::from("backend/bin/carta_backend binary not found"))
+    trace!(
+        "backend binary: {} (exists: {})",
+        candidate.display(),
+        candidate.exists()
+    );
+    if candidate.exists() {
+        return Ok(candidate);
+    }
+    Err(missing_resource(
+        Resource::Backend,
+        &candidate,
+        &[ENV_BACKEND_DIR],
+    ))
+}
+
+/// The bundled backend, or on macOS and Linux a system-installed one as
+/// `choice` says. The flag is true for the bundled binary.
+#[cfg_attr(target_os = "windows", allow(unused_variables))]
+fn resolve_backend_binary(
+    resource_dir: &Path,
+    choice: BackendChoice,
+) -> AppResult<(PathBuf, bool)> {
+    let bundled = resolve_backend_path(resource_dir);
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        let use_system = match choice {
+            BackendChoice::Auto => bundled.is_err(),
+            BackendChoice::Bundled => false,
+            BackendChoice::System => true,
+        };
+        if use_system && let Some(backend) = system_backend::find() {
+            trace!("using system backend {}", backend.path.display());
+            return Ok((backend.path.clone(), false));
+        }
+        if choice == BackendChoice::System {
+            eprintln!("Warning: no system carta_backend found; using the bundled one");
+        }
+    }
+    bundled.map(|path| (path, true))
+}
+
+fn backend_choice(app: &AppHandle) -> BackendChoice {
+    app.state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .backend_choice
+}
+
+/// A backend that can be chosen in Preferences.
+#[derive(Debug, Clone, serde::Serialize)]
+struct AvailableBackend {
+    path: PathBuf,
+    version: String,
+}
+
+/// The backends to choose from and the current choice.
+#[derive(Debug, Clone, serde::Serialize)]
+struct BackendChoices {
+    choice: BackendChoice,
+    bundled: Option<AvailableBackend>,
+    /// Always unset on Windows, where the backend runs in WSL.
+    system: Option<AvailableBackend>,
+}
+
+fn backend_choices(app: &AppHandle) -> BackendChoices {
+    // The bundled backend is built with the app, so it has the app's version.
+    let bundled = resolve_resource_dir(app)
+        .and_then(|dir| resolve_backend_path(&dir).ok())
+        .map(|path| AvailableBackend {
+            path,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        });
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    let system = system_backend::find().map(|backend| AvailableBackend {
+        path: backend.path.clone(),
+        version: backend.version.clone(),
+    });
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    let system = None;
+    BackendChoices {
+        choice: backend_choice(app),
+        bundled,
+        system,
+    }
 }
 
 fn resolve_frontend_path(resource_dir: &Path) -> AppResult<PathBuf> {
-    let candidate = resource_dir.join(FRONTEND_DIR);
-    candidate
-        .exists()
-        .then_some(candidate)
-        .ok_or_else(|| AppError::from("frontend directory not found"))
+    let candidate =
+        resource_override(ENV_FRONTEND_DIR).unwrap_or_else(|| resource_dir.join(FRONTEND_DIR));
+    trace!(
+        "frontend folder: {} (exists: {})",
+        candidate.display(),
+        candidate.exists()
+    );
+    if candidate.exists() {
+        return Ok(candidate);
+    }
+    Err(missing_resource(
+        Resource::Frontend,
+        &candidate,
+        &[ENV_FRONTEND_DIR],
+    ))
 }
 
 #[cfg(any(target_os = "windows", target_os = "linux"))]
 fn resolve_libs_path(resource_dir: &Path) -> Option<PathBuf> {
-    let candidate = resource_dir.join(BACKEND_DIR).join("libs");
+    let candidate = backend_root(resource_dir).join("libs");
+    trace!(
+        "backend libs: {} (exists: {})",
+        candidate.display(),
+        candidate.exists()
+    );
     candidate.exists().then_some(candidate)
 }
 
+/// CASA data for `CASAPATH`: `CARTA_CASA_DATA`, else the backend's `etc/`.
 fn resolve_etc_path(resource_dir: &Path) -> AppResult<String> {
-    let etc_path = resource_dir.join(BACKEND_DIR).join("etc");
+    let etc_path =
+        resource_override(ENV_CASA_DATA).unwrap_or_else(|| backend_root(resource_dir).join("etc"));
     if !etc_path.exists() {
-        return Err(AppError::from("backend/etc directory not found"));
+        return Err(missing_resource(
+            Resource::CasaData,
+            &etc_path,
+            &[ENV_CASA_DATA, ENV_BACKEND_DIR],
+        ));
     }
 
     let resolved = fs::canonicalize(&etc_path).unwrap_or(etc_path);
+    trace!("CASA data: {}", resolved.display());
 
     #[cfg(target_os = "windows")]
     {
         let wsl_path = win_to_wsl_path(&resolved.to_string_lossy())
-            .ok_or_else(|| AppError::from("Failed to convert etc path to WSL format"))?;
+            .ok_or(AppError::WslConversionFailed("etc path"))?;
 
         // If path doesn't contain spaces, use it directly
         if !wsl_path.contains(' ') {
@@ -884,66 +1998,7 @@ fn resolve_etc_path(resource_dir: &Path) -> AppResult<String> {
         }
 
         // Path contains spaces, need to create symlink in WSL
-        let link_path = format!("{}/{}", SYMLINK_BASE, SYMLINK_NAME);
-
-        // Check if symlink already exists and points to correct target
-        let mut check_cmd = Command::new("wsl.exe");
-        add_wsl_distro(&mut check_cmd);
-        check_cmd
-            .args(["--", "readlink", "-f", &link_path])
-            .creation_flags(CREATE_NO_WINDOW);
-
-        if let Ok(output) = check_cmd.output() {
-            let existing = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if existing == wsl_path {
-                return Ok(link_path);
-            }
-        }
-
-        // Check if something exists at link path
-        let mut stat_cmd = Command::new("wsl.exe");
-        add_wsl_distro(&mut stat_cmd);
-        stat_cmd
-            .args(["--", "test", "-e", &link_path])
-            .creation_flags(CREATE_NO_WINDOW);
-
-        let exists = stat_cmd.status().map(|s| s.success()).unwrap_or(false);
-
-        // Check if it's a symlink
-        let mut is_link_cmd = Command::new("wsl.exe");
-        add_wsl_distro(&mut is_link_cmd);
-        is_link_cmd
-            .args(["--", "test", "-L", &link_path])
-            .creation_flags(CREATE_NO_WINDOW);
-
-        let is_symlink = is_link_cmd.status().map(|s| s.success()).unwrap_or(false);
-
-        if exists && !is_symlink {
-            return Err("symlink path already exists".into());
-        }
-
-        // Remove existing symlink if it points to wrong target
-        if is_symlink {
-            let mut rm_cmd = Command::new("wsl.exe");
-            add_wsl_distro(&mut rm_cmd);
-            rm_cmd
-                .args(["--", "rm", "-f", &link_path])
-                .creation_flags(CREATE_NO_WINDOW);
-            let _ = rm_cmd.status();
-        }
-
-        // Create new symlink
-        let mut ln_cmd = Command::new("wsl.exe");
-        add_wsl_distro(&mut ln_cmd);
-        ln_cmd
-            .args(["--", "ln", "-s", &wsl_path, &link_path])
-            .creation_flags(CREATE_NO_WINDOW);
-
-        if ln_cmd.status().map(|s| s.success()).unwrap_or(false) {
-            Ok(link_path)
-        } else {
-            Err("failed to create symlink for etc path with spaces".into())
-        }
+        link_wsl_etc_path(&SystemRunner, &wsl_path)
     }
 
     #[cfg(any(target_os = "macos", target_os = "linux"))]
@@ -957,32 +2012,124 @@ fn resolve_etc_path(resource_dir: &Path) -> AppResult<String> {
         let base_dir = PathBuf::from(SYMLINK_BASE);
         let _ = fs::create_dir_all(&base_dir);
         let link_path = base_dir.join(SYMLINK_NAME);
+        trace!(
+            "CASA data path has spaces; linking {} to it",
+            link_path.display()
+        );
 
         if let Ok(metadata) = fs::symlink_metadata(&link_path) {
             if !metadata.file_type().is_symlink() {
-                return Err("symlink path already exists".into());
+                return Err(AppError::SymlinkConflict(
+                    link_path.to_string_lossy().into_owned(),
+                ));
             }
 
             if let Ok(existing) = fs::read_link(&link_path)
                 && existing == resolved
             {
+                trace!("reusing existing link {}", link_path.display());
                 return Ok(link_path.to_string_lossy().into_owned());
             }
 
+            trace!("replacing stale link {}", link_path.display());
             let _ = fs::remove_file(&link_path);
         }
 
         if std::os::unix::fs::symlink(&resolved, &link_path).is_ok() {
             Ok(link_path.to_string_lossy().into_owned())
         } else {
-            Err("failed to create symlink for etc path with spaces".into())
+            Err(AppError::SymlinkFailed(
+                link_path.to_string_lossy().into_owned(),
+            ))
         }
     }
 
     #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
     {
         let _ = resolved;
-        Err("unsupported platform".into())
+        Err(AppError::UnsupportedPlatform)
+    }
+}
+
+/// Links `SYMLINK_BASE/SYMLINK_NAME` inside WSL to `wsl_path`, which has
+/// spaces the backend cannot handle, and returns the link.
+#[cfg(target_os = "windows")]
+fn link_wsl_etc_path(processes: &dyn ProcessRunner, wsl_path: &str) -> AppResult<String> {
+    let link_path = format!("{}/{}", SYMLINK_BASE, SYMLINK_NAME);
+    trace!(
+        "CASA data path has spaces; linking {} to {}",
+        link_path, wsl_path
+    );
+
+    // Check if symlink already exists and points to correct target
+    let mut check_cmd = Command::new("wsl.exe");
+    add_wsl_distro(&mut check_cmd);
+    check_cmd
+        .args(["--", "readlink", "-f", &link_path])
+        .creation_flags(CREATE_NO_WINDOW);
+
+    if let Ok(output) = processes.output(&mut check_cmd) {
+        let existing = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if existing == wsl_path {
+            trace!("reusing existing link {}", link_path);
+            return Ok(link_path);
+        }
+    }
+
+    // Check if something exists at link path
+    let mut stat_cmd = Command::new("wsl.exe");
+    add_wsl_distro(&mut stat_cmd);
+    stat_cmd
+        .args(["--", "test", "-e", &link_path])
+        .creation_flags(CREATE_NO_WINDOW);
+
+    let exists = processes
+        .status(&mut stat_cmd)
+        .map(|s| s.success())
+        .unwrap_or(false);
+
+    // Check if it's a symlink
+    let mut is_link_cmd = Command::new("wsl.exe");
+    add_wsl_distro(&mut is_link_cmd);
+    is_link_cmd
+        .args(["--", "test", "-L", &link_path])
+        .creation_flags(CREATE_NO_WINDOW);
+
+    let is_symlink = processes
+        .status(&mut is_link_cmd)
+        .map(|s| s.success())
+        .unwrap_or(false);
+
+    if exists && !is_symlink {
+        return Err(AppError::SymlinkConflict(link_path));
+    }
+
+    // Remove existing symlink if it points to wrong target
+    if is_symlink {
+        trace!("replacing stale link {}", link_path);
+        let mut rm_cmd = Command::new("wsl.exe");
+        add_wsl_distro(&mut rm_cmd);
+        rm_cmd
+            .args(["--", "rm", "-f", &link_path])
+            .creation_flags(CREATE_NO_WINDOW);
+        let _ = processes.status(&mut rm_cmd);
+    }
+
+    // Create new symlink
+    let mut ln_cmd = Command::new("wsl.exe");
+    add_wsl_distro(&mut ln_cmd);
+    ln_cmd
+        .args(["--", "ln", "-s", wsl_path, &link_path])
+        .creation_flags(CREATE_NO_WINDOW);
+
+    if processes
+        .status(&mut ln_cmd)
+        .map(|s| s.success())
+        .unwrap_or(false)
+    {
+        Ok(link_path)
+    } else {
+        Err(AppError::SymlinkFailed(link_path))
     }
 }
 
@@ -994,23 +2141,23 @@ fn resolve_casa_path(resource_dir: &Path) -> AppResult<String> {
 }
 
 #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-fn run_backend_help(_app: &AppHandle, _version: bool) -> AppResult<()> {
-    Err("unsupported platform".into())
+fn backend_flag_output(_app: &AppHandle, _flag: &str) -> AppResult<std::process::Output> {
+    Err(AppError::UnsupportedPlatform)
 }
 
+/// Runs the selected backend with just `flag`, such as `--help`.
 #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
-fn run_backend_help(app: &AppHandle, version: bool) -> AppResult<()> {
-    let flag = if version { "--version" } else { "--help" };
-
-    let resource_dir =
-        resolve_resource_dir(app).ok_or_else(|| AppError::from("resource directory not found"))?;
-    let backend_path = resolve_backend_path(&resource_dir)?;
+fn backend_flag_output(app: &AppHandle, flag: &str) -> AppResult<std::process::Output> {
+    let resource_dir = resolve_resource_dir(app).ok_or(AppError::ResourceDirNotFound)?;
+    let (backend_path, _) = resolve_backend_binary(&resource_dir, backend_choice(app))?;
+    let state = app.state::<AppState>();
+    let processes = &state.processes;
 
     let output = {
         #[cfg(target_os = "windows")]
         {
             let backend = win_to_wsl_path(&backend_path.to_string_lossy())
-                .ok_or_else(|| AppError::from("Failed to convert backend path to WSL format"))?;
+                .ok_or(AppError::WslConversionFailed("backend path"))?;
             let libs_path = resolve_libs_path(&resource_dir)
                 .and_then(|p| win_to_wsl_path(&p.to_string_lossy()));
             let ld_export = libs_path
@@ -1027,10 +2174,11 @@ fn run_backend_help(app: &AppHandle, version: bool) -> AppResult<()> {
                 backend = bash_escape(&backend),
                 flag = bash_escape(flag)
             );
-            let mut cmd = wsl_bash_command(&command);
+            let mut cmd = wsl_shell_command(processes, &command);
             cmd.creation_flags(CREATE_NO_WINDOW);
-            cmd.output()
-                .map_err(|err| AppError(format!("Failed to run wsl.exe bash command: {}", err)))?
+            processes.output(&mut cmd).map_err(|err| {
+                AppError::Other(format!("Failed to run wsl.exe bash command: {}", err))
+            })?
         }
         #[cfg(any(target_os = "macos", target_os = "linux"))]
         {
@@ -1051,502 +2199,3202 @@ fn run_backend_help(app: &AppHandle, version: bool) -> AppResult<()> {
                 }
             }
 
-            cmd.output()?
+            processes.output(&mut cmd)?
         }
     };
+    Ok(output)
+}
 
+fn run_backend_help(app: &AppHandle) -> AppResult<()> {
+    let flag = "--help";
+    let output = backend_flag_output(app, flag)?;
     print!("{}", String::from_utf8_lossy(&output.stdout));
     eprint!("{}", String::from_utf8_lossy(&output.stderr));
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(AppError(format!(
+        return Err(AppError::Other(format!(
             "carta_backend {} failed: {}",
             flag,
             stderr.trim()
         )));
     }
 
-    if !version {
-        println!();
-        println!("Additional Tauri flag:");
-        println!("      --inspect      Open the DevTools in the Tauri window.");
-    }
+    println!();
+    println!("Additional Tauri flags:");
+    println!("      --inspect      Open the DevTools in the Tauri window.");
+    println!("      --kiosk        Start in presentation mode: fullscreen, no DevTools");
+    println!("                     or new windows.");
+    println!("      --new-window   Open a new window in the running CARTA instead of");
+    println!("                     starting another session, if CARTA is running.");
+    println!("      --share-lan    Let other devices on the local network join the");
+    println!("                     session; shows its link and a QR code.");
+    println!("      --insecure-no-auth");
+    println!("                     Start the backend without token authentication,");
+    println!("                     for isolated networks only.");
+    println!("      --read-only    Start the backend in read-only mode.");
+    println!("      --backend-timeout <seconds>");
+    println!(
+        "                     How long to wait for the backend to start (default {}).",
+        BACKEND_TIMEOUT_SECS
+    );
+    println!("      --env <KEY=VALUE>");
+    println!("                     Set an environment variable for the backend; may be");
+    println!("                     repeated. CARTA_* variables are passed on as well.");
+    println!("      --controller <url>");
+    println!("                     Sign in to the CARTA controller at <url> and use its");
+    println!("                     sessions instead of starting a backend.");
+    println!("      --layout <name>");
+    println!("                     Open the frontend with the layout preset <name>.");
+    println!("      --profile <name>");
+    println!("                     Use the backend, frontend and flags of the launch");
+    println!("                     profile <name> in the settings file.");
+    println!("      --data-profile <name>");
+    println!("                     Keep frontend preferences and other webview data");
+    println!("                     separate from other data profiles.");
+    println!("      --disable-gpu  Render without hardware acceleration, e.g. if images");
+    println!("                     appear black (Linux and Windows).");
+    println!("      --scale-factor <factor>");
+    println!("                     Render the frontend at this device scale factor,");
+    println!(
+        "                     from {} to {}, instead of the display's.",
+        MIN_SCALE_FACTOR, MAX_SCALE_FACTOR
+    );
+    println!("      --bind-address <ip>");
+    println!("                     Run the backend on <ip>, e.g. ::1 where IPv4 loopback");
+    println!("                     is disabled, instead of 127.0.0.1.");
+    println!("      --wsl-distro <name>");
+    println!("                     Run the backend in the WSL distribution <name> from");
+    println!("                     now on (Windows).");
+    println!("      --x11, --wayland");
+    println!("                     Use XWayland or native Wayland windows (Linux).");
+    println!("      --maximized, --minimized, --fullscreen");
+    println!("                     Start the first window maximized, minimized or");
+    println!("                     fullscreen.");
+    println!("      --monitor <index>");
+    println!("                     Start the first window on monitor <index>, counting");
+    println!("                     from 0 in the order the system lists them.");
+    println!("      --verbose      Log each startup step to stderr and startup.log in");
+    println!("                     the app log folder.");
+    println!("      --print-backend-command");
+    println!("                     Print the backend command line and environment");
+    println!("                     without starting anything.");
+    println!("      --version --json");
+    println!("                     Print the versions of CARTA's parts as JSON.");
+    println!("      --smoke-test   Start the backend, check that it serves the frontend,");
+    println!("                     then quit without opening a window.");
+    println!("      --smoke-test-webview");
+    println!("                     As --smoke-test, and also load the frontend in a");
+    println!("                     hidden window.");
+    println!();
+    println!("Exit codes:");
+    println!("      1  other failure         4  backend not found");
+    println!("      2  invalid options       5  backend failed to start");
+    println!("      3  invalid input path    6  port unavailable");
 
     Ok(())
 }
 
-/// Spawns a thread to pipe backend output to stdout/stderr.
-/// The thread exits naturally when the pipe closes. JoinHandle is intentionally
-/// discarded as waiting for it adds complexity with minimal benefit.
-fn pipe_output<T: std::io::Read + Send + 'static>(reader: T, is_stderr: bool) {
-    std::thread::spawn(move || {
-        let buf = BufReader::new(reader);
-        for line in buf.lines().map_while(Result::ok) {
-            if is_stderr {
-                eprintln!("{}", line);
-            } else {
-                println!("{}", line);
-            }
-        }
-    });
+/// What `--version` reports: the launcher and the parts it runs.
+#[derive(Debug, serde::Serialize)]
+struct VersionReport {
+    carta: String,
+    tauri: &'static str,
+    webview: Option<String>,
+    backend: Option<String>,
+    frontend: Option<String>,
 }
 
-fn spawn_backend(
-    app: &AppHandle,
-    state: &AppState,
-    base_dir: &Path,
-    extra_args: &[String],
-) -> AppResult<()> {
-    let resource_dir =
-        resolve_resource_dir(app).ok_or_else(|| AppError::from("resource directory not found"))?;
-
-    #[cfg(target_os = "windows")]
-    {
-        let backend_path = resolve_backend_path(&resource_dir)?;
-        let frontend_path = resolve_frontend_path(&resource_dir)?;
-
-        // Convert Windows paths to WSL paths directly in Rust
-        let backend = to_wsl_path_str(&backend_path.to_string_lossy())?;
-        let frontend = to_wsl_path_str(&frontend_path.to_string_lossy())?;
-        let base = to_wsl_path_str(&base_dir.to_string_lossy())?;
-        let casa_path = resolve_casa_path(&resource_dir)?;
-        let normalized_extra_args = normalize_backend_args_for_wsl(extra_args)?;
-
-        // Libs directory for LD_LIBRARY_PATH
-        let libs_path =
-            resolve_libs_path(&resource_dir).and_then(|p| win_to_wsl_path(&p.to_string_lossy()));
-        let extra = normalized_extra_args
-            .iter()
-            .map(|arg| bash_escape(arg))
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        let auth_token = &state.backend_token;
-        let port = state.backend_port;
-        let backend_escaped = bash_escape(&backend);
-        let frontend_escaped = bash_escape(&frontend);
-        let base_escaped = bash_escape(&base);
-        let auth_token_escaped = bash_escape(auth_token);
-        let casa_path_escaped = bash_escape(&casa_path);
-
-        let ld_export = libs_path
-            .map(|p| {
-                format!(
-                    "export LD_LIBRARY_PATH={}:$LD_LIBRARY_PATH; ",
-                    bash_escape(&p)
-                )
-            })
-            .unwrap_or_default();
-
-        let command = format!(
-            "{ld_export}export {ENV_AUTH_TOKEN}={auth_token_escaped}; export {ENV_CASAPATH}={casa_path_escaped}; exec {backend_escaped} {base_escaped} --port={port} --frontend_folder={frontend_escaped} --no_browser {extra}"
-        );
-
-        let mut cmd = wsl_bash_command(&command);
-        cmd.stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .creation_flags(CREATE_NO_WINDOW);
-
-        let mut child = cmd.spawn().map_err(AppError::from)?;
-
-        if let Some(stdout) = child.stdout.take() {
-            pipe_output(stdout, false);
-        }
-        if let Some(stderr) = child.stderr.take() {
-            pipe_output(stderr, true);
-        }
-
-        *state.backend.lock().unwrap() = Some(child);
-        Ok(())
+impl VersionReport {
+    fn text(&self) -> String {
+        let unknown = |version: &Option<String>| version.clone().unwrap_or("unknown".to_string());
+        format!(
+            "CARTA {}\nTauri {}\nWeb view {}\nBackend {}\nFrontend {}\n",
+            self.carta,
+            self.tauri,
+            unknown(&self.webview),
+            unknown(&self.backend),
+            unknown(&self.frontend)
+        )
     }
-    #[cfg(any(target_os = "macos", target_os = "linux"))]
-    {
-        let backend_path = resolve_backend_path(&resource_dir)?;
-        let frontend_path = resolve_frontend_path(&resource_dir)?;
-
-        let mut cmd = Command::new(&backend_path);
-        cmd.arg(base_dir)
-            .arg(format!("--port={}", state.backend_port))
-            .arg(format!("--frontend_folder={}", frontend_path.display()))
-            .arg("--no_browser")
-            .args(extra_args)
-            .env(ENV_AUTH_TOKEN, &state.backend_token)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+}
 
-        let casa_path = resolve_casa_path(&resource_dir)?;
-        cmd.env(ENV_CASAPATH, casa_path);
+fn version_report(app: &AppHandle) -> VersionReport {
+    let frontend = resolve_resource_dir(app)
+        .and_then(|dir| resolve_frontend_path(&dir).ok())
+        .and_then(|dir| frontend_version(&dir));
+    VersionReport {
+        carta: app.package_info().version.to_string(),
+        tauri: tauri::VERSION,
+        webview: tauri::webview_version().ok(),
+        backend: backend_version(app),
+        frontend,
+    }
+}
 
-        #[cfg(target_os = "linux")]
-        {
-            if let Some(libs_dir) = resolve_libs_path(&resource_dir) {
-                let mut ld_library_path = libs_dir.to_string_lossy().into_owned();
-                if let Ok(existing) = std::env::var("LD_LIBRARY_PATH")
-                    && !existing.trim().is_empty()
-                {
-                    ld_library_path.push(':');
-                    ld_library_path.push_str(existing.trim());
-                }
-                cmd.env("LD_LIBRARY_PATH", ld_library_path);
-            }
+pub(crate) fn backend_version(app: &AppHandle) -> Option<String> {
+    match backend_flag_output(app, "--version") {
+        Ok(output) if output.status.success() => {
+            parse_backend_version(&String::from_utf8_lossy(&output.stdout))
         }
-
-        let mut child = cmd.spawn().map_err(AppError::from)?;
-
-        if let Some(stdout) = child.stdout.take() {
-            pipe_output(stdout, false);
+        Ok(output) => {
+            eprintln!(
+                "Warning: carta_backend --version failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            None
         }
-        if let Some(stderr) = child.stderr.take() {
-            pipe_output(stderr, true);
+        Err(err) => {
+            eprintln!("Warning: failed to run carta_backend --version: {}", err);
+            None
         }
-
-        *state.backend.lock().unwrap() = Some(child);
-        Ok(())
     }
+}
 
-    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+/// Catches bundled parts that do not belong together, as a partial manual
+/// update leaves them, before they fail with a less helpful message. Parts
+/// from `CARTA_BACKEND_DIR` or `CARTA_FRONTEND_DIR`, or a system backend, are
+/// the user's choice and not checked.
+fn check_bundle(
+    app: &AppHandle,
+    resource_dir: &Path,
+    backend: &AppResult<(PathBuf, bool)>,
+) -> AppResult<()> {
+    if resource_override(ENV_BACKEND_DIR).is_some() || resource_override(ENV_FRONTEND_DIR).is_some()
     {
-        let _ = (app, state, base_dir, extra_args);
-        Err("unsupported platform".into())
+        return Ok(());
     }
-}
-
-fn wait_for_backend(state: &AppState, timeout: Duration) -> AppResult<()> {
-    let addr = SocketAddr::from(([127, 0, 0, 1], state.backend_port));
-    let start = Instant::now();
-    let mut last_error: Option<io::Error> = None;
-
-    while start.elapsed() < timeout {
-        // Check if backend process is still running.
-        // On Windows, this checks wsl.exe which exits when the inner carta_backend exits.
-        if let Some(ref mut child) = *state.backend.lock().unwrap()
-            && let Ok(Some(status)) = child.try_wait()
-        {
-            return Err(AppError(format!(
-                "Backend process exited unexpectedly with status: {}",
-                status
+    let frontend = resolve_frontend_path(resource_dir).ok();
+    let frontend = match (backend, frontend) {
+        (Err(_), Some(_)) => {
+            return Err(AppError::BrokenBundle(format!(
+                "The frontend is installed, but the backend is missing from {}.",
+                backend_root(resource_dir).display()
             )));
         }
-
-        match TcpStream::connect_timeout(&addr, Duration::from_millis(CONNECT_TIMEOUT_MS)) {
-            Ok(_) => return Ok(()),
-            Err(err) => last_error = Some(err),
+        (Ok((_, true)), None) => {
+            return Err(AppError::BrokenBundle(format!(
+                "The backend is installed, but the frontend is missing from {}.",
+                resource_dir.join(FRONTEND_DIR).display()
+            )));
+        }
+        (Ok((_, true)), Some(frontend)) => frontend,
+        _ => return Ok(()),
+    };
+    let carta = app.package_info().version.to_string();
+    // In WSL, asking the backend its version would be slow as well.
+    if !cfg!(target_os = "windows") {
+        check_backend_release(app, carta.clone());
+    }
+    match frontend_version(&frontend) {
+        Some(version) if !same_release(&version, &carta) => {
+            Err(release_mismatch("frontend", &version, &carta))
         }
-        std::thread::sleep(Duration::from_millis(CONNECT_RETRY_MS));
+        _ => Ok(()),
     }
-
-    let detail = last_error
-        .map(|err| format!(" ({})", err))
-        .unwrap_or_default();
-    Err(AppError(format!(
-        "Backend not ready on port {} after {}s{}",
-        state.backend_port,
-        timeout.as_secs(),
-        detail
-    )))
 }
 
-fn window_state_path(app: &AppHandle) -> Option<PathBuf> {
-    app.path()
-        .app_config_dir()
-        .ok()
-        .map(|dir| dir.join(WINDOW_STATE_FILE))
+/// The backend's part of `check_bundle`. Asking its version runs it, so this
+/// happens off the startup path and a mismatch is reported once known.
+fn check_backend_release(app: &AppHandle, carta: String) {
+    let handle = app.clone();
+    app.state::<AppState>().tasks.spawn(async move {
+        let app = handle.clone();
+        let version = tauri::async_runtime::spawn_blocking(move || backend_version(&app)).await;
+        let Ok(Some(version)) = version else {
+            return;
+        };
+        if same_release(&version, &carta) {
+            return;
+        }
+        let err = release_mismatch("backend", &version, &carta);
+        eprintln!("Error: {}", err);
+        handle
+            .dialog()
+            .message(err.to_string())
+            .title("CARTA")
+            .kind(MessageDialogKind::Error)
+            .show(|_| {});
+    });
 }
 
-fn load_window_bounds(app: &AppHandle) -> Option<WindowBounds> {
-    let path = window_state_path(app)?;
-    let contents = fs::read_to_string(path).ok()?;
-    serde_json::from_str(&contents).ok().map(WindowBounds::clamp_min_size)
+fn release_mismatch(part: &str, version: &str, carta: &str) -> AppError {
+    AppError::BrokenBundle(format!(
+        "The {} is version {}, but this CARTA is version {}.",
+        part, version, carta
+    ))
 }
 
-fn save_window_bounds(app: &AppHandle, window: &Window) {
-    let Some(path) = window_state_path(app) else {
-        return;
-    };
-    let (Ok(pos), Ok(size), Ok(scale)) = (
-        window.outer_position(),
-        #[cfg(not(target_os = "windows"))]
-        window.outer_size(),
-        #[cfg(target_os = "windows")]
-        window.inner_size(),
-        window.scale_factor(),
-    ) else {
-        return;
+/// Whether two versions share their major and minor numbers, as the parts of
+/// one release do.
+fn same_release(a: &str, b: &str) -> bool {
+    let release = |version: &str| -> Vec<String> {
+        version
+            .split(['.', '-'])
+            .take(2)
+            .map(ToString::to_string)
+            .collect()
     };
-
-    let devtools_open = app
-        .get_webview_window(window.label())
-        .map(|w| w.is_devtools_open())
-        .unwrap_or(false);
-    let bounds = WindowBounds::new(pos, size, scale, devtools_open);
-    let bounds = bounds.clamp_min_size();
-    if let Some(parent) = path.parent() {
-        let _ = fs::create_dir_all(parent);
-    }
-    if let Ok(payload) = serde_json::to_string(&bounds) {
-        let _ = fs::write(path, payload);
-    }
+    release(a) == release(b)
 }
 
-fn focused_window(app: &AppHandle) -> Option<WebviewWindow> {
-    app.webview_windows()
-        .values()
-        .find(|window| window.is_focused().unwrap_or(false))
-        .cloned()
+/// The version in the frontend's `package.json`, next to its build or one
+/// folder up as in the npm package.
+fn frontend_version(frontend_dir: &Path) -> Option<String> {
+    [frontend_dir, frontend_dir.parent()?]
+        .iter()
+        .find_map(|dir| fs::read(dir.join("package.json")).ok())
+        .and_then(|contents| serde_json::from_slice::<serde_json::Value>(&contents).ok())
+        .and_then(|package| package.get("version")?.as_str().map(ToString::to_string))
 }
 
-fn next_window_bounds(app: &AppHandle) -> WindowBounds {
-    let (mut bounds, monitor) = focused_window(app)
-        .or_else(|| app.webview_windows().values().next().cloned())
-        .and_then(|w| {
-            let pos = w.outer_position().ok()?;
-            #[cfg(not(target_os = "windows"))]
-            let size = w.outer_size().ok()?;
-            #[cfg(target_os = "windows")]
-            let size = w.inner_size().ok()?;
-            let scale = w.scale_factor().ok()?;
-            let devtools_open = w.is_devtools_open();
-            let bounds =
-                WindowBounds::new(pos, size, scale, devtools_open).with_offset(WINDOW_OFFSET);
-            let monitor = w
-                .current_monitor()
-                .ok()
-                .flatten()
-                .or_else(|| w.primary_monitor().ok().flatten());
-            Some((bounds, monitor))
+/// The first dotted version number in `carta_backend --version` output.
+fn parse_backend_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| {
+            word.contains('.')
+                && word.starts_with(|c: char| c.is_ascii_digit())
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
         })
-        .or_else(|| load_window_bounds(app).map(|bounds| (bounds, None)))
-        .unwrap_or((
-            WindowBounds {
-                width: DEFAULT_WINDOW_WIDTH,
-                height: DEFAULT_WINDOW_HEIGHT,
-                x: 0,
-                y: 0,
-                devtools_open: false,
-            },
-            None,
-        ));
+        .map(ToString::to_string)
+}
 
-    let monitor = monitor.or_else(|| app.primary_monitor().ok().flatten());
-    if let Some(monitor) = monitor {
-        bounds = wrap_window_bounds(bounds, &monitor);
+fn print_version(app: &AppHandle, json: bool) {
+    let report = version_report(app);
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(err) => ExitCode::Failure.exit(err),
+        }
+    } else {
+        print!("{}", report.text());
     }
-    bounds
 }
 
-fn new_window_label() -> String {
-    format!("carta-{}", uuid::Uuid::new_v4())
+fn warn_no_auth(app: &AppHandle, port: u16) {
+    eprintln!("Warning: backend authentication is disabled (--insecure-no-auth)");
+    app.dialog()
+        .message(format!(
+            "CARTA is running without authentication. Anyone who can reach port {} on \
+             this computer can open your session and read any file the backend can access.\n\n\
+             Only use --insecure-no-auth on an isolated, trusted network.",
+            port
+        ))
+        .title("CARTA")
+        .kind(MessageDialogKind::Warning)
+        .show(|_| {});
 }
 
-fn wrap_window_bounds(mut bounds: WindowBounds, monitor: &tauri::window::Monitor) -> WindowBounds {
-    let work_area = monitor.work_area();
-    let monitor_size = monitor.size();
-    let monitor_pos = monitor.position();
-    let scale = monitor.scale_factor();
-
-    // Convert all work area values to logical pixels with floating point precision
-    let work_x = work_area.position.x as f64 / scale;
-    let work_y = work_area.position.y as f64 / scale;
-    let work_width = work_area.size.width as f64 / scale;
-    let work_height = work_area.size.height as f64 / scale;
-
-    // Constrain window size to work area
-    if (bounds.width as f64) > work_width {
-        bounds.width = work_width.round() as u32;
-    }
-    if (bounds.height as f64) > work_height {
-        bounds.height = work_height.round() as u32;
-    }
-
-    // Max boundaries:
-    // On macOS, we use monitor total size because work_area often has
-    // coordinate mismatches that prevent hitting the bottom edge in Tauri 2.0.
-    // On other platforms (Windows/Linux), we MUST use work_area to
-    // avoid overlapping the taskbar (menu bar).
-    let (max_x, max_y) = if cfg!(target_os = "macos") {
-        (
-            (monitor_pos.x + monitor_size.width as i32) as f64 / scale,
-            (monitor_pos.y + monitor_size.height as i32) as f64 / scale,
-        )
-    } else {
-        (work_x + work_width, work_y + work_height)
+/// GTK picks its display backend when it initializes, before settings are
+/// normally loaded, so the preference comes from `startup_settings`. An
+/// explicit flag wins over `GDK_BACKEND`, which wins over the preference.
+#[cfg(target_os = "linux")]
+fn select_display_backend(cli: Option<DisplayBackend>, settings: &Settings) {
+    let backend = match cli {
+        Some(backend) => backend,
+        None if std::env::var_os("GDK_BACKEND").is_some() => return,
+        None => settings.display_backend,
     };
-
-    let bx = bounds.x as f64;
-    let by = bounds.y as f64;
-    let bw = bounds.width as f64;
-    let bh = bounds.height as f64;
-
-    // Use a small epsilon for comparisons to avoid floating point issues
-    let epsilon = 0.1;
-    let hits_x = bx < work_x - epsilon || bx + bw > max_x + epsilon;
-    let hits_y = by < work_y - epsilon || by + bh > max_y + epsilon;
-
-    if hits_x || hits_y {
-        bounds.x = work_x.round() as i32;
-        bounds.y = work_y.round() as i32;
+    trace!("display backend: {:?}", backend);
+    if let Some(value) = backend.gdk_backend() {
+        // SAFETY: called at the start of `run`, before Tauri or GTK start any
+        // threads that could read the environment.
+        unsafe { std::env::set_var("GDK_BACKEND", value) };
     }
-    bounds
 }
 
-// IPC Commands for frontend menu integration
-#[tauri::command]
-fn cmd_new_window(app: AppHandle) {
-    let state = app.state::<AppState>();
-    let _ = create_window(&app, &state, new_window_label(), None);
+/// Settings read at the start of `run`, for the few that must take effect
+/// before the app (and its path resolver) exists.
+fn startup_settings(identifier: &str) -> Settings {
+    let Some(path) = config_dir().map(|dir| dir.join(identifier).join(SETTINGS_FILE)) else {
+        trace!("no config directory; using default settings");
+        return Settings::default();
+    };
+    trace!("settings file: {}", path.display());
+    Settings::load(&path)
 }
 
-#[tauri::command]
-fn cmd_toggle_fullscreen(window: WebviewWindow) {
-    toggle_fullscreen(&window);
+/// The base of Tauri's `app_config_dir`.
+#[cfg(target_os = "linux")]
+fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
 }
 
-#[tauri::command]
-fn cmd_toggle_devtools(window: WebviewWindow) {
-    toggle_devtools(&window);
+#[cfg(target_os = "macos")]
+fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join("Library")
+            .join("Application Support")
+    })
 }
 
-#[tauri::command]
-fn cmd_close_window(window: WebviewWindow) {
-    let _ = window.close();
+#[cfg(target_os = "windows")]
+fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(PathBuf::from)
 }
 
-#[tauri::command]
-fn cmd_quit_app(app: AppHandle) {
-    let state = app.state::<AppState>();
-    shutdown_backend(&state);
-    app.exit(0);
+/// The launch profile `name`, or why there is none: a mistyped name should not
+/// quietly start the bundled components.
+fn launch_profile<'a>(
+    profiles: &'a BTreeMap<String, LaunchProfile>,
+    name: &str,
+) -> Result<&'a LaunchProfile, String> {
+    profiles.get(name).ok_or_else(|| {
+        if profiles.is_empty() {
+            format!(
+                "No launch profile named {}; the settings file has none",
+                name
+            )
+        } else {
+            format!(
+                "No launch profile named {} (configured: {})",
+                name,
+                profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        }
+    })
 }
 
-#[cfg(target_os = "macos")]
-fn build_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<tauri::menu::Menu<R>> {
-    let new_window = MenuItem::with_id(
-        app,
-        MENU_NEW_WINDOW,
-        "New CARTA Window",
-        true,
-        Some("Cmd+N"),
-    )?;
-    let toggle_fullscreen = MenuItem::with_id(
-        app,
-        MENU_TOGGLE_FULLSCREEN,
-        "Toggle Fullscreen",
-        true,
-        Some("Ctrl+Cmd+F"),
-    )?;
-    let toggle_devtools = MenuItem::with_id(
-        app,
-        MENU_TOGGLE_DEVTOOLS,
-        "Toggle DevTools",
-        true,
-        Some("Alt+Cmd+I"),
-    )?;
-
-    let app_menu = SubmenuBuilder::new(app, &app.package_info().name)
-        .item(&new_window)
-        .separator()
-        .item(&toggle_fullscreen)
-        .separator()
-        .item(&toggle_devtools)
-        .separator()
-        .close_window()
-        .quit()
-        .build()?;
-
-    MenuBuilder::new(app)
-        .item(&app_menu)
-        .build()
+/// Points resource lookups at the profile's components and adds its backend
+/// flags ahead of the command line's, so explicit flags and the
+/// `CARTA_*_DIR` variables still win.
+fn apply_launch_profile(name: &str, profile: &LaunchProfile, extra_args: &mut Vec<String>) {
+    eprintln!("Using launch profile {}", name);
+    trace!("launch profile {}: {:?}", name, profile);
+    for (var, dir) in [
+        (ENV_BACKEND_DIR, &profile.backend_dir),
+        (ENV_FRONTEND_DIR, &profile.frontend_dir),
+    ] {
+        if let Some(dir) = dir
+            && resource_override(var).is_none()
+        {
+            // SAFETY: called at the start of `run`, before Tauri starts any
+            // threads that could read the environment.
+            unsafe { std::env::set_var(var, dir) };
+        }
+    }
+    extra_args.splice(0..0, profile.backend_args.iter().cloned());
 }
 
-fn toggle_devtools(window: &WebviewWindow) {
-    if window.is_devtools_open() {
-        window.close_devtools();
-    } else {
-        window.open_devtools();
+/// Asks the kernel to send SIGTERM to the backend when the launcher dies, so a
+/// SIGKILLed launcher does not leave the backend running and holding the port.
+/// The signal fires when the spawning thread exits, so the backend must be
+/// spawned from a thread that lives as long as the app (the main thread).
+#[cfg(target_os = "linux")]
+fn set_parent_death_signal(cmd: &mut Command) {
+    let launcher_pid = std::process::id();
+    // SAFETY: the closure runs in the forked child before exec and only calls
+    // async-signal-safe functions (prctl, getppid).
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            // The launcher may have died between fork and prctl.
+            if libc::getppid() as u32 != launcher_pid {
+                return Err(io::Error::other("launcher exited before backend started"));
+            }
+            Ok(())
+        });
     }
-    save_window_bounds(window.app_handle(), &window.as_ref().window());
 }
 
-fn toggle_fullscreen(window: &WebviewWindow) {
-    let next_state = !window.is_fullscreen().unwrap_or(false);
-    let _ = window.set_fullscreen(next_state);
+/// Starts the backend at reduced CPU priority, and on Linux reduced I/O
+/// priority, as `nice` and `ionice` would.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn lower_priority(cmd: &mut Command) {
+    // SAFETY: the closure runs in the forked child before exec and only makes
+    // system calls (setpriority, ioprio_set), which are async-signal-safe.
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setpriority(libc::PRIO_PROCESS as _, 0, BACKEND_NICENESS) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            #[cfg(target_os = "linux")]
+            {
+                // Best-effort class at its lowest level; the idle class could
+                // stall image loads for as long as other jobs read the disk.
+                const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+                const IOPRIO_BEST_EFFORT_LOWEST: libc::c_int = (2 << 13) | 7;
+                // Kernels without I/O priorities still get the CPU priority.
+                libc::syscall(
+                    libc::SYS_ioprio_set,
+                    IOPRIO_WHO_PROCESS,
+                    0,
+                    IOPRIO_BEST_EFFORT_LOWEST,
+                );
+            }
+            Ok(())
+        });
+    }
 }
 
-fn create_window(
-    app: &AppHandle,
-    state: &AppState,
-    label: String,
-    window_url: Option<&str>,
-) -> tauri::Result<WebviewWindow> {
-    let bounds = next_window_bounds(app);
-    let label_for_state = label.clone();
-    let url = window_url
-        .map(ToString::to_string)
-        .unwrap_or_else(|| state.window_url.clone());
-    let url = WebviewUrl::App(url.into());
-    let mut builder = WebviewWindowBuilder::new(app, label, url)
-        .title(WINDOW_TITLE)
-        .inner_size(bounds.width as f64, bounds.height as f64)
-        .position(bounds.x as f64, bounds.y as f64);
+/// Kill-on-close Job Object that owns the backend process tree. The handle is
+/// held in `AppState` for the lifetime of the app; if the launcher exits
+/// abnormally, Windows closes the handle and terminates every process in the job.
+#[cfg(target_os = "windows")]
+struct BackendJob(windows_sys::Win32::Foundation::HANDLE);
 
-    #[cfg(target_os = "macos")]
-    {
-        let menu = build_menu(app)?;
-        builder = builder.menu(menu);
-    }
+// SAFETY: a job handle is a process-wide kernel object reference that may be
+// used and closed from any thread.
+#[cfg(target_os = "windows")]
+unsafe impl Send for BackendJob {}
+#[cfg(target_os = "windows")]
+unsafe impl Sync for BackendJob {}
 
-    // Disable browser context menu (reload, inspect element, etc.)
-    builder = builder.initialization_script(
-        r#"document.addEventListener('contextmenu', function(e) { e.preventDefault(); });"#,
-    );
+#[cfg(target_os = "windows")]
+impl BackendJob {
+    fn new() -> AppResult<Self> {
+        use windows_sys::Win32::System::JobObjects::{
+            CreateJobObjectW, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+            JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectExtendedLimitInformation,
+            SetInformationJobObject,
+        };
 
-    let window = builder.build()?;
+        // SAFETY: null attributes and name create an anonymous job object.
+        let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+        if handle.is_null() {
+            return Err(AppError::Other(format!(
+                "Failed to create backend job object: {}",
+                io::Error::last_os_error()
+            )));
+        }
+        let job = Self(handle);
+
+        let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        // SAFETY: `info` is a valid JOBOBJECT_EXTENDED_LIMIT_INFORMATION and the
+        // length matches its size.
+        let ok = unsafe {
+            SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const std::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        };
+        if ok == 0 {
+            return Err(AppError::Other(format!(
+                "Failed to configure backend job object: {}",
+                io::Error::last_os_error()
+            )));
+        }
+        Ok(job)
+    }
 
-    let _ = window.set_size(tauri::Size::Logical(tauri::LogicalSize::new(
-        bounds.width as f64,
-        bounds.height as f64,
-    )));
+    fn assign(&self, child: &Child) -> AppResult<()> {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::System::JobObjects::AssignProcessToJobObject;
 
-    {
-        let mut labels = state.window_order.lock().unwrap();
-        if !labels.iter().any(|item| item == &label_for_state) {
-            labels.push(label_for_state);
+        // SAFETY: both handles are valid for the duration of the call.
+        let ok = unsafe { AssignProcessToJobObject(self.0, child.as_raw_handle()) };
+        if ok == 0 {
+            return Err(AppError::Other(format!(
+                "Failed to assign backend to job object: {}",
+                io::Error::last_os_error()
+            )));
         }
+        Ok(())
     }
-    if state.inspect || bounds.devtools_open {
-        window.open_devtools();
-    }
-    Ok(window)
 }
 
-#[cfg(target_os = "macos")]
-fn handle_menu_event(app: &AppHandle, state: &AppState, event: tauri::menu::MenuEvent) {
-    match event.id().as_ref() {
-        MENU_NEW_WINDOW => {
-            let _ = create_window(app, state, new_window_label(), None);
+#[cfg(target_os = "windows")]
+impl Drop for BackendJob {
+    fn drop(&mut self) {
+        // SAFETY: the handle was returned by CreateJobObjectW and is closed once.
+        unsafe {
+            windows_sys::Win32::Foundation::CloseHandle(self.0);
         }
-        MENU_TOGGLE_FULLSCREEN => {
-            if let Some(window) = focused_window(app) {
-                toggle_fullscreen(&window);
+    }
+}
+
+/// Places a freshly spawned backend into the app's kill-on-close job, creating
+/// the job on first use. Failure is not fatal: shutdown then falls back to
+/// `child.kill()` alone.
+#[cfg(target_os = "windows")]
+fn attach_backend_job(state: &AppState, child: &Child) {
+    let mut job = state.backend_job.lock().unwrap();
+    if job.is_none() {
+        match BackendJob::new() {
+            Ok(created) => *job = Some(created),
+            Err(err) => {
+                eprintln!("Warning: {}", err);
+                return;
             }
         }
-        MENU_TOGGLE_DEVTOOLS => {
-            if let Some(window) =
-                focused_window(app)
-            {
+    }
+    if let Some(job) = job.as_ref()
+        && let Err(err) = job.assign(child)
+    {
+        eprintln!("Warning: {}", err);
+    }
+}
+
+/// Forwards the backend's output to the launcher's own and keeps it in `log`.
+/// Each stream is a task that ends when the backend closes it.
+fn pipe_output(child: &mut Child, log: Arc<supervisor::BackendLog>) {
+    // The pipes can only be registered with the runtime from inside it.
+    if let Some(stdout) = child.stdout.take() {
+        let log = log.clone();
+        tauri::async_runtime::spawn(async move {
+            match tokio::process::ChildStdout::from_std(stdout) {
+                Ok(stdout) => forward_lines(stdout, OutputStream::Stdout, log).await,
+                Err(err) => eprintln!("Warning: failed to read backend output: {}", err),
+            }
+        });
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tauri::async_runtime::spawn(async move {
+            match tokio::process::ChildStderr::from_std(stderr) {
+                Ok(stderr) => forward_lines(stderr, OutputStream::Stderr, log).await,
+                Err(err) => eprintln!("Warning: failed to read backend output: {}", err),
+            }
+        });
+    }
+}
+
+async fn forward_lines(
+    reader: impl AsyncRead + Unpin,
+    stream: OutputStream,
+    log: Arc<supervisor::BackendLog>,
+) {
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        match stream {
+            OutputStream::Stdout => println!("{}", line),
+            OutputStream::Stderr => eprintln!("{}", line),
+        }
+        log.push(stream, line);
+    }
+}
+
+fn spawn_backend(
+    app: &AppHandle,
+    state: &AppState,
+    base_dir: &Path,
+    extra_args: &[String],
+) -> AppResult<()> {
+    let child = spawn_backend_process(
+        app,
+        state,
+        state.backend_log.clone(),
+        state.backend_port(),
+        &state.backend_token,
+        base_dir,
+        extra_args,
+    )?;
+    *state.backend.lock().unwrap() = Some(child);
+    Ok(())
+}
+
+/// Starts a backend serving `base_dir` on `port`. Used for the launch backend
+/// and for additional per-folder backends.
+fn spawn_backend_process(
+    app: &AppHandle,
+    state: &AppState,
+    log: Arc<supervisor::BackendLog>,
+    port: u16,
+    token: &str,
+    base_dir: &Path,
+    extra_args: &[String],
+) -> AppResult<Child> {
+    let mut cmd = backend_command(app, state, port, token, base_dir, extra_args)?;
+    if trace::enabled() {
+        trace!(
+            "spawning backend: {}",
+            command_line(&cmd).replace(token, AUTH_TOKEN_PLACEHOLDER)
+        );
+        for (name, value) in cmd.get_envs() {
+            let value = value.map(|value| {
+                value
+                    .to_string_lossy()
+                    .replace(token, AUTH_TOKEN_PLACEHOLDER)
+            });
+            trace!("  {}={}", name.to_string_lossy(), value.unwrap_or_default());
+        }
+    }
+    let mut child = state.processes.spawn(&mut cmd)?;
+    trace!("backend pid {}", child.id());
+    #[cfg(target_os = "windows")]
+    attach_backend_job(state, &child);
+
+    pipe_output(&mut child, log);
+
+    Ok(child)
+}
+
+/// The user's `CARTA_*` variables, then `overrides`, for a backend that does
+/// not inherit the launcher's environment. Variables the launcher sets or
+/// reads for itself are left out.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn forwarded_env(
+    vars: impl Iterator<Item = (String, String)>,
+    overrides: &[(String, String)],
+) -> Vec<(String, String)> {
+    let launcher_vars = [
+        ENV_AUTH_TOKEN,
+        ENV_BACKEND_DIR,
+        ENV_FRONTEND_DIR,
+        ENV_CASA_DATA,
+        ENV_WSL_DISTRO,
+    ];
+    let mut env: Vec<(String, String)> = vars
+        .filter(|(name, _)| {
+            name.starts_with(ENV_PREFIX)
+                && is_env_name(name)
+                && !launcher_vars.contains(&name.as_str())
+        })
+        .filter(|(name, _)| !overrides.iter().any(|(overridden, _)| overridden == name))
+        .collect();
+    env.sort();
+    env.extend(overrides.iter().cloned());
+    env
+}
+
+/// `WSLENV` with `name` added, so `wsl.exe` passes that Windows variable on
+/// into the distribution unchanged.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn wslenv_with(existing: Option<&str>, name: &str) -> String {
+    existing
+        .unwrap_or_default()
+        .split(':')
+        .filter(|entry| !entry.is_empty() && entry.split('/').next() != Some(name))
+        .chain(std::iter::once(name))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Browsers on the LAN still load the frontend from the backend.
+fn backend_serves_frontend(state: &AppState) -> bool {
+    !state.frontend_from_app || state.share_lan
+}
+
+/// The backend command line and environment, ready to spawn. On Windows this
+/// is a `wsl.exe` call whose last argument is the bash script.
+fn backend_command(
+    app: &AppHandle,
+    state: &AppState,
+    port: u16,
+    token: &str,
+    base_dir: &Path,
+    extra_args: &[String],
+) -> AppResult<Command> {
+    let resource_dir = resolve_resource_dir(app).ok_or(AppError::ResourceDirNotFound)?;
+    let mut extra_args = extra_args.to_vec();
+    if state.read_only.load(Ordering::Relaxed) {
+        extra_args.push(READ_ONLY_ARG.to_string());
+    }
+    let (timeouts, limits) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.backend_timeouts, settings.backend_limits)
+    };
+    let timeout_args = timeouts.args(&extra_args);
+    extra_args.extend(timeout_args);
+    let limit_args = limits.args(&extra_args);
+    extra_args.extend(limit_args);
+
+    #[cfg(target_os = "windows")]
+    {
+        let backend_path = resolve_backend_path(&resource_dir)?;
+
+        // Convert Windows paths to WSL paths directly in Rust
+        let backend = to_wsl_path_str(&backend_path.to_string_lossy())?;
+        let frontend_arg = if backend_serves_frontend(state) {
+            let frontend_path = resolve_frontend_path(&resource_dir)?;
+            let frontend = to_wsl_path_str(&frontend_path.to_string_lossy())?;
+            format!("--frontend_folder={}", bash_escape(&frontend))
+        } else {
+            NO_FRONTEND_ARG.to_string()
+        };
+        let base = to_wsl_path_str(&base_dir.to_string_lossy())?;
+        let casa_path = resolve_casa_path(&resource_dir)?;
+        let normalized_extra_args = normalize_backend_args_for_wsl(&extra_args)?;
+
+        // Libs directory for LD_LIBRARY_PATH
+        let libs_path =
+            resolve_libs_path(&resource_dir).and_then(|p| win_to_wsl_path(&p.to_string_lossy()));
+        let extra = normalized_extra_args
+            .iter()
+            .map(|arg| bash_escape(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let backend_escaped = bash_escape(&backend);
+        let base_escaped = bash_escape(&base);
+        let casa_path_escaped = bash_escape(&casa_path);
+
+        let ld_export = libs_path
+            .map(|p| {
+                format!(
+                    "export LD_LIBRARY_PATH={}:$LD_LIBRARY_PATH; ",
+                    bash_escape(&p)
+                )
+            })
+            .unwrap_or_default();
+
+        // WSL does not pass on Windows environment variables by itself.
+        let env_exports = forwarded_env(std::env::vars(), &state.backend_env)
+            .iter()
+            .map(|(name, value)| format!("export {}={}; ", name, bash_escape(value)))
+            .collect::<String>();
+
+        // `nice` execs the backend in turn, keeping the process the same.
+        let nice = if limits.low_priority {
+            format!("nice -n {} ", BACKEND_NICENESS)
+        } else {
+            String::new()
+        };
+        let command = format!(
+            "{ld_export}{env_exports}export {ENV_CASAPATH}={casa_path_escaped}; exec {nice}{backend_escaped} {base_escaped} --port={port} {frontend_arg} --no_browser {extra}"
+        );
+
+        trace!(
+            "WSL distro: {}",
+            wsl_distro().as_deref().unwrap_or("(default)")
+        );
+        let mut cmd = wsl_shell_command(&*state.processes, &command);
+        // The token goes through the environment rather than the command
+        // line, which other users can list.
+        if !state.no_auth {
+            let wslenv = std::env::var("WSLENV").ok();
+            cmd.env(ENV_AUTH_TOKEN, token)
+                .env("WSLENV", wslenv_with(wslenv.as_deref(), ENV_AUTH_TOKEN));
+        }
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .creation_flags(CREATE_NO_WINDOW);
+        Ok(cmd)
+    }
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        let (backend_path, bundled) =
+            resolve_backend_binary(&resource_dir, state.settings.lock().unwrap().backend_choice)?;
+
+        let mut cmd = Command::new(&backend_path);
+        cmd.arg(base_dir).arg(format!("--port={}", port));
+        // A system backend finds its own frontend and CASA data when the
+        // bundle lacks them.
+        if !backend_serves_frontend(state) {
+            cmd.arg(NO_FRONTEND_ARG);
+        } else {
+            match resolve_frontend_path(&resource_dir) {
+                Ok(frontend_path) => {
+                    cmd.arg(format!("--frontend_folder={}", frontend_path.display()));
+                }
+                Err(err) if bundled => return Err(err),
+                Err(_) => {}
+            }
+        }
+        cmd.arg("--no_browser")
+            .args(&extra_args)
+            .envs(state.backend_env.iter().cloned())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if !state.no_auth {
+            cmd.env(ENV_AUTH_TOKEN, token);
+        }
+
+        match resolve_casa_path(&resource_dir) {
+            Ok(casa_path) => {
+                cmd.env(ENV_CASAPATH, casa_path);
+            }
+            Err(err) if bundled => return Err(err),
+            Err(_) => {}
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(libs_dir) = resolve_libs_path(&resource_dir).filter(|_| bundled) {
+                let mut ld_library_path = libs_dir.to_string_lossy().into_owned();
+                if let Ok(existing) = std::env::var("LD_LIBRARY_PATH")
+                    && !existing.trim().is_empty()
+                {
+                    ld_library_path.push(':');
+                    ld_library_path.push_str(existing.trim());
+                }
+                cmd.env("LD_LIBRARY_PATH", ld_library_path);
+            }
+            set_parent_death_signal(&mut cmd);
+        }
+        if limits.low_priority {
+            lower_priority(&mut cmd);
+        }
+
+        Ok(cmd)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        let _ = (app, state, port, token, base_dir, extra_args, limits);
+        Err(AppError::UnsupportedPlatform)
+    }
+}
+
+/// Prints what `spawn_backend` would run, with a placeholder for the auth
+/// token, so backend startup can be reproduced by hand.
+fn print_backend_command(
+    app: &AppHandle,
+    state: &AppState,
+    base_dir: &Path,
+    extra_args: &[String],
+) -> AppResult<()> {
+    print!(
+        "{}",
+        backend_command_report(app, state, base_dir, extra_args)?
+    );
+    Ok(())
+}
+
+/// The launch backend's command line for bug reports, as `--print-backend-command`
+/// shows it.
+fn copy_backend_command(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let extra_args = state.backend_args.lock().unwrap().clone();
+    let report = match backend_command_report(app, &state, &state.base_dir, &extra_args) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("Warning: failed to build the backend command: {}", err);
+            return;
+        }
+    };
+    if let Err(err) = app.clipboard().write_text(report) {
+        eprintln!("Warning: failed to copy the backend command: {}", err);
+    }
+}
+
+fn backend_command_report(
+    app: &AppHandle,
+    state: &AppState,
+    base_dir: &Path,
+    extra_args: &[String],
+) -> AppResult<String> {
+    use std::fmt::Write as _;
+
+    let cmd = backend_command(
+        app,
+        state,
+        state.backend_port(),
+        AUTH_TOKEN_PLACEHOLDER,
+        base_dir,
+        extra_args,
+    )?;
+    let envs: Vec<String> = cmd
+        .get_envs()
+        .filter_map(|(name, value)| {
+            Some(format!(
+                "{}={}",
+                name.to_string_lossy(),
+                shell_quote(&value?.to_string_lossy())
+            ))
+        })
+        .collect();
+    let mut report = String::new();
+    if !envs.is_empty() {
+        let _ = writeln!(report, "Environment:");
+        for env in envs {
+            let _ = writeln!(report, "  {}", env);
+        }
+    }
+    #[cfg(target_os = "windows")]
+    if let Some(script) = cmd.get_args().last() {
+        let _ = writeln!(report, "WSL script:");
+        let _ = writeln!(report, "  {}", script.to_string_lossy());
+    }
+    let _ = writeln!(report, "Command:");
+    let _ = writeln!(report, "  {}", command_line(&cmd));
+    Ok(report)
+}
+
+fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| shell_quote(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The address backends listen on, from `--bind-address` or the settings.
+static BIND_ADDRESS: std::sync::OnceLock<IpAddr> = std::sync::OnceLock::new();
+
+/// Where the app reaches its backends: 127.0.0.1 unless configured.
+pub(crate) fn bind_address() -> IpAddr {
+    BIND_ADDRESS
+        .get()
+        .copied()
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+/// The last `--host` value in backend arguments, as the backend takes it.
+fn host_arg(args: &[String]) -> Option<&str> {
+    let mut host = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--host" {
+            host = iter.next().map(String::as_str);
+        } else if let Some(value) = arg.strip_prefix("--host=") {
+            host = Some(value);
+        }
+    }
+    host
+}
+
+/// Where the app reaches a backend started with `--host=host`: that address,
+/// or `None` for the default when it is a name or all interfaces.
+fn reach_address(host: &str) -> Option<IpAddr> {
+    host.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .ok()
+        .filter(|address| !address.is_unspecified())
+}
+
+/// The host of backend URLs: `localhost` for the default address, so page
+/// origins stay the same as before it could be changed.
+pub(crate) fn backend_host() -> String {
+    match bind_address() {
+        IpAddr::V4(Ipv4Addr::LOCALHOST) => "localhost".to_string(),
+        IpAddr::V4(address) => address.to_string(),
+        IpAddr::V6(address) => format!("[{}]", address),
+    }
+}
+
+/// Pages from a backend on an address `capabilities/default.json` does not
+/// list get the same permissions, so they can still call the app.
+fn allow_backend_host(app: &AppHandle) -> tauri::Result<()> {
+    let host = backend_host();
+    if matches!(host.as_str(), "localhost" | "127.0.0.1" | "[::1]") {
+        return Ok(());
+    }
+    let default: serde_json::Value =
+        serde_json::from_str(include_str!("../capabilities/default.json"))?;
+    let mut capability = tauri::ipc::CapabilityBuilder::new("bind-address")
+        // `:` starts a named group in URL patterns.
+        .remote(format!("http://{}:*/*", host.replace(':', "\\:")))
+        .window("*");
+    for permission in default["permissions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(serde_json::Value::as_str)
+    {
+        capability = capability.permission(permission);
+    }
+    app.add_capability(capability)
+}
+
+/// Whether nothing is listening on `port` at the bind address yet.
+fn port_is_free(port: u16) -> bool {
+    std::net::TcpListener::bind(SocketAddr::new(bind_address(), port)).is_ok()
+}
+
+/// Whether `port` is free where the backend listens: on Windows, inside WSL
+/// as well, whose services the Windows side does not always see.
+#[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+fn backend_port_is_free(processes: &dyn ProcessRunner, port: u16) -> bool {
+    #[cfg(target_os = "windows")]
+    if wsl_listening_ports(processes).contains(&port) {
+        return false;
+    }
+    port_is_free(port)
+}
+
+/// A port for a backend, free on both sides on Windows.
+#[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+pub(crate) fn pick_backend_port(processes: &dyn ProcessRunner) -> Option<u16> {
+    #[cfg(target_os = "windows")]
+    {
+        let in_wsl = wsl_listening_ports(processes);
+        (0..PORT_PICK_ATTEMPTS)
+            .filter_map(|_| portpicker::pick_unused_port())
+            .find(|port| !in_wsl.contains(port))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        portpicker::pick_unused_port()
+    }
+}
+
+/// Ports something listens on inside the WSL distribution, or none when it
+/// cannot be asked.
+#[cfg(target_os = "windows")]
+fn wsl_listening_ports(processes: &dyn ProcessRunner) -> HashSet<u16> {
+    let mut cmd = Command::new("wsl.exe");
+    add_wsl_distro(&mut cmd);
+    // `cat` fails on a kernel without IPv6 but still prints the IPv4 table.
+    cmd.args(["--exec", "cat", "/proc/net/tcp", "/proc/net/tcp6"])
+        .creation_flags(CREATE_NO_WINDOW);
+    match processes.output(&mut cmd) {
+        Ok(output) => listening_ports(&String::from_utf8_lossy(&output.stdout)),
+        Err(err) => {
+            eprintln!("Warning: failed to list the ports in use in WSL: {}", err);
+            HashSet::new()
+        }
+    }
+}
+
+/// The local ports of sockets in the LISTEN state in `/proc/net/tcp` tables.
+#[cfg(any(target_os = "windows", test))]
+fn listening_ports(tables: &str) -> HashSet<u16> {
+    const LISTEN: &str = "0A";
+    tables
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (local, state) = (fields.get(1)?, fields.get(3)?);
+            if *state != LISTEN {
+                return None;
+            }
+            u16::from_str_radix(local.rsplit(':').next()?, 16).ok()
+        })
+        .collect()
+}
+
+/// Moves the launch backend to a free port when another program took its port
+/// while it was down, so that it can start again. Windows follow it in
+/// `supervisor::reload_windows`. On Windows, startup calls it too, once WSL
+/// can be asked about the port picked before.
+fn free_launch_port(state: &AppState) -> AppResult<()> {
+    let port = state.backend_port();
+    if backend_port_is_free(&*state.processes, port) {
+        return Ok(());
+    }
+    let new_port = pick_backend_port(&*state.processes).ok_or(AppError::NoFreePort)?;
+    eprintln!(
+        "Warning: port {} is in use; moving the backend to port {}",
+        port, new_port
+    );
+    state.backend_port.store(new_port, Ordering::Relaxed);
+    let mut window_url = state.window_url.lock().unwrap();
+    if let Ok(url) = tauri::Url::parse(&window_url) {
+        *window_url = frontend_protocol::with_backend_port(&url, new_port).to_string();
+    }
+    Ok(())
+}
+
+async fn wait_for_backend(state: &AppState, timeout: Duration) -> AppResult<()> {
+    wait_for_port(&state.backend, state.backend_port(), timeout).await
+}
+
+/// Waits until a backend accepts connections on `port`, failing early if its
+/// process exits.
+async fn wait_for_port(
+    backend: &Mutex<Option<Child>>,
+    port: u16,
+    timeout: Duration,
+) -> AppResult<()> {
+    let addr = SocketAddr::new(bind_address(), port);
+    let start = Instant::now();
+    let mut last_error: Option<io::Error> = None;
+    let mut attempts = 0u32;
+
+    while start.elapsed() < timeout {
+        // Check if backend process is still running.
+        // On Windows, this checks wsl.exe which exits when the inner carta_backend exits.
+        if let Some(ref mut child) = *backend.lock().unwrap()
+            && let Ok(Some(status)) = child.try_wait()
+        {
+            return Err(AppError::BackendExited(status));
+        }
+
+        attempts += 1;
+        let connected = tokio::time::timeout(
+            Duration::from_millis(CONNECT_TIMEOUT_MS),
+            tokio::net::TcpStream::connect(addr),
+        )
+        .await
+        .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()));
+        match connected {
+            Ok(_) => {
+                trace!(
+                    "port {} ready after {} attempts ({:.1}s)",
+                    port,
+                    attempts,
+                    start.elapsed().as_secs_f64()
+                );
+                return Ok(());
+            }
+            Err(err) => {
+                // Only changes are logged; polling runs ten times a second.
+                if last_error.as_ref().map(io::Error::kind) != Some(err.kind()) {
+                    trace!("port {} attempt {}: {}", port, attempts, err);
+                }
+                last_error = Some(err);
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(CONNECT_RETRY_MS)).await;
+    }
+
+    Err(AppError::BackendTimeout {
+        port,
+        secs: timeout.as_secs(),
+        detail: last_error.map(|err| err.to_string()),
+    })
+}
+
+fn window_state_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join(WINDOW_STATE_FILE))
+}
+
+/// Writes `contents` to a unique temp file next to `path` and renames it into
+/// place, so a crash mid-write never leaves a truncated file behind.
+fn write_file_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_atomic(path, contents, false)
+}
+
+/// `write_file_atomic` for files holding a secret, which on Unix only their
+/// owner can read.
+fn write_private_file_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_atomic(path, contents, true)
+}
+
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn write_atomic(path: &Path, contents: &[u8], private: bool) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name,
+        uuid::Uuid::new_v4().simple()
+    ));
+
+    let result = (|| {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        if private {
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        }
+        let mut file = options.open(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+fn window_state_version(value: &serde_json::Value) -> u64 {
+    value
+        .get("version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0)
+}
+
+/// Upgrades a parsed state document to the current schema one version at a time.
+/// Version 0 is the original unversioned file: a bare `WindowBounds` object.
+/// Version 1 had no bounds per monitor setup.
+fn migrate_window_state(mut value: serde_json::Value) -> serde_json::Result<WindowState> {
+    if window_state_version(&value) < 1 {
+        value = serde_json::json!({ "version": 1, "bounds": value });
+    }
+    if window_state_version(&value) < 2 {
+        value["version"] = 2.into();
+        value["setups"] = serde_json::json!({});
+    }
+    serde_json::from_value(value)
+}
+
+/// Whether the file at `path` was written by a newer launcher, which
+/// `save_window_bounds` then leaves alone.
+fn is_newer_window_state(path: &Path) -> bool {
+    fs::read(path)
+        .ok()
+        .and_then(|contents| serde_json::from_slice::<serde_json::Value>(&contents).ok())
+        .is_some_and(|value| window_state_version(&value) > WINDOW_STATE_VERSION)
+}
+
+fn read_window_state(path: &Path) -> Option<WindowState> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+        Err(err) => {
+            eprintln!("Warning: failed to read {}: {}", path.display(), err);
+            return None;
+        }
+    };
+
+    let parsed = serde_json::from_slice::<serde_json::Value>(&contents).and_then(|value| {
+        if window_state_version(&value) > WINDOW_STATE_VERSION {
+            Ok(None)
+        } else {
+            migrate_window_state(value).map(Some)
+        }
+    });
+
+    match parsed {
+        Ok(Some(mut state)) => {
+            state.bounds = state.bounds.clamp_min_size();
+            for bounds in state.setups.values_mut() {
+                *bounds = std::mem::take(bounds).clamp_min_size();
+            }
+            Some(state)
+        }
+        Ok(None) => {
+            // Written by a newer launcher; kept intact in case of a downgrade,
+            // since `save_window_bounds` does not overwrite it either.
+            eprintln!(
+                "Warning: {} was written by a newer version, using default window state",
+                path.display()
+            );
+            None
+        }
+        Err(err) => {
+            // Replace the corrupt file so the next save/load cycle starts clean
+            // instead of ignoring the stored state forever.
+            eprintln!(
+                "Warning: {} is corrupt ({}), resetting window state",
+                path.display(),
+                err
+            );
+            let defaults = WindowState::new(WindowBounds::default());
+            if let Ok(payload) = serde_json::to_vec(&defaults) {
+                let _ = write_file_atomic(path, &payload);
+            }
+            Some(defaults)
+        }
+    }
+}
+
+fn load_window_bounds(app: &AppHandle) -> Option<WindowBounds> {
+    let path = window_state_path(app)?;
+    let setup = current_monitor_setup(app);
+    read_window_state(&path).map(|state| state.bounds_for(setup.as_deref()))
+}
+
+fn save_window_bounds(app: &AppHandle, window: &Window) {
+    let Some(path) = window_state_path(app) else {
+        return;
+    };
+    if is_newer_window_state(&path) {
+        return;
+    }
+    let (Ok(pos), Ok(size), Ok(scale)) = (
+        window.outer_position(),
+        #[cfg(not(target_os = "windows"))]
+        window.outer_size(),
+        #[cfg(target_os = "windows")]
+        window.inner_size(),
+        window.scale_factor(),
+    ) else {
+        return;
+    };
+
+    let devtools_open = app
+        .get_webview_window(window.label())
+        .map(|w| w.is_devtools_open())
+        .unwrap_or(false);
+    let bounds = WindowBounds::new(pos, size, scale, devtools_open).clamp_min_size();
+    let mut state = read_window_state(&path).unwrap_or_else(|| WindowState::new(bounds.clone()));
+    if let Some(setup) = current_monitor_setup(app) {
+        state.setups.insert(setup, bounds.clone());
+    }
+    state.bounds = bounds;
+    if let Ok(payload) = serde_json::to_vec(&state) {
+        let _ = write_file_atomic(&path, &payload);
+    }
+}
+
+/// Debounces window-state writes so that a burst of Moved/Resized events only
+/// saves once, shortly after the last event. A single worker thread is spawned
+/// per window and burst and keeps sleeping while new events push the deadline
+/// out, so windows moved at the same time are each saved.
+fn schedule_save_window_bounds(app: &AppHandle, window: &Window) {
+    let state = app.state::<AppState>();
+    let label = window.label().to_string();
+    let deadline = Instant::now() + Duration::from_millis(WINDOW_STATE_SAVE_DEBOUNCE_MS);
+    let already_pending = state
+        .pending_bounds_save
+        .lock()
+        .unwrap()
+        .insert(label.clone(), deadline)
+        .is_some();
+    if already_pending {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        loop {
+            let state = app.state::<AppState>();
+            let mut pending = state.pending_bounds_save.lock().unwrap();
+            let Some(&deadline) = pending.get(&label) else {
+                return;
+            };
+            let now = Instant::now();
+            if now < deadline {
+                drop(pending);
+                std::thread::sleep(deadline - now);
+                continue;
+            }
+            pending.remove(&label);
+            drop(pending);
+            if let Some(window) = app.get_webview_window(&label) {
+                save_window_bounds(&app, &window.as_ref().window());
+            }
+            return;
+        }
+    });
+}
+
+fn settings_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join(SETTINGS_FILE))
+}
+
+fn load_settings(app: &AppHandle, state: &AppState) {
+    if let Some(path) = settings_path(app) {
+        *state.settings.lock().unwrap() = Settings::load(&path);
+    }
+    let settings = state.settings.lock().unwrap().clone();
+    *state.proxy.lock().unwrap() = proxy::Proxy::from_settings(&settings.proxy);
+    state
+        .disable_gpu
+        .fetch_or(settings.disable_gpu, Ordering::Relaxed);
+}
+
+/// Applies `update` to the in-memory settings and persists the result.
+fn update_settings(app: &AppHandle, update: impl FnOnce(&mut Settings)) {
+    let state = app.state::<AppState>();
+    let snapshot = {
+        let mut settings = state.settings.lock().unwrap();
+        update(&mut settings);
+        settings.clone()
+    };
+    if let Some(path) = settings_path(app)
+        && let Err(err) = snapshot.save(&path)
+    {
+        eprintln!("Warning: failed to save {}: {}", path.display(), err);
+    }
+}
+
+fn focused_window(app: &AppHandle) -> Option<WebviewWindow> {
+    app.webview_windows()
+        .values()
+        .find(|window| window.is_focused().unwrap_or(false))
+        .cloned()
+}
+
+fn next_window_bounds(app: &AppHandle) -> WindowBounds {
+    let (mut bounds, monitor) = focused_window(app)
+        .or_else(|| app.webview_windows().values().next().cloned())
+        .and_then(|w| {
+            let pos = w.outer_position().ok()?;
+            #[cfg(not(target_os = "windows"))]
+            let size = w.outer_size().ok()?;
+            #[cfg(target_os = "windows")]
+            let size = w.inner_size().ok()?;
+            let scale = w.scale_factor().ok()?;
+            let devtools_open = w.is_devtools_open();
+            let bounds =
+                WindowBounds::new(pos, size, scale, devtools_open).with_offset(WINDOW_OFFSET);
+            let monitor = w
+                .current_monitor()
+                .ok()
+                .flatten()
+                .or_else(|| w.primary_monitor().ok().flatten());
+            Some((bounds, monitor))
+        })
+        .or_else(|| load_window_bounds(app).map(|bounds| (bounds, None)))
+        .unwrap_or_else(|| (WindowBounds::default(), None));
+
+    let monitor = monitor.or_else(|| app.primary_monitor().ok().flatten());
+    if let Some(monitor) = monitor {
+        bounds = wrap_window_bounds(bounds, &monitor);
+    }
+    bounds
+}
+
+/// Setting these replaces wry's defaults, so its feature flags are repeated.
+/// WebView2 shares one browser process per data directory and rejects windows
+/// with different arguments, so everything here is fixed at startup.
+#[cfg(target_os = "windows")]
+fn webview2_browser_args(state: &AppState) -> String {
+    let mut args = String::from("--disable-features=msWebOOUI,msPdfOOUI,msSmartScreenProtection");
+    if let Some(proxy) = state.proxy.lock().unwrap().as_ref() {
+        args.push(' ');
+        args.push_str(&proxy::browser_args(proxy));
+    }
+    if state.disable_gpu.load(Ordering::Relaxed) {
+        args.push_str(" --disable-gpu");
+    }
+    if let Some(factor) = state.scale_factor {
+        args.push_str(&format!(" --force-device-scale-factor={}", factor));
+    }
+    args
+}
+
+/// WebKit has no device scale override, so pages are zoomed by the ratio of
+/// the forced scale to the display's, which also scales `devicePixelRatio`.
+/// Repeated when the window moves to a display with another scale.
+#[cfg(not(target_os = "windows"))]
+fn apply_scale_factor(window: &WebviewWindow) {
+    let Some(factor) = window.app_handle().state::<AppState>().scale_factor else {
+        return;
+    };
+    let display = window.scale_factor().unwrap_or(1.0);
+    if let Err(err) = window.set_zoom(factor / display) {
+        eprintln!("Warning: failed to apply the scale factor: {}", err);
+    }
+}
+
+/// Some WebKitGTK and driver combinations render the image canvas black with
+/// accelerated compositing (notably the DMA-BUF renderer on NVIDIA).
+#[cfg(target_os = "linux")]
+fn disable_hardware_acceleration(window: &WebviewWindow) {
+    use webkit2gtk::{HardwareAccelerationPolicy, SettingsExt, WebViewExt};
+
+    let result = window.with_webview(|webview| {
+        if let Some(settings) = webview.inner().settings() {
+            settings.set_hardware_acceleration_policy(HardwareAccelerationPolicy::Never);
+        }
+    });
+    if let Err(err) = result {
+        eprintln!("Warning: failed to disable hardware acceleration: {}", err);
+    }
+}
+
+/// Profile names become directory names.
+fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Points the webview at the profile's own cookies and localStorage, where
+/// the frontend keeps its preferences.
+#[cfg(not(target_os = "macos"))]
+fn with_profile<'a>(
+    app: &AppHandle,
+    builder: WebviewWindowBuilder<'a, tauri::Wry, AppHandle>,
+    profile: &str,
+) -> WebviewWindowBuilder<'a, tauri::Wry, AppHandle> {
+    match app.path().app_local_data_dir() {
+        Ok(dir) => builder.data_directory(dir.join(PROFILES_DIR).join(profile)),
+        Err(err) => {
+            eprintln!(
+                "Warning: no data directory for profile {}: {}",
+                profile, err
+            );
+            builder
+        }
+    }
+}
+
+/// WKWebView has no data directory; a stable per-profile data store id serves
+/// instead. It needs macOS 14, and older versions share the default store.
+#[cfg(target_os = "macos")]
+fn with_profile<'a>(
+    _app: &AppHandle,
+    builder: WebviewWindowBuilder<'a, tauri::Wry, AppHandle>,
+    profile: &str,
+) -> WebviewWindowBuilder<'a, tauri::Wry, AppHandle> {
+    let id = uuid::Uuid::new_v5(
+        &uuid::Uuid::NAMESPACE_OID,
+        format!("carta-profile:{}", profile).as_bytes(),
+    );
+    builder.data_store_identifier(*id.as_bytes())
+}
+
+fn new_window_label() -> String {
+    format!("{}{}", WINDOW_LABEL_PREFIX, uuid::Uuid::new_v4())
+}
+
+/// Session windows, as opposed to auxiliary ones like the LAN sharing window.
+fn is_session_window(label: &str) -> bool {
+    label.starts_with(WINDOW_LABEL_PREFIX)
+}
+
+fn wrap_window_bounds(mut bounds: WindowBounds, monitor: &tauri::window::Monitor) -> WindowBounds {
+    let work_area = monitor.work_area();
+    let monitor_size = monitor.size();
+    let monitor_pos = monitor.position();
+    let scale = monitor.scale_factor();
+
+    // Convert all work area values to logical pixels with floating point precision
+    let work_x = work_area.position.x as f64 / scale;
+    let work_y = work_area.position.y as f64 / scale;
+    let work_width = work_area.size.width as f64 / scale;
+    let work_height = work_area.size.height as f64 / scale;
+
+    // Constrain window size to work area
+    if (bounds.width as f64) > work_width {
+        bounds.width = work_width.round() as u32;
+    }
+    if (bounds.height as f64) > work_height {
+        bounds.height = work_height.round() as u32;
+    }
+
+    // Max boundaries:
+    // On macOS, we use monitor total size because work_area often has
+    // coordinate mismatches that prevent hitting the bottom edge in Tauri 2.0.
+    // On other platforms (Windows/Linux), we MUST use work_area to
+    // avoid overlapping the taskbar (menu bar).
+    let (max_x, max_y) = if cfg!(target_os = "macos") {
+        (
+            (monitor_pos.x + monitor_size.width as i32) as f64 / scale,
+            (monitor_pos.y + monitor_size.height as i32) as f64 / scale,
+        )
+    } else {
+        (work_x + work_width, work_y + work_height)
+    };
+
+    let bx = bounds.x as f64;
+    let by = bounds.y as f64;
+    let bw = bounds.width as f64;
+    let bh = bounds.height as f64;
+
+    // Use a small epsilon for comparisons to avoid floating point issues
+    let epsilon = 0.1;
+    let hits_x = bx < work_x - epsilon || bx + bw > max_x + epsilon;
+    let hits_y = by < work_y - epsilon || by + bh > max_y + epsilon;
+
+    if hits_x || hits_y {
+        bounds.x = work_x.round() as i32;
+        bounds.y = work_y.round() as i32;
+    }
+    bounds
+}
+
+// IPC Commands for frontend menu integration
+#[tauri::command]
+fn cmd_new_window(app: AppHandle) {
+    let state = app.state::<AppState>();
+    open_new_window(&app, &state);
+}
+
+#[tauri::command]
+fn cmd_new_window_with_folder(app: AppHandle) {
+    folders::new_window_with_folder(&app);
+}
+
+#[tauri::command]
+fn cmd_duplicate_window(app: AppHandle, window: WebviewWindow) {
+    let state = app.state::<AppState>();
+    duplicate_window(&app, &state, &window);
+}
+
+#[tauri::command]
+fn cmd_focus_next_window(app: AppHandle) {
+    cycle_window_focus(&app, true);
+}
+
+#[tauri::command]
+fn cmd_focus_previous_window(app: AppHandle) {
+    cycle_window_focus(&app, false);
+}
+
+/// The session's windows in creation order, for window pickers.
+#[tauri::command]
+fn cmd_list_windows(app: AppHandle) -> Vec<WindowInfo> {
+    list_windows(&app)
+}
+
+/// Brings the window `label` from `cmd_list_windows` to the front.
+#[tauri::command]
+fn cmd_focus_window(app: AppHandle, label: String) -> Result<(), String> {
+    focus_window(&app, &label)
+}
+
+#[tauri::command]
+fn cmd_get_kiosk_mode(app: AppHandle) -> bool {
+    is_kiosk(&app.state::<AppState>())
+}
+
+#[tauri::command]
+fn cmd_set_kiosk_mode(app: AppHandle, enabled: bool) {
+    set_kiosk_mode(&app, enabled);
+}
+
+#[tauri::command]
+fn cmd_toggle_fullscreen(window: WebviewWindow) {
+    toggle_fullscreen(&window);
+}
+
+#[tauri::command]
+fn cmd_toggle_devtools(window: WebviewWindow) {
+    toggle_devtools(&window);
+}
+
+/// Lets the frontend name the open file explicitly, e.g. when the document
+/// title is not updated.
+#[tauri::command]
+fn cmd_set_window_file(window: WebviewWindow, file: Option<String>) {
+    let folder = window_folder(&window);
+    let _ = window.set_title(&window_title(file.as_deref(), folder.as_deref()));
+}
+
+/// `fraction` is from 0 to 1; leave it out while the amount is unknown.
+#[tauri::command]
+fn cmd_set_progress(window: WebviewWindow, progress: TaskProgress, fraction: Option<f64>) {
+    let state = window.state::<AppState>();
+    let mut busy = state.busy_windows.lock().unwrap();
+    if matches!(progress, TaskProgress::Running | TaskProgress::Paused) {
+        busy.insert(window.label().to_string());
+    } else {
+        busy.remove(window.label());
+    }
+    drop(busy);
+    set_task_progress(&window, progress, fraction);
+}
+
+#[tauri::command]
+fn cmd_save_window_screenshot(window: WebviewWindow) {
+    screenshot::save_window_screenshot(&window);
+}
+
+#[tauri::command]
+fn cmd_open_log_folder(app: AppHandle) {
+    open_log_folder(&app);
+}
+
+/// Shows the backend options for editing; does nothing in kiosk mode.
+#[tauri::command]
+fn cmd_show_advanced_start(app: AppHandle) {
+    advanced_start::show(&app);
+}
+
+#[tauri::command]
+fn cmd_close_window(window: WebviewWindow) {
+    let _ = window.close();
+}
+
+/// For the frontend's Close All Windows (Ctrl+Shift+W).
+#[tauri::command]
+fn cmd_close_all_windows(app: AppHandle) {
+    close_all_windows(&app);
+}
+
+#[tauri::command]
+fn cmd_get_settings(app: AppHandle) -> Settings {
+    app.state::<AppState>().settings.lock().unwrap().clone()
+}
+
+#[tauri::command]
+fn cmd_set_always_open_devtools(app: AppHandle, enabled: bool) {
+    set_always_open_devtools(&app, enabled);
+}
+
+/// Resolved UI language tag, so menus drawn by the frontend can match the native ones.
+#[tauri::command]
+fn cmd_get_language(app: AppHandle) -> &'static str {
+    ui_language(&app).tag()
+}
+
+#[tauri::command]
+fn cmd_set_language(app: AppHandle, language: Option<String>) {
+    update_settings(&app, |settings| settings.language = language);
+    #[cfg(target_os = "macos")]
+    rebuild_menus(&app);
+}
+
+/// Asks for the folder CARTA opens in when started from the desktop, and
+/// returns it; `None` when cancelled. Takes effect the next time CARTA starts.
+#[tauri::command(async)]
+fn cmd_choose_default_data_dir(app: AppHandle) -> Option<PathBuf> {
+    first_run::pick_data_folder(&app)
+}
+
+/// `None` goes back to the home folder. Takes effect the next time CARTA
+/// starts.
+#[tauri::command]
+fn cmd_set_default_data_dir(app: AppHandle, dir: Option<PathBuf>) {
+    update_settings(&app, |settings| settings.default_data_dir = dir);
+}
+
+/// A system-wide shortcut such as `CmdOrCtrl+Alt+N` for a new window, or
+/// `None` for none. Takes effect at once.
+#[tauri::command]
+fn cmd_set_new_window_shortcut(app: AppHandle, shortcut: Option<String>) -> Result<(), String> {
+    global_shortcut::set(&app, shortcut)
+}
+
+/// Saves the launcher's settings, with the window positions if
+/// `include_window_state`, to a file for other machines; false when the
+/// dialog is cancelled.
+#[tauri::command(async)]
+fn cmd_export_settings(app: AppHandle, include_window_state: bool) -> Result<bool, String> {
+    settings_transfer::export(&app, include_window_state)
+}
+
+/// Replaces the settings with those of an exported file; false when the
+/// dialog is cancelled. Most take effect the next time CARTA starts.
+#[tauri::command(async)]
+fn cmd_import_settings(app: AppHandle) -> Result<bool, String> {
+    settings_transfer::import(&app)
+}
+
+#[tauri::command]
+fn cmd_set_theme(app: AppHandle, theme: ThemePreference) {
+    update_settings(&app, |settings| settings.theme = theme);
+    apply_theme(&app);
+}
+
+#[tauri::command]
+fn cmd_set_tray_icon(app: AppHandle, enabled: bool) {
+    update_settings(&app, |settings| settings.tray_icon = enabled);
+    tray::sync_tray(&app);
+}
+
+#[tauri::command]
+fn cmd_set_minimize_to_tray(app: AppHandle, enabled: bool) {
+    update_settings(&app, |settings| settings.minimize_to_tray = enabled);
+}
+
+#[tauri::command]
+fn cmd_get_read_only(app: AppHandle) -> bool {
+    is_read_only(&app.state::<AppState>())
+}
+
+/// Restarts the backend, reloading every window on it.
+#[tauri::command]
+fn cmd_set_read_only(app: AppHandle, enabled: bool) {
+    set_read_only(&app, enabled);
+}
+
+/// Shows the LAN link and QR code again; does nothing unless sharing.
+#[tauri::command]
+fn cmd_show_lan_share(app: AppHandle) {
+    if app.state::<AppState>().share_lan {
+        lan::show_share_window(&app);
+    }
+}
+
+/// Takes effect the next time CARTA starts.
+#[tauri::command]
+fn cmd_set_proxy(app: AppHandle, proxy: ProxySettings) {
+    update_settings(&app, |settings| settings.proxy = proxy);
+}
+
+/// `None` asks for a location with a save dialog for every download.
+#[tauri::command]
+fn cmd_set_download_dir(app: AppHandle, dir: Option<PathBuf>) {
+    update_settings(&app, |settings| settings.download_dir = dir);
+}
+
+/// Linux only; takes effect the next time CARTA starts.
+#[tauri::command]
+fn cmd_set_display_backend(app: AppHandle, backend: DisplayBackend) {
+    update_settings(&app, |settings| settings.display_backend = backend);
+}
+
+/// Takes effect the next time CARTA starts.
+#[tauri::command]
+fn cmd_set_wsl_shell(app: AppHandle, shell: WslShell) {
+    update_settings(&app, |settings| settings.wsl_shell = shell);
+}
+
+/// Takes effect the next time CARTA starts.
+#[tauri::command]
+fn cmd_set_disable_gpu(app: AppHandle, enabled: bool) {
+    update_settings(&app, |settings| settings.disable_gpu = enabled);
+}
+
+/// Takes effect the next time CARTA starts.
+#[tauri::command]
+fn cmd_set_start_window_state(app: AppHandle, window_state: StartWindowState) {
+    update_settings(&app, |settings| settings.start_window_state = window_state);
+}
+
+/// Takes effect the next time CARTA starts.
+#[tauri::command]
+fn cmd_set_start_monitor(app: AppHandle, index: Option<usize>) {
+    update_settings(&app, |settings| settings.start_monitor = index);
+}
+
+/// Takes effect the next time CARTA starts.
+#[tauri::command]
+fn cmd_set_check_for_updates(app: AppHandle, enabled: bool) {
+    update_settings(&app, |settings| settings.check_for_updates = enabled);
+}
+
+/// Applies to windows closed from then on.
+#[tauri::command]
+fn cmd_set_last_window_close(app: AppHandle, behavior: LastWindowClose) {
+    update_settings(&app, |settings| settings.last_window_close = behavior);
+}
+
+/// Applies to launches from then on.
+#[tauri::command]
+fn cmd_set_second_instance(app: AppHandle, behavior: SecondInstance) {
+    update_settings(&app, |settings| settings.second_instance = behavior);
+}
+
+/// Takes effect the next time CARTA starts; values outside the range
+/// `--scale-factor` accepts are ignored.
+#[tauri::command]
+fn cmd_set_scale_factor(app: AppHandle, factor: Option<f64>) {
+    let factor = factor.filter(|factor| (MIN_SCALE_FACTOR..=MAX_SCALE_FACTOR).contains(factor));
+    update_settings(&app, |settings| settings.scale_factor = factor);
+}
+
+/// Takes effect the next time CARTA starts.
+#[tauri::command]
+fn cmd_set_frontend_from_app(app: AppHandle, enabled: bool) {
+    update_settings(&app, |settings| settings.frontend_from_app = enabled);
+}
+
+/// Takes effect the next time CARTA starts; `None` starts a backend again.
+#[tauri::command]
+fn cmd_set_controller_url(app: AppHandle, url: Option<String>) {
+    update_settings(&app, |settings| settings.controller_url = url);
+}
+
+/// Takes effect when CARTA quits: the open windows are kept for the next start.
+#[tauri::command]
+fn cmd_set_restore_session(app: AppHandle, enabled: bool) {
+    update_settings(&app, |settings| settings.restore_session = enabled);
+}
+
+/// Searching for a system backend runs it, so this stays off the main thread.
+#[tauri::command(async)]
+fn cmd_get_backend_choices(app: AppHandle) -> BackendChoices {
+    backend_choices(&app)
+}
+
+/// Applies from the next backend start.
+#[tauri::command]
+fn cmd_set_backend_choice(app: AppHandle, choice: BackendChoice) {
+    update_settings(&app, |settings| settings.backend_choice = choice);
+}
+
+#[tauri::command]
+fn cmd_run_setup_wizard(app: AppHandle) {
+    first_run::start(&app);
+}
+
+/// Makes this CARTA open FITS and HDF5 images from Explorer, taking them
+/// back from other apps and older installs. Packages on the other systems
+/// register their file types on install.
+#[tauri::command(async)]
+#[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+fn cmd_register_file_associations(app: AppHandle) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        file_associations::register(&*app.state::<AppState>().processes)
+            .map_err(|err| err.to_string())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err("File types are registered when CARTA is installed".to_string())
+    }
+}
+
+/// Opting out also deletes the reports kept so far.
+#[tauri::command]
+fn cmd_set_telemetry(app: AppHandle, enabled: bool) {
+    update_settings(&app, |settings| settings.telemetry = enabled);
+    telemetry::set_enabled(enabled);
+}
+
+/// The launch reports that would be sent, for review before opting in.
+#[tauri::command]
+fn cmd_get_telemetry_preview() -> Vec<telemetry::LaunchReport> {
+    telemetry::preview()
+}
+
+/// Takes effect the next time a backend starts.
+#[tauri::command]
+fn cmd_set_backend_timeouts(app: AppHandle, timeouts: BackendTimeouts) {
+    update_settings(&app, |settings| settings.backend_timeouts = timeouts);
+}
+
+/// Takes effect the next time a backend starts.
+#[tauri::command]
+fn cmd_set_backend_limits(app: AppHandle, limits: BackendLimits) {
+    update_settings(&app, |settings| settings.backend_limits = limits);
+}
+
+#[tauri::command]
+fn cmd_set_restart_policy(app: AppHandle, restart: RestartSettings) {
+    update_settings(&app, |settings| settings.restart = restart);
+}
+
+/// Keeps the system awake until `cmd_release_sleep` is called with the returned id,
+/// e.g. around a file conversion or moment map generation. The hold also ends
+/// when the window closes or leaves its page.
+#[tauri::command]
+fn cmd_inhibit_sleep(window: WebviewWindow) -> u64 {
+    window
+        .state::<AppState>()
+        .sleep_inhibitor
+        .acquire(window.label())
+}
+
+#[tauri::command]
+fn cmd_release_sleep(app: AppHandle, id: u64) -> bool {
+    app.state::<AppState>().sleep_inhibitor.release(id)
+}
+
+#[tauri::command]
+fn cmd_get_prevent_sleep(app: AppHandle) -> bool {
+    app.state::<AppState>().sleep_inhibitor.is_manual()
+}
+
+#[tauri::command]
+fn cmd_set_prevent_sleep(app: AppHandle, enabled: bool) {
+    set_prevent_sleep(&app, enabled);
+}
+
+#[tauri::command]
+fn cmd_get_backend_status(app: AppHandle) -> monitor::BackendStatus {
+    let state = app.state::<AppState>();
+    state.resource_monitor.sample(&state)
+}
+
+/// The last output lines of the backend `window` is on, oldest first; all
+/// that are kept when `lines` is not given.
+#[tauri::command]
+fn cmd_get_backend_log_tail(
+    app: AppHandle,
+    window: WebviewWindow,
+    lines: Option<usize>,
+) -> Vec<supervisor::LogLine> {
+    folders::window_log(&app, &window).tail(lines.unwrap_or(usize::MAX))
+}
+
+#[tauri::command]
+fn cmd_quit_app(app: AppHandle) {
+    quit::quit(&app);
+}
+
+/// Takes effect the next time CARTA is quit or its last window is closed.
+#[tauri::command]
+fn cmd_set_confirm_quit_when_busy(app: AppHandle, enabled: bool) {
+    update_settings(&app, |settings| settings.confirm_quit_when_busy = enabled);
+}
+
+fn ui_language<R: Runtime>(app: &AppHandle<R>) -> Language {
+    let preference = app
+        .try_state::<AppState>()
+        .and_then(|state| state.settings.lock().unwrap().language.clone());
+    Language::resolve(preference.as_deref())
+}
+
+#[cfg(target_os = "macos")]
+fn build_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<tauri::menu::Menu<R>> {
+    let language = ui_language(app);
+    let new_window = MenuItem::with_id(
+        app,
+        MENU_NEW_WINDOW,
+        tr(language, Message::NewWindow),
+        true,
+        Some("Cmd+N"),
+    )?;
+    let new_window_with_folder = MenuItem::with_id(
+        app,
+        MENU_NEW_WINDOW_WITH_FOLDER,
+        tr(language, Message::NewWindowWithFolder),
+        true,
+        Some("Alt+Cmd+N"),
+    )?;
+    let duplicate_window = MenuItem::with_id(
+        app,
+        MENU_DUPLICATE_WINDOW,
+        tr(language, Message::DuplicateWindow),
+        true,
+        Some("Shift+Cmd+N"),
+    )?;
+    let toggle_fullscreen = MenuItem::with_id(
+        app,
+        MENU_TOGGLE_FULLSCREEN,
+        tr(language, Message::ToggleFullscreen),
+        true,
+        Some("Ctrl+Cmd+F"),
+    )?;
+    let toggle_devtools = MenuItem::with_id(
+        app,
+        MENU_TOGGLE_DEVTOOLS,
+        tr(language, Message::ToggleDevtools),
+        true,
+        Some("Alt+Cmd+I"),
+    )?;
+    let save_screenshot = MenuItem::with_id(
+        app,
+        MENU_SAVE_SCREENSHOT,
+        tr(language, Message::SaveScreenshot),
+        true,
+        None::<&str>,
+    )?;
+    let copy_auth_token = MenuItem::with_id(
+        app,
+        MENU_COPY_AUTH_TOKEN,
+        tr(language, Message::CopyAuthToken),
+        true,
+        None::<&str>,
+    )?;
+    let prevent_sleep = CheckMenuItem::with_id(
+        app,
+        MENU_PREVENT_SLEEP,
+        tr(language, Message::PreventSleep),
+        true,
+        app.try_state::<AppState>()
+            .map(|state| state.sleep_inhibitor.is_manual())
+            .unwrap_or(false),
+        None::<&str>,
+    )?;
+    let read_only = CheckMenuItem::with_id(
+        app,
+        MENU_READ_ONLY,
+        tr(language, Message::ReadOnlySession),
+        true,
+        app.try_state::<AppState>()
+            .map(|state| is_read_only(&state))
+            .unwrap_or(false),
+        None::<&str>,
+    )?;
+    let advanced_start = MenuItem::with_id(
+        app,
+        MENU_ADVANCED_START,
+        tr(language, Message::AdvancedStart),
+        true,
+        None::<&str>,
+    )?;
+    let always_open_devtools = CheckMenuItem::with_id(
+        app,
+        MENU_ALWAYS_OPEN_DEVTOOLS,
+        tr(language, Message::AlwaysOpenDevtools),
+        true,
+        app.try_state::<AppState>()
+            .map(|state| state.settings.lock().unwrap().always_open_devtools)
+            .unwrap_or(false),
+        None::<&str>,
+    )?;
+
+    // Not the predefined item, so quitting can ask first while work runs.
+    let close_all_windows = MenuItem::with_id(
+        app,
+        MENU_CLOSE_ALL_WINDOWS,
+        tr(language, Message::CloseAllWindows),
+        true,
+        Some("Shift+Cmd+W"),
+    )?;
+    let quit = MenuItem::with_id(
+        app,
+        MENU_QUIT,
+        tr(language, Message::Quit),
+        true,
+        Some("Cmd+Q"),
+    )?;
+
+    let app_menu = SubmenuBuilder::new(app, &app.package_info().name)
+        .item(&new_window)
+        .item(&new_window_with_folder)
+        .item(&duplicate_window)
+        .item(&save_screenshot)
+        .item(&copy_auth_token)
+        .separator()
+        .item(&toggle_fullscreen)
+        .item(&prevent_sleep)
+        .item(&read_only)
+        .item(&advanced_start)
+        .separator()
+        .item(&toggle_devtools)
+        .item(&always_open_devtools)
+        .separator()
+        .close_window_with_text(tr(language, Message::CloseWindow))
+        .item(&close_all_windows)
+        .item(&quit)
+        .build()?;
+
+    let show_tab_bar = MenuItem::with_id(
+        app,
+        MENU_SHOW_TAB_BAR,
+        tr(language, Message::ShowTabBar),
+        true,
+        Some("Shift+Cmd+T"),
+    )?;
+    let merge_all_windows = MenuItem::with_id(
+        app,
+        MENU_MERGE_ALL_WINDOWS,
+        tr(language, Message::MergeAllWindows),
+        true,
+        None::<&str>,
+    )?;
+    let next_window = MenuItem::with_id(
+        app,
+        MENU_NEXT_WINDOW,
+        tr(language, Message::NextWindow),
+        true,
+        Some("Ctrl+Tab"),
+    )?;
+    let previous_window = MenuItem::with_id(
+        app,
+        MENU_PREVIOUS_WINDOW,
+        tr(language, Message::PreviousWindow),
+        true,
+        Some("Ctrl+Shift+Tab"),
+    )?;
+    let kiosk_mode = CheckMenuItem::with_id(
+        app,
+        MENU_KIOSK_MODE,
+        tr(language, Message::PresentationMode),
+        true,
+        app.try_state::<AppState>()
+            .map(|state| is_kiosk(&state))
+            .unwrap_or(false),
+        None::<&str>,
+    )?;
+    let view_menu = SubmenuBuilder::new(app, tr(language, Message::View))
+        .item(&kiosk_mode)
+        .separator()
+        .item(&show_tab_bar)
+        .item(&merge_all_windows)
+        .separator()
+        .item(&next_window)
+        .item(&previous_window)
+        .build()?;
+
+    let open_log_folder = MenuItem::with_id(
+        app,
+        MENU_OPEN_LOG_FOLDER,
+        tr(language, Message::OpenLogFolder),
+        true,
+        None::<&str>,
+    )?;
+    let copy_backend_command = MenuItem::with_id(
+        app,
+        MENU_COPY_BACKEND_COMMAND,
+        tr(language, Message::CopyBackendCommand),
+        true,
+        None::<&str>,
+    )?;
+    let help_menu = SubmenuBuilder::new(app, tr(language, Message::Help))
+        .item(&open_log_folder)
+        .item(&copy_backend_command)
+        .build()?;
+
+    MenuBuilder::new(app)
+        .item(&app_menu)
+        .item(&view_menu)
+        .item(&help_menu)
+        .build()
+}
+
+/// Updates the checked state of every check item with `id`, in the app menu and
+/// in each window menu, including items nested in submenus.
+#[cfg(target_os = "macos")]
+fn set_menu_item_checked(app: &AppHandle, id: &str, checked: bool) {
+    fn visit(items: Vec<tauri::menu::MenuItemKind<tauri::Wry>>, id: &str, checked: bool) {
+        for item in items {
+            if let Some(submenu) = item.as_submenu() {
+                visit(submenu.items().unwrap_or_default(), id, checked);
+            } else if item.id() == &id
+                && let Some(check) = item.as_check_menuitem()
+            {
+                let _ = check.set_checked(checked);
+            }
+        }
+    }
+
+    let window_menus = app
+        .webview_windows()
+        .into_values()
+        .filter_map(|window| window.menu());
+    for menu in app.menu().into_iter().chain(window_menus) {
+        visit(menu.items().unwrap_or_default(), id, checked);
+    }
+}
+
+/// Replaces the app menu and every window menu, e.g. after the language changed.
+#[cfg(target_os = "macos")]
+fn rebuild_menus(app: &AppHandle) {
+    if let Ok(menu) = build_menu(app) {
+        let _ = app.set_menu(menu);
+    }
+    for window in app.webview_windows().values() {
+        if let Ok(menu) = build_menu(app) {
+            let _ = window.set_menu(menu);
+        }
+    }
+}
+
+/// Applies the theme preference to the app and all open windows. `None` lets
+/// windows follow the system appearance, including later dark-mode switches.
+fn apply_theme(app: &AppHandle) {
+    let theme = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .theme
+        .as_theme();
+    app.set_theme(theme);
+    for window in app.webview_windows().values() {
+        let _ = window.set_theme(theme);
+    }
+}
+
+fn set_prevent_sleep(app: &AppHandle, enabled: bool) {
+    app.state::<AppState>().sleep_inhibitor.set_manual(enabled);
+    #[cfg(target_os = "macos")]
+    set_menu_item_checked(app, MENU_PREVENT_SLEEP, enabled);
+}
+
+fn set_always_open_devtools(app: &AppHandle, enabled: bool) {
+    update_settings(app, |settings| settings.always_open_devtools = enabled);
+    #[cfg(target_os = "macos")]
+    set_menu_item_checked(app, MENU_ALWAYS_OPEN_DEVTOOLS, enabled);
+}
+
+fn is_kiosk(state: &AppState) -> bool {
+    state.kiosk.load(Ordering::Relaxed)
+}
+
+/// Opens a window on the launch session, unless kiosk mode forbids it.
+fn open_new_window(app: &AppHandle, state: &AppState) {
+    if is_kiosk(state) {
+        return;
+    }
+    let _ = create_window(app, state, new_window_label(), None);
+}
+
+/// Kiosk mode: borderless fullscreen windows, no DevTools and no new windows.
+/// The frontend hides its own menu bar on `kiosk-mode-changed`.
+fn set_kiosk_mode(app: &AppHandle, enabled: bool) {
+    app.state::<AppState>()
+        .kiosk
+        .store(enabled, Ordering::Relaxed);
+    for window in app.webview_windows().values() {
+        apply_kiosk_mode(window, enabled);
+    }
+    let _ = app.emit(EVENT_KIOSK_MODE_CHANGED, enabled);
+    #[cfg(target_os = "macos")]
+    set_menu_item_checked(app, MENU_KIOSK_MODE, enabled);
+}
+
+fn is_read_only(state: &AppState) -> bool {
+    state.read_only.load(Ordering::Relaxed)
+}
+
+/// Read-only mode is a backend option, so switching it restarts the launch
+/// backend and the folder-window backends, and reloads their windows.
+fn set_read_only(app: &AppHandle, enabled: bool) {
+    let state = app.state::<AppState>();
+    if state.read_only.swap(enabled, Ordering::Relaxed) == enabled {
+        return;
+    }
+    let _ = app.emit(EVENT_READ_ONLY_CHANGED, enabled);
+    #[cfg(target_os = "macos")]
+    set_menu_item_checked(app, MENU_READ_ONLY, enabled);
+
+    restart_launch_backend(app);
+    let handle = app.clone();
+    state.tasks.spawn(async move {
+        folders::restart_folder_backends(&handle).await;
+    });
+}
+
+/// Stops the launch backend and starts it again in the background with the
+/// current options, then reloads its windows.
+fn restart_launch_backend(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    forget_launch_backend_work(app);
+    shutdown_backend(&state);
+    let handle = app.clone();
+    let port = state.backend_port();
+    state.tasks.spawn(async move {
+        match supervisor::restart_backend(&handle).await {
+            Ok(()) => supervisor::reload_windows(&handle, port),
+            Err(err) => eprintln!("Warning: failed to restart backend: {}", err),
+        }
+    });
+}
+
+/// Asks before switching from the menu, since the restart closes open images.
+#[cfg(target_os = "macos")]
+fn confirm_read_only(app: &AppHandle, enabled: bool) {
+    use tauri_plugin_dialog::MessageDialogButtons;
+
+    // The check mark already flipped; keep it in sync until confirmed.
+    set_menu_item_checked(app, MENU_READ_ONLY, !enabled);
+    let message = if enabled {
+        "Restart the backend in read-only mode? All windows will reload and open images will be closed."
+    } else {
+        "Restart the backend with editing allowed? All windows will reload and open images will be closed."
+    };
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title("CARTA")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Restart".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            if confirmed {
+                set_read_only(&handle, enabled);
+            }
+        });
+}
+
+fn apply_kiosk_mode(window: &WebviewWindow, enabled: bool) {
+    if enabled && window.is_devtools_open() {
+        window.close_devtools();
+    }
+    let _ = window.set_decorations(!enabled);
+    let _ = window.set_fullscreen(enabled);
+}
+
+/// From `--maximized`/`--minimized`/`--fullscreen`/`--monitor` or the
+/// settings; kiosk mode is fullscreen already. A minimized window goes to the
+/// tray when windows hide there.
+fn apply_start_window_state(
+    window: &WebviewWindow,
+    window_state: StartWindowState,
+    monitor: Option<usize>,
+) {
+    if let Some(index) = monitor {
+        move_to_monitor(window, index);
+    }
+    if is_kiosk(&window.app_handle().state::<AppState>()) {
+        return;
+    }
+    let result = match window_state {
+        StartWindowState::Normal => Ok(()),
+        StartWindowState::Maximized => window.maximize(),
+        StartWindowState::Minimized => window.minimize(),
+        StartWindowState::Fullscreen => window.set_fullscreen(true),
+    };
+    if let Err(err) = result {
+        eprintln!("Warning: failed to set the window's start state: {}", err);
+    }
+}
+
+/// Puts `window` at the top left of a monitor; a fullscreen window leaves
+/// fullscreen to move and then fills the new monitor.
+fn move_to_monitor(window: &WebviewWindow, index: usize) {
+    let monitors = window.available_monitors().unwrap_or_default();
+    let Some(monitor) = monitors.get(index) else {
+        eprintln!(
+            "Warning: there is no monitor {} ({} connected); keeping the window where it is",
+            index,
+            monitors.len()
+        );
+        return;
+    };
+    let fullscreen = window.is_fullscreen().unwrap_or(false);
+    if fullscreen {
+        let _ = window.set_fullscreen(false);
+    }
+    if let Err(err) = window.set_position(*monitor.position()) {
+        eprintln!(
+            "Warning: failed to move the window to monitor {}: {}",
+            index, err
+        );
+    }
+    if fullscreen {
+        let _ = window.set_fullscreen(true);
+    }
+}
+
+/// URL for a copy of a window: its current URL, query parameters included, as
+/// long as it still points at this launcher's backend or one of its folder
+/// backends on `folder_ports`.
+fn duplicate_window_url(
+    base_url: &str,
+    folder_ports: &[u16],
+    current: &tauri::Url,
+) -> Option<String> {
+    let base = tauri::Url::parse(base_url).ok()?;
+    let port = frontend_protocol::backend_port(current)?;
+    // Custom schemes have opaque origins, which never compare equal.
+    let same_backend = base.scheme() == current.scheme()
+        && base.host_str() == current.host_str()
+        && (frontend_protocol::backend_port(&base) == Some(port) || folder_ports.contains(&port));
+    same_backend.then(|| current.to_string())
+}
+
+fn duplicate_window(app: &AppHandle, state: &AppState, source: &WebviewWindow) {
+    if is_kiosk(state) {
+        return;
+    }
+    let url = source.url().ok().and_then(|url| {
+        duplicate_window_url(&state.window_url(), &state.folder_backends.ports(), &url)
+    });
+    if let Err(err) = create_window(app, state, new_window_label(), url.as_deref()) {
+        eprintln!("Warning: failed to duplicate window: {}", err);
+    }
+}
+
+/// Sends a standard NSWindow action such as `toggleTabBar:` to `window`.
+#[cfg(target_os = "macos")]
+fn send_window_action(window: &WebviewWindow, action: objc2::runtime::Sel) {
+    use objc2::runtime::AnyObject;
+
+    let Ok(ns_window) = window.ns_window() else {
+        return;
+    };
+    let ns_window = ns_window.cast::<AnyObject>();
+    // SAFETY: `ns_window` is the live NSWindow behind `window`, menu events are
+    // delivered on the main thread, and tab actions take a nullable sender.
+    unsafe {
+        let _: *mut AnyObject = objc2::msg_send![
+            ns_window,
+            performSelector: action,
+            withObject: std::ptr::null_mut::<AnyObject>()
+        ];
+    }
+}
+
+fn toggle_devtools(window: &WebviewWindow) {
+    if is_kiosk(&window.app_handle().state::<AppState>()) {
+        return;
+    }
+    if window.is_devtools_open() {
+        window.close_devtools();
+    } else {
+        window.open_devtools();
+    }
+    save_window_bounds(window.app_handle(), &window.as_ref().window());
+}
+
+/// Index of the window to focus after `current` when cycling through `len`
+/// windows; starts at the first (or last) window when none is focused.
+fn cycle_window_index(len: usize, current: Option<usize>, forward: bool) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    Some(match (current, forward) {
+        (Some(index), true) => (index + 1) % len,
+        (Some(index), false) => (index + len - 1) % len,
+        (None, true) => 0,
+        (None, false) => len - 1,
+    })
+}
+
+/// A session window as `cmd_list_windows` reports it.
+#[derive(Debug, Clone, serde::Serialize)]
+struct WindowInfo {
+    label: String,
+    title: String,
+    focused: bool,
+    visible: bool,
+    minimized: bool,
+}
+
+fn list_windows(app: &AppHandle) -> Vec<WindowInfo> {
+    let state = app.state::<AppState>();
+    let labels = state.window_order.lock().unwrap().clone();
+    labels
+        .iter()
+        .filter_map(|label| app.get_webview_window(label))
+        .map(|window| WindowInfo {
+            label: window.label().to_string(),
+            title: window.title().unwrap_or_default(),
+            focused: window.is_focused().unwrap_or(false),
+            visible: window.is_visible().unwrap_or(true),
+            minimized: window.is_minimized().unwrap_or(false),
+        })
+        .collect()
+}
+
+/// Shows `label` again if it is hidden to the tray or minimized.
+fn focus_window(app: &AppHandle, label: &str) -> Result<(), String> {
+    let window = app
+        .get_webview_window(label)
+        .filter(|_| is_session_window(label))
+        .ok_or_else(|| format!("No window {}", label))?;
+    let _ = window.show();
+    let _ = window.unminimize();
+    window.set_focus().map_err(|err| err.to_string())
+}
+
+/// Moves focus to the next or previous visible window in creation order.
+fn cycle_window_focus(app: &AppHandle, forward: bool) {
+    let state = app.state::<AppState>();
+    let labels = state.window_order.lock().unwrap().clone();
+    let windows: Vec<WebviewWindow> = labels
+        .iter()
+        .filter_map(|label| app.get_webview_window(label))
+        .filter(|window| window.is_visible().unwrap_or(true))
+        .collect();
+    let current = windows
+        .iter()
+        .position(|window| window.is_focused().unwrap_or(false));
+    if let Some(index) = cycle_window_index(windows.len(), current, forward) {
+        let window = &windows[index];
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn toggle_fullscreen(window: &WebviewWindow) {
+    let next_state = !window.is_fullscreen().unwrap_or(false);
+    let _ = window.set_fullscreen(next_state);
+}
+
+/// Links away from the local backend (documentation, catalog services) go to
+/// the system browser so the session page stays loaded.
+fn opens_externally(url: &tauri::Url) -> bool {
+    match url.scheme() {
+        "http" | "https" => !matches!(
+            url.host_str(),
+            Some(host) if matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+                || host.ends_with(".localhost")
+                || host == backend_host()
+        ),
+        "mailto" => true,
+        _ => false,
+    }
+}
+
+fn open_externally(url: &tauri::Url) {
+    if let Err(err) = tauri_plugin_opener::open_url(url.as_str(), None::<&str>) {
+        eprintln!("Warning: failed to open {}: {}", url, err);
+    }
+}
+
+/// Shows the save dialog for a frontend export and returns the chosen path,
+/// which `cmd_write_file` may then write; `None` when cancelled.
+#[tauri::command(async)]
+fn cmd_save_file_dialog(
+    window: WebviewWindow,
+    file_name: Option<String>,
+    filters: Vec<exports::FileFilter>,
+) -> Option<PathBuf> {
+    exports::ask_save_path(&window, file_name, &filters)
+}
+
+#[tauri::command(async)]
+fn cmd_write_file(app: AppHandle, path: PathBuf, contents: Vec<u8>) -> Result<(), String> {
+    exports::write(&app.state::<AppState>().save_targets, &path, &contents)
+}
+
+/// Shows the native open dialog for `window`; see `folders::pick_file` for
+/// files its backend cannot serve. `None` when cancelled.
+#[tauri::command]
+async fn cmd_open_file_dialog(
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<Option<folders::PickedFile>, String> {
+    folders::pick_file(&app, &window)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Where the launcher finds `path`, as the backend gave it to the frontend;
+/// relative paths are under the backend's top-level folder.
+fn host_path(top_level: &Path, path: &str) -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        let top = top_level.to_string_lossy();
+        let path = if is_wsl_path_str(path) || Path::new(path).is_absolute() {
+            path.to_string()
+        } else if is_wsl_path_str(&top) {
+            format!("{}/{}", top.trim_end_matches('/'), path)
+        } else {
+            return Some(top_level.join(path));
+        };
+        if is_wsl_path_str(&path) {
+            wsl_to_win_path(&path, wsl_distro().as_deref()).map(PathBuf::from)
+        } else {
+            Some(PathBuf::from(path))
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Some(top_level.join(path))
+    }
+}
+
+/// Shows `path` selected in the system file manager, for the frontend's
+/// "Show in Finder/Explorer" on loaded images and exports. Relative paths are
+/// under the top-level folder of `window`'s backend.
+#[tauri::command]
+fn cmd_reveal_path(app: AppHandle, window: WebviewWindow, path: String) {
+    let Some(host) =
+        folders::window_top_level(&app, &window).and_then(|top_level| host_path(&top_level, &path))
+    else {
+        eprintln!("Warning: cannot find {} to show it", path);
+        return;
+    };
+    if let Err(err) = tauri_plugin_opener::reveal_item_in_dir(&host) {
+        eprintln!("Warning: failed to reveal {}: {}", host.display(), err);
+    }
+}
+
+/// Shows the launcher's log directory (the `--verbose` startup trace) in the
+/// file manager, creating it first so there is always something to open.
+fn open_log_folder(app: &AppHandle) {
+    let dir = match app.path().app_log_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Warning: log directory not found: {}", err);
+            return;
+        }
+    };
+    if let Err(err) = fs::create_dir_all(&dir) {
+        eprintln!("Warning: failed to create {}: {}", dir.display(), err);
+        return;
+    }
+    if let Err(err) = tauri_plugin_opener::open_path(&dir, None::<&str>) {
+        eprintln!("Warning: failed to open {}: {}", dir.display(), err);
+    }
+}
+
+/// For a diagnostics button; see `copy_backend_command`.
+#[tauri::command]
+fn cmd_copy_backend_command(app: AppHandle) {
+    copy_backend_command(&app);
+}
+
+/// Copies the backend token of the focused window, or of the launch backend,
+/// for pasting into carta-python or a browser asking for it.
+pub(crate) fn copy_auth_token(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let token = focused_window(app)
+        .and_then(|window| window.url().ok())
+        .and_then(|url| url_token(&url))
+        .or_else(|| {
+            (!state.no_auth && state.controller.is_none()).then(|| state.backend_token.clone())
+        });
+    let Some(token) = token else {
+        eprintln!("Warning: the backend runs without an auth token; nothing to copy");
+        return;
+    };
+    if let Err(err) = app.clipboard().write_text(token) {
+        eprintln!("Warning: failed to copy the auth token: {}", err);
+    }
+}
+
+fn url_token(url: &tauri::Url) -> Option<String> {
+    url.query_pairs()
+        .find(|(name, _)| name == "token")
+        .map(|(_, token)| token.into_owned())
+}
+
+/// Where a long frontend operation, such as a file conversion or moment maps,
+/// has got to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TaskProgress {
+    Running,
+    Paused,
+    Error,
+    /// Clears the progress shown.
+    Done,
+}
+
+/// The taskbar progress for `progress`, with `fraction` from 0 to 1. Running
+/// without a fraction shows an indeterminate bar.
+fn progress_bar_state(progress: TaskProgress, fraction: Option<f64>) -> ProgressBarState {
+    let percent = fraction
+        .filter(|fraction| fraction.is_finite())
+        .map(|fraction| (fraction.clamp(0.0, 1.0) * 100.0).round() as u64);
+    let status = match (progress, percent) {
+        (TaskProgress::Done, _) => ProgressBarStatus::None,
+        (TaskProgress::Running, None) => ProgressBarStatus::Indeterminate,
+        (TaskProgress::Running, Some(_)) => ProgressBarStatus::Normal,
+        (TaskProgress::Paused, _) => ProgressBarStatus::Paused,
+        (TaskProgress::Error, _) => ProgressBarStatus::Error,
+    };
+    ProgressBarState {
+        status: Some(status),
+        progress: percent.filter(|_| progress != TaskProgress::Done),
+    }
+}
+
+/// Shows progress on the window's taskbar button, so it can be followed while
+/// CARTA is in the background. On macOS and Linux the bar is on the app's
+/// Dock or launcher icon, and macOS also badges the icon with the percentage.
+fn set_task_progress(window: &WebviewWindow, progress: TaskProgress, fraction: Option<f64>) {
+    let state = progress_bar_state(progress, fraction);
+    #[cfg(target_os = "macos")]
+    let badge = state.progress.map(|percent| format!("{}%", percent));
+    if let Err(err) = window.set_progress_bar(state) {
+        eprintln!("Warning: failed to show progress: {}", err);
+    }
+    #[cfg(target_os = "macos")]
+    if let Err(err) = window.set_badge_label(badge) {
+        eprintln!("Warning: failed to show progress: {}", err);
+    }
+}
+
+/// Native title for a window showing `file`, which may be a path or a bare name,
+/// from the backend rooted at `folder`.
+fn window_title(file: Option<&str>, folder: Option<&str>) -> String {
+    let name = file
+        .and_then(|file| {
+            file.rsplit(['/', '\\'])
+                .find(|part| !part.trim().is_empty())
+        })
+        .map(str::trim);
+    [Some(WINDOW_TITLE), name, folder]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" — ")
+}
+
+/// The folder the backend of the window at `url` serves, for its title.
+fn window_folder_for_url(app: &AppHandle, url: &tauri::Url) -> Option<String> {
+    let state = app.state::<AppState>();
+    let port = frontend_protocol::backend_port(url)?;
+    let dir = if port == state.backend_port() {
+        state.base_dir.clone()
+    } else {
+        state.folder_backends.base_dir_for_port(port)?
+    };
+    Some(tilde_path(&dir, home_dir().as_deref()))
+}
+
+fn window_folder(window: &WebviewWindow) -> Option<String> {
+    window_folder_for_url(window.app_handle(), &window.url().ok()?)
+}
+
+/// `path` with the home folder shortened to `~`.
+fn tilde_path(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => Path::new("~").join(rest).display().to_string(),
+        None => path.display().to_string(),
+    }
+}
+
+/// The file-specific part of a frontend document title. The page title may
+/// already lead with "CARTA", so that prefix and any separator are dropped.
+fn file_from_document_title(title: &str) -> Option<&str> {
+    let title = title.trim();
+    let rest = title
+        .strip_prefix(WINDOW_TITLE)
+        .unwrap_or(title)
+        .trim_start_matches([' ', '-', '—', '–', ':', '|'])
+        .trim();
+    (!rest.is_empty()).then_some(rest)
+}
+
+fn create_window(
+    app: &AppHandle,
+    state: &AppState,
+    label: String,
+    window_url: Option<&str>,
+) -> tauri::Result<WebviewWindow> {
+    let bounds = next_window_bounds(app);
+    let label_for_state = label.clone();
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    let navigation_label = label.clone();
+    let url = window_url
+        .map(ToString::to_string)
+        .unwrap_or_else(|| state.window_url());
+    let folder = tauri::Url::parse(&url)
+        .ok()
+        .and_then(|url| window_folder_for_url(app, &url));
+    let url = WebviewUrl::App(url.into());
+    let theme = state.settings.lock().unwrap().theme.as_theme();
+    let handle = app.clone();
+    let (controller, popup_controller) = (state.controller.clone(), state.controller.clone());
+    let sign_in_hosts = state
+        .settings
+        .lock()
+        .unwrap()
+        .controller_sign_in_hosts
+        .clone();
+    let mut builder = WebviewWindowBuilder::new(app, label, url)
+        .title(window_title(None, folder.as_deref()))
+        .on_document_title_changed(|window, title| {
+            let folder = window_folder(&window);
+            let _ = window.set_title(&window_title(
+                file_from_document_title(&title),
+                folder.as_deref(),
+            ));
+        })
+        .on_navigation(move |url| {
+            if connection_lost::handle_navigation(&handle, url) {
+                return false;
+            }
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            if certificates::handle_navigation(&handle, &navigation_label, url) {
+                return false;
+            }
+            if controller.as_ref().is_some_and(|controller| {
+                controller::keeps_navigation(controller, &sign_in_hosts, url)
+            }) {
+                return true;
+            }
+            if opens_externally(url) {
+                open_externally(url);
+                return false;
+            }
+            true
+        })
+        .on_new_window(move |url, _features| {
+            if popup_controller
+                .as_ref()
+                .is_some_and(|controller| controller::keeps_popup(controller, &url))
+            {
+                return tauri::webview::NewWindowResponse::Allow;
+            }
+            if opens_externally(&url) {
+                open_externally(&url);
+                return tauri::webview::NewWindowResponse::Deny;
+            }
+            tauri::webview::NewWindowResponse::Allow
+        })
+        .on_download(|webview, event| downloads::handle(&webview, event))
+        .on_page_load(|window, payload| {
+            if payload.event() == PageLoadEvent::Started {
+                forget_window_work(&window.state::<AppState>(), window.label());
+            }
+        })
+        .theme(theme)
+        .inner_size(bounds.width as f64, bounds.height as f64)
+        .position(bounds.x as f64, bounds.y as f64);
+
+    if let Some(profile) = &state.data_profile {
+        builder = with_profile(app, builder, profile);
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        builder = builder.additional_browser_args(&webview2_browser_args(state));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let menu = build_menu(app)?;
+        builder = builder.menu(menu).tabbing_identifier(WINDOW_TABBING_ID);
+    }
+
+    // Disable browser context menu (reload, inspect element, etc.)
+    builder = builder.initialization_script(
+        r#"document.addEventListener('contextmenu', function(e) { e.preventDefault(); });"#,
+    );
+
+    let window = builder.build()?;
+
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    certificates::watch(&window);
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(proxy) = state.proxy.lock().unwrap().as_ref() {
+            proxy::apply_to_webview(&window, proxy);
+        }
+        if state.disable_gpu.load(Ordering::Relaxed) {
+            disable_hardware_acceleration(&window);
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    apply_scale_factor(&window);
+
+    let _ = window.set_size(tauri::Size::Logical(tauri::LogicalSize::new(
+        bounds.width as f64,
+        bounds.height as f64,
+    )));
+
+    {
+        let mut labels = state.window_order.lock().unwrap();
+        if !labels.iter().any(|item| item == &label_for_state) {
+            labels.push(label_for_state);
+        }
+    }
+    let always_open_devtools = state.settings.lock().unwrap().always_open_devtools;
+    if is_kiosk(state) {
+        apply_kiosk_mode(&window, true);
+    } else if state.inspect || always_open_devtools || bounds.devtools_open {
+        window.open_devtools();
+    }
+    Ok(window)
+}
+
+#[cfg(target_os = "macos")]
+fn handle_menu_event(app: &AppHandle, state: &AppState, event: tauri::menu::MenuEvent) {
+    match event.id().as_ref() {
+        MENU_NEW_WINDOW => open_new_window(app, state),
+        MENU_NEW_WINDOW_WITH_FOLDER => folders::new_window_with_folder(app),
+        MENU_DUPLICATE_WINDOW => {
+            if let Some(window) = focused_window(app) {
+                duplicate_window(app, state, &window);
+            }
+        }
+        MENU_TOGGLE_FULLSCREEN => {
+            if let Some(window) = focused_window(app) {
+                toggle_fullscreen(&window);
+            }
+        }
+        MENU_TOGGLE_DEVTOOLS => {
+            if let Some(window) =
+                focused_window(app)
+            {
                 let _ = window.set_focus();
                 toggle_devtools(&window);
             }
         }
+        MENU_SHOW_TAB_BAR => {
+            if let Some(window) = focused_window(app) {
+                send_window_action(&window, objc2::sel!(toggleTabBar:));
+            }
+        }
+        MENU_MERGE_ALL_WINDOWS => {
+            if let Some(window) = focused_window(app) {
+                send_window_action(&window, objc2::sel!(mergeAllWindows:));
+            }
+        }
+        MENU_SAVE_SCREENSHOT => {
+            if let Some(window) = focused_window(app) {
+                screenshot::save_window_screenshot(&window);
+            }
+        }
+        MENU_OPEN_LOG_FOLDER => open_log_folder(app),
+        MENU_CLOSE_ALL_WINDOWS => close_all_windows(app),
+        MENU_QUIT => quit::quit(app),
+        MENU_COPY_AUTH_TOKEN => copy_auth_token(app),
+        MENU_COPY_BACKEND_COMMAND => copy_backend_command(app),
+        MENU_ADVANCED_START => advanced_start::show(app),
+        MENU_KIOSK_MODE => set_kiosk_mode(app, !is_kiosk(state)),
+        MENU_READ_ONLY => confirm_read_only(app, !is_read_only(state)),
+        MENU_NEXT_WINDOW => cycle_window_focus(app, true),
+        MENU_PREVIOUS_WINDOW => cycle_window_focus(app, false),
+        MENU_PREVENT_SLEEP => {
+            let enabled = !state.sleep_inhibitor.is_manual();
+            set_prevent_sleep(app, enabled);
+        }
+        MENU_ALWAYS_OPEN_DEVTOOLS => {
+            let enabled = !state.settings.lock().unwrap().always_open_devtools;
+            set_always_open_devtools(app, enabled);
+        }
         _ => {}
     }
 }
 
+/// Closes every window as closing them one at a time would: they hide to the
+/// tray when windows do, close when CARTA keeps running without them, and
+/// otherwise CARTA quits with them, asking first while an operation is
+/// running. Only one confirmation, not one per window.
+fn close_all_windows(app: &AppHandle) {
+    let windows: Vec<WebviewWindow> = app
+        .webview_windows()
+        .into_iter()
+        .filter(|(label, _)| is_session_window(label))
+        .map(|(_, window)| window)
+        .collect();
+    if tray::hides_to_tray(app) {
+        for window in &windows {
+            save_window_bounds(app, &window.as_ref().window());
+            let _ = window.hide();
+        }
+        return;
+    }
+    if !quits_on_last_window_close(app) {
+        for window in &windows {
+            let _ = window.close();
+        }
+        return;
+    }
+    quit::confirm(app, move |app| {
+        for window in &windows {
+            save_window_bounds(app, &window.as_ref().window());
+        }
+        app.exit(0);
+    });
+}
+
+/// Off macOS, a CARTA without windows can only be reached from its tray
+/// icon, so without one it quits whatever the setting says.
+fn quits_on_last_window_close(app: &AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let last_window_close = state.settings.lock().unwrap().last_window_close;
+    last_window_close.quits() || (!cfg!(target_os = "macos") && !tray::is_visible(app))
+}
+
+fn session_window_count(app: &AppHandle) -> usize {
+    app.webview_windows()
+        .into_keys()
+        .filter(|label| is_session_window(label))
+        .count()
+}
+
 fn remove_window_label(state: &AppState, label: &str) {
     state.window_order.lock().unwrap().retain(|l| l != label);
 }
 
+/// Forgets the progress and sleep holds a window reported; its operations end
+/// with its page.
+fn forget_window_work(state: &AppState, label: &str) {
+    state.busy_windows.lock().unwrap().remove(label);
+    state.sleep_inhibitor.release_window(label);
+}
+
+/// Forgets the work of the windows on the launch backend, whose operations a
+/// backend restart ends.
+fn forget_launch_backend_work(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    for (label, window) in app.webview_windows() {
+        let port = window
+            .url()
+            .ok()
+            .and_then(|url| frontend_protocol::backend_port(&url));
+        if is_session_window(&label) && port == Some(state.backend_port()) {
+            forget_window_work(&state, &label);
+        }
+    }
+}
+
+/// The rest of startup once the launch backend is spawned: waits for it, then
+/// opens the first window and starts the session's services. It runs as a
+/// task so the event loop is not held up meanwhile.
+async fn finish_startup(
+    app: AppHandle,
+    spawned: AppResult<()>,
+    smoke_test: Option<smoke::SmokeTest>,
+    initial_window_url: String,
+    restore_session: bool,
+    window_state: StartWindowState,
+    monitor: Option<usize>,
+) {
+    let state = app.state::<AppState>();
+    let mut initial_window_url = initial_window_url;
+    if state.controller.is_none() {
+        if let Err(err) = wait_for_launch_backend(&app, spawned).await {
+            exit_after_startup_failure(&app, ExitCode::BackendFailed, err);
+            return;
+        }
+        // The backend may have moved since the URL was built: in setup on
+        // Windows, or when a retry found its port taken.
+        if let Ok(url) = tauri::Url::parse(&initial_window_url)
+            && frontend_protocol::backend_port(&url)
+                .is_some_and(|port| port != state.backend_port())
+        {
+            initial_window_url =
+                frontend_protocol::with_backend_port(&url, state.backend_port()).to_string();
+        }
+    }
+    if let Some(test) = smoke_test {
+        smoke::run(&app, test, &state.window_url());
+        return;
+    }
+    telemetry::record_started();
+
+    #[cfg(target_os = "macos")]
+    {
+        let urls = state
+            .deferred_opened_urls
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_default();
+        if !urls.is_empty() {
+            handle_opened_urls(&app, &state, urls);
+        }
+    }
+    // Files opened with the app on macOS may already have windows.
+    let has_windows = |app: &AppHandle| {
+        app.webview_windows()
+            .into_keys()
+            .any(|label| is_session_window(&label))
+    };
+    if restore_session && !has_windows(&app) {
+        let restored = session::restore(&app).await;
+        trace!("restored {} windows of the previous session", restored);
+    }
+    if !has_windows(&app) {
+        match create_window(&app, &state, new_window_label(), Some(&initial_window_url)) {
+            Ok(window) => apply_start_window_state(&window, window_state, monitor),
+            Err(err) => {
+                exit_after_startup_failure(
+                    &app,
+                    ExitCode::Failure,
+                    AppError::Other(err.to_string()),
+                );
+                return;
+            }
+        }
+    }
+    state.tasks.spawn(instance::listen(app.clone()));
+    global_shortcut::register_from_settings(&app);
+    #[cfg(target_os = "windows")]
+    jump_list::update(&app);
+    // A controller starts the backends of its sessions itself.
+    if state.controller.is_some() {
+        return;
+    }
+    supervisor::start(&app);
+    updates::start(&app);
+    first_run::start_if_needed(&app);
+    if state.share_lan {
+        lan::show_share_window(&app);
+    }
+}
+
+/// Waits for the launch backend, starting it again after a pause when it
+/// failed in a way that may pass; see `AppError::is_transient`.
+async fn wait_for_launch_backend(app: &AppHandle, spawned: AppResult<()>) -> AppResult<()> {
+    let state = app.state::<AppState>();
+    let mut result = match spawned {
+        Ok(()) => wait_for_backend(&state, state.backend_timeout).await,
+        Err(err) => Err(err),
+    };
+    let mut attempt = 1;
+    while let Err(err) = &result
+        && err.is_transient()
+        && attempt < BACKEND_START_ATTEMPTS
+    {
+        let delay = backend_retry_delay(attempt);
+        eprintln!(
+            "Warning: the backend did not start (attempt {} of {}): {}; trying again in {}s",
+            attempt,
+            BACKEND_START_ATTEMPTS,
+            err,
+            delay.as_secs()
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+        result = supervisor::restart_backend(app).await;
+    }
+    result
+}
+
+fn backend_retry_delay(attempt: u32) -> Duration {
+    Duration::from_millis(BACKEND_RETRY_DELAY_MS << (attempt - 1))
+}
+
+/// Stops the backend and exits from the main thread, where setup failures
+/// show their error dialog too.
+fn exit_after_startup_failure(app: &AppHandle, stage: ExitCode, err: AppError) {
+    shutdown_backend(&app.state::<AppState>());
+    let code = err.exit_code().unwrap_or(stage);
+    let message = err.to_string();
+    if app.run_on_main_thread(move || code.exit(message)).is_err() {
+        code.exit(err);
+    }
+}
+
+/// Keeps files opened before the launch backend is ready for
+/// `finish_startup`; hands them back once it is.
+#[cfg(target_os = "macos")]
+fn defer_opened_urls(state: &AppState, urls: Vec<tauri::Url>) -> Option<Vec<tauri::Url>> {
+    match state.deferred_opened_urls.lock().unwrap().as_mut() {
+        Some(deferred) => {
+            deferred.extend(urls);
+            None
+        }
+        None => Some(urls),
+    }
+}
+
 fn shutdown_backend(state: &AppState) {
     if let Some(mut child) = state.backend.lock().unwrap().take() {
         let _ = child.kill();
@@ -1556,206 +5404,1067 @@ fn shutdown_backend(state: &AppState) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let cli = parse_cli_args();
-    if let Some(message) = cli.port_error.as_deref() {
-        eprintln!("Error: {}", message);
-        std::process::exit(1);
+    let mut cli = parse_cli_args();
+    if cli.verbose {
+        trace::enable();
+    }
+    ERROR_DIALOGS.store(wants_error_dialogs(&cli), Ordering::Relaxed);
+    trace!(
+        "launcher {} arguments: {:?}",
+        env!("CARGO_PKG_VERSION"),
+        launcher_args().collect::<Vec<_>>()
+    );
+    if let Some(message) = cli
+        .port_error
+        .as_deref()
+        .or(cli.profile_error.as_deref())
+        .or(cli.data_profile_error.as_deref())
+        .or(cli.layout_error.as_deref())
+        .or(cli.backend_timeout_error.as_deref())
+        .or(cli.env_error.as_deref())
+        .or(cli.wsl_distro_error.as_deref())
+        .or(cli.controller_error.as_deref())
+        .or(cli.monitor_error.as_deref())
+        .or(cli.scale_factor_error.as_deref())
+        .or(cli.bind_address_error.as_deref())
+    {
+        ExitCode::Usage.exit(format_args!("Error: {}", message));
     }
-    let mut base_dir = match resolve_base_directory(cli.input_path.as_deref()) {
+    let context = tauri::generate_context!();
+    let startup_settings = startup_settings(&context.config().identifier);
+    telemetry::init(
+        startup_settings.telemetry,
+        config_dir().map(|dir| dir.join(&context.config().identifier)),
+    );
+    #[cfg(target_os = "linux")]
+    select_display_backend(cli.display_backend, &startup_settings);
+    #[cfg(target_os = "windows")]
+    set_wsl_distro(
+        cli.wsl_distro
+            .clone()
+            .or_else(wsl_distro)
+            .or_else(|| startup_settings.wsl_distro.clone()),
+    );
+    #[cfg(target_os = "windows")]
+    let _ = WSL_SHELL.set(startup_settings.wsl_shell.clone());
+
+    let mut base_dir = match std::env::current_dir()
+        .map_err(AppError::from)
+        .and_then(|cwd| {
+            resolve_base_directory(
+                cwd,
+                cli.input_path.as_deref(),
+                startup_settings.default_data_dir.as_deref(),
+                ERROR_DIALOGS.load(Ordering::Relaxed),
+            )
+        }) {
         Ok(path) => path,
-        Err(message) => {
-            eprintln!("{}", message);
-            std::process::exit(1);
-        }
+        Err(message) => ExitCode::InvalidInput.exit(message),
     };
+    trace!("base directory: {}", base_dir.display());
 
     let mut input_files = Vec::new();
     if let Some(path) = match resolve_input_file_path(cli.input_path.as_deref(), None) {
         Ok(path) => path,
-        Err(message) => {
-            eprintln!("{}", message);
-            std::process::exit(1);
-        }
+        Err(message) => ExitCode::InvalidInput.exit(message),
     } {
         input_files.push(path);
     }
 
-    for arg in &cli.extra_args {
-        // Collect additional positional arguments that appear to be valid files.
-        if !arg.starts_with('-')
-            && let Ok(Some(path)) = resolve_input_file_path(Some(arg), None)
-        {
-            input_files.push(path);
+    let mut file_args = Vec::new();
+    for arg in &cli.extra_args {
+        // Collect additional positional arguments that appear to be valid files.
+        if !arg.starts_with('-')
+            && let Ok(Some(path)) = resolve_input_file_path(Some(arg), None)
+        {
+            input_files.push(path);
+            file_args.push(arg.as_str());
+        }
+    }
+
+    trace!("input files: {:?}", input_files);
+
+    // A folder that is not an image still starts its own session there.
+    let second_instance =
+        second_instance_action(&cli, &file_args, startup_settings.second_instance);
+    if second_instance != SecondInstance::Separate
+        && (cli.input_path.is_none() || !input_files.is_empty())
+        && let Some(dir) = config_dir().map(|dir| dir.join(&context.config().identifier))
+        && instance::forward_new_window(
+            &dir,
+            &input_files,
+            second_instance == SecondInstance::Focus,
+        )
+    {
+        trace!("handed the launch to the running instance");
+        std::process::exit(0);
+    }
+
+    // After input files are collected, so profile flag values are not taken
+    // for files.
+    if let Some(name) = &cli.profile {
+        match launch_profile(&startup_settings.profiles, name) {
+            Ok(profile) => apply_launch_profile(name, profile, &mut cli.extra_args),
+            Err(message) => ExitCode::Usage.exit(format_args!("Error: {}", message)),
+        }
+    }
+
+    // Tracked as a toggle so the menu can turn it off again.
+    if let Some(index) = cli.extra_args.iter().position(|arg| arg == READ_ONLY_ARG) {
+        cli.extra_args.remove(index);
+        cli.read_only = true;
+    }
+
+    let passes_no_auth = cli.extra_args.iter().any(|arg| arg == NO_AUTH_ARG);
+    if passes_no_auth && !cli.insecure_no_auth {
+        ExitCode::Usage.exit(format_args!(
+            "Error: {} is only accepted together with --insecure-no-auth.",
+            NO_AUTH_ARG
+        ));
+    }
+    if cli.insecure_no_auth && !passes_no_auth {
+        cli.extra_args.push(NO_AUTH_ARG.to_string());
+    }
+
+    // Checked like `--bind-address`.
+    let setting_address = startup_settings.bind_address.filter(|address| {
+        if address.is_unspecified() {
+            eprintln!(
+                "Warning: ignoring bind_address setting {} (use --share-lan for all interfaces)",
+                address
+            );
         }
+        !address.is_unspecified()
+    });
+    let passes_host = host_arg(&cli.extra_args).is_some();
+    let bind_address = cli.bind_address.or(setting_address);
+    if cli.share_lan && !passes_host {
+        cli.extra_args.push(lan::BIND_ALL_ARG.to_string());
+    } else if let Some(address) = bind_address.filter(|_| !passes_host) {
+        cli.extra_args.push(format!("--host={}", address));
+    }
+    // From the `--host` backends get, which a passed one overrides.
+    if let Some(address) = host_arg(&cli.extra_args).and_then(reach_address) {
+        let _ = BIND_ADDRESS.set(address);
     }
 
     let backend_port = match cli.port {
-        Some(port) => port,
+        Some(port) if !port_is_free(port) => {
+            ExitCode::PortUnavailable.exit(format_args!("Error: Port {} is already in use.", port))
+        }
+        Some(port) => {
+            trace!("port {} from the command line", port);
+            port
+        }
         None => match portpicker::pick_unused_port() {
-            Some(port) => port,
-            None => {
-                eprintln!("Error: No free port available.");
-                std::process::exit(1);
+            Some(port) => {
+                trace!("picked free port {}", port);
+                port
             }
+            None => ExitCode::PortUnavailable.exit("Error: No free port available."),
         },
     };
     let backend_token = uuid::Uuid::new_v4().to_string();
-    let window_url = format!("http://localhost:{}/?token={}", backend_port, backend_token);
+    // The smoke test fetches the page from the backend.
+    let frontend_from_app = startup_settings.frontend_from_app && cli.smoke_test.is_none();
+    let window_url = backend_window_url(
+        frontend_from_app,
+        backend_port,
+        (!cli.insecure_no_auth).then_some(backend_token.as_str()),
+    );
+    let window_url = match &cli.layout {
+        Some(layout) => with_layout(&window_url, layout),
+        None => window_url,
+    };
+    let controller = cli.controller.clone().or_else(|| {
+        let value = startup_settings.controller_url.as_deref()?;
+        controller::parse_url(value)
+            .map_err(|err| eprintln!("Warning: ignoring controller_url setting: {}", err))
+            .ok()
+    });
+    let window_url = match &controller {
+        Some(url) => {
+            trace!("connecting to controller {}", url);
+            if !input_files.is_empty() {
+                eprintln!("Warning: files are not opened through a CARTA controller");
+            }
+            url.to_string()
+        }
+        None => window_url,
+    };
     let top_level_folder =
         resolve_top_level_folder(&cli.extra_args).unwrap_or_else(|| "/".to_string());
     let top_level_path = match resolve_top_level_path(&top_level_folder) {
         Ok(path) => path,
-        Err(message) => {
-            eprintln!("{}", message);
-            std::process::exit(1);
-        }
+        Err(message) => ExitCode::InvalidInput.exit(message),
     };
     base_dir = ensure_base_dir_within_top_level(base_dir, &top_level_path);
+    trace!(
+        "top-level folder: {} ({}); base directory: {}",
+        top_level_folder,
+        top_level_path.display(),
+        base_dir.display()
+    );
     let initial_window_url = build_window_url(&window_url, &input_files, &top_level_path)
+        .filter(|_| controller.is_none())
         .unwrap_or_else(|| window_url.clone());
 
     let state = AppState {
         backend: Mutex::new(None),
-        backend_port,
+        backend_port: AtomicU16::new(backend_port),
         backend_token,
-        window_url,
+        base_dir: base_dir.clone(),
+        backend_args: Mutex::new(cli.extra_args.clone()),
+        backend_log: Arc::default(),
+        backend_restarting: AtomicBool::new(false),
+        busy_windows: Mutex::default(),
+        tasks: supervisor::BackgroundTasks::default(),
+        processes: Box::new(SystemRunner),
+        folder_backends: folders::FolderBackends::default(),
+        downloads: downloads::Downloads::default(),
+        save_targets: exports::SaveTargets::default(),
+        proxy: Mutex::new(None),
+        window_url: Mutex::new(window_url),
+        controller: controller.clone(),
+        frontend_from_app,
+        backend_timeout: Duration::from_secs(cli.backend_timeout.unwrap_or(BACKEND_TIMEOUT_SECS)),
+        backend_env: cli.env.clone(),
         inspect: cli.inspect,
+        kiosk: AtomicBool::new(cli.kiosk),
+        share_lan: cli.share_lan,
+        no_auth: cli.insecure_no_auth,
+        read_only: AtomicBool::new(cli.read_only),
+        data_profile: cli.data_profile.clone(),
+        disable_gpu: AtomicBool::new(cli.disable_gpu),
+        scale_factor: cli.scale_factor.or(startup_settings
+            .scale_factor
+            .filter(|factor| (MIN_SCALE_FACTOR..=MAX_SCALE_FACTOR).contains(factor))),
         window_order: Mutex::new(Vec::new()),
+        settings: Mutex::new(Settings::default()),
+        sleep_inhibitor: power::SleepInhibitor::default(),
+        #[cfg(any(target_os = "linux", target_os = "windows"))]
+        refused_certificates: certificates::Refused::default(),
+        resource_monitor: monitor::ResourceMonitor::default(),
+        pending_bounds_save: Mutex::new(HashMap::new()),
         #[cfg(target_os = "macos")]
-        top_level_path,
+        deferred_opened_urls: Mutex::new(Some(Vec::new())),
+        #[cfg(target_os = "windows")]
+        backend_job: Mutex::new(None),
     };
 
     let extra_args = cli.extra_args.clone();
+    let smoke_test = cli.smoke_test;
+    let window_state = cli
+        .window_state
+        .unwrap_or(startup_settings.start_window_state);
+    let monitor = cli.monitor.or(startup_settings.start_monitor);
+    // Launching with a path asks for that path instead.
+    let restore_session = startup_settings.restore_session
+        && cli.input_path.is_none()
+        && !cli.kiosk
+        && smoke_test.is_none();
     let mut builder = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(global_shortcut::plugin())
         .manage(state)
         .invoke_handler(tauri::generate_handler![
             cmd_new_window,
+            cmd_new_window_with_folder,
+            cmd_duplicate_window,
+            cmd_focus_next_window,
+            cmd_focus_previous_window,
+            cmd_list_windows,
+            cmd_focus_window,
+            cmd_get_kiosk_mode,
+            cmd_set_kiosk_mode,
+            cmd_get_read_only,
+            cmd_set_read_only,
             cmd_toggle_fullscreen,
             cmd_toggle_devtools,
+            cmd_set_progress,
+            cmd_save_window_screenshot,
+            cmd_open_log_folder,
+            cmd_show_advanced_start,
+            cmd_show_lan_share,
             cmd_close_window,
-            cmd_quit_app
+            cmd_close_all_windows,
+            cmd_set_window_file,
+            cmd_get_settings,
+            cmd_set_always_open_devtools,
+            cmd_get_language,
+            cmd_set_language,
+            cmd_set_theme,
+            cmd_set_tray_icon,
+            cmd_set_minimize_to_tray,
+            cmd_set_restart_policy,
+            cmd_set_backend_timeouts,
+            cmd_set_backend_limits,
+            cmd_set_disable_gpu,
+            cmd_set_start_window_state,
+            cmd_set_start_monitor,
+            cmd_set_scale_factor,
+            cmd_set_second_instance,
+            cmd_set_last_window_close,
+            cmd_set_new_window_shortcut,
+            cmd_choose_default_data_dir,
+            cmd_set_default_data_dir,
+            cmd_export_settings,
+            cmd_import_settings,
+            cmd_set_check_for_updates,
+            cmd_set_wsl_shell,
+            cmd_set_restore_session,
+            cmd_set_controller_url,
+            cmd_reveal_path,
+            cmd_copy_backend_command,
+            cmd_open_file_dialog,
+            cmd_save_file_dialog,
+            cmd_write_file,
+            cmd_set_frontend_from_app,
+            cmd_get_backend_choices,
+            cmd_set_backend_choice,
+            cmd_set_display_backend,
+            cmd_set_download_dir,
+            cmd_set_proxy,
+            cmd_set_telemetry,
+            cmd_run_setup_wizard,
+            cmd_register_file_associations,
+            cmd_get_telemetry_preview,
+            cmd_inhibit_sleep,
+            cmd_release_sleep,
+            cmd_get_prevent_sleep,
+            cmd_set_prevent_sleep,
+            cmd_get_backend_status,
+            cmd_get_backend_log_tail,
+            cmd_quit_app,
+            cmd_set_confirm_quit_when_busy
         ]);
 
-    #[cfg(target_os = "macos")]
-    {
-        builder = builder
-            .menu(build_menu)
-            .on_menu_event(|app, event| {
-                let state = app.state::<AppState>();
-                handle_menu_event(app, &state, event);
-            });
+    builder = builder.register_uri_scheme_protocol(connection_lost::SCHEME, |ctx, request| {
+        connection_lost::handle(ctx.app_handle(), &request)
+    });
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    {
+        builder = builder.register_uri_scheme_protocol(certificates::SCHEME, |ctx, request| {
+            certificates::handle(ctx.app_handle(), &request)
+        });
+    }
+    if frontend_from_app {
+        builder = builder
+            .register_uri_scheme_protocol(frontend_protocol::SCHEME, |ctx, request| {
+                frontend_protocol::handle(ctx.app_handle(), &request)
+            });
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        builder = builder
+            .menu(build_menu)
+            .on_menu_event(|app, event| {
+                let state = app.state::<AppState>();
+                handle_menu_event(app, &state, event);
+            });
+    }
+
+    let app = builder
+        .setup(move |app| {
+            let state = app.state::<AppState>();
+            // Settings pick the backend `--help` runs.
+            load_settings(app.handle(), &state);
+            #[cfg(target_os = "windows")]
+            if let Some(distro) = &cli.wsl_distro {
+                update_settings(app.handle(), |settings| {
+                    settings.wsl_distro = Some(distro.clone())
+                });
+            }
+            if cli.version {
+                print_version(app.handle(), cli.json);
+                std::process::exit(0);
+            }
+            if cli.help {
+                match run_backend_help(app.handle()) {
+                    Ok(()) => std::process::exit(0),
+                    Err(err) => ExitCode::BackendFailed.exit(err),
+                }
+            }
+
+            trace::open_log_file(app.handle());
+            if let Err(err) = allow_backend_host(app.handle()) {
+                eprintln!("Warning: failed to allow pages from the backend: {}", err);
+            }
+            if cli.print_backend_command {
+                if let Err(err) = validate_backend_args(&extra_args) {
+                    ExitCode::Usage.exit(err);
+                }
+                if let Err(err) =
+                    print_backend_command(app.handle(), &state, &base_dir, &extra_args)
+                {
+                    err.exit_code()
+                        .unwrap_or(ExitCode::BackendMissing)
+                        .exit(err);
+                }
+                std::process::exit(0);
+            }
+            apply_theme(app.handle());
+            tray::sync_tray(app.handle());
+            monitor::start_tray_updates(app.handle());
+            #[cfg(target_os = "macos")]
+            {
+                // The app menu was built before settings were loaded.
+                rebuild_menus(app.handle());
+                services::register(app.handle());
+            }
+            // The spawn result when it may still succeed on a retry, which
+            // `finish_startup` makes off the main thread.
+            let result: Result<AppResult<()>, (ExitCode, AppError)> = (|| {
+                validate_backend_args(&extra_args).map_err(|err| (ExitCode::Usage, err))?;
+                if state.controller.is_some() {
+                    return Ok(Ok(()));
+                }
+                resolve_resource_dir(app.handle())
+                    .ok_or(AppError::ResourceDirNotFound)
+                    .and_then(|dir| {
+                        let backend = resolve_backend_binary(&dir, backend_choice(app.handle()));
+                        check_bundle(app.handle(), &dir, &backend)?;
+                        backend
+                    })
+                    .map_err(|err| (ExitCode::BackendMissing, err))?;
+
+                #[cfg(target_os = "windows")]
+                {
+                    wsl_setup::ensure_ready(&*state.processes)
+                        .map_err(|err| (ExitCode::BackendMissing, err))?;
+                    // The port was only checked on the Windows side so far.
+                    if cli.port.is_some()
+                        && !backend_port_is_free(&*state.processes, state.backend_port())
+                    {
+                        return Err((
+                            ExitCode::PortUnavailable,
+                            AppError::Other(format!(
+                                "Port {} is already in use in WSL.",
+                                state.backend_port()
+                            )),
+                        ));
+                    }
+                    free_launch_port(&state).map_err(|err| (ExitCode::PortUnavailable, err))?;
+                }
+                preflight::run(app.handle(), &base_dir);
+                if state.no_auth {
+                    warn_no_auth(app.handle(), state.backend_port());
+                }
+                match spawn_backend(app.handle(), &state, &base_dir, &extra_args) {
+                    Err(err) if !err.is_transient() => Err((ExitCode::BackendFailed, err)),
+                    spawned => Ok(spawned),
+                }
+            })();
+
+            let spawned = match result {
+                Ok(spawned) => spawned,
+                Err((stage, err)) => {
+                    shutdown_backend(&state);
+                    err.exit_code().unwrap_or(stage).exit(err);
+                }
+            };
+            if !input_files.is_empty() {
+                update_settings(app.handle(), |settings| {
+                    settings.add_recent_files(&input_files)
+                });
+            }
+            state.tasks.spawn(finish_startup(
+                app.handle().clone(),
+                spawned,
+                smoke_test,
+                initial_window_url,
+                restore_session,
+                window_state,
+                monitor,
+            ));
+            Ok(())
+        })
+        .on_window_event(|window, event| match event {
+            _ if !is_session_window(window.label()) => {}
+            WindowEvent::Resized(_)
+                if window.is_minimized().unwrap_or(false)
+                    && tray::hides_to_tray(window.app_handle()) =>
+            {
+                let _ = window.hide();
+            }
+            #[cfg(not(target_os = "windows"))]
+            WindowEvent::ScaleFactorChanged { .. } => {
+                if let Some(window) = window.app_handle().get_webview_window(window.label()) {
+                    apply_scale_factor(&window);
+                }
+            }
+            WindowEvent::Moved(_) | WindowEvent::Resized(_) | WindowEvent::Focused(true) => {
+                schedule_save_window_bounds(window.app_handle(), window);
+            }
+            WindowEvent::CloseRequested { api, .. } if tray::hides_to_tray(window.app_handle()) => {
+                save_window_bounds(window.app_handle(), window);
+                api.prevent_close();
+                let _ = window.hide();
+            }
+            WindowEvent::CloseRequested { api, .. }
+                if session_window_count(window.app_handle()) <= 1
+                    && quits_on_last_window_close(window.app_handle())
+                    && quit::needs_confirmation(window.app_handle()) =>
+            {
+                api.prevent_close();
+                let app = window.app_handle().clone();
+                let window = window.clone();
+                quit::confirm(&app, move |app| {
+                    save_window_bounds(app, &window);
+                    app.exit(0);
+                });
+            }
+            WindowEvent::CloseRequested { .. } => {
+                let app = window.app_handle();
+                let state = app.state::<AppState>();
+                remove_window_label(&state, window.label());
+                save_window_bounds(app, window);
+                if session_window_count(app) <= 1 && quits_on_last_window_close(app) {
+                    app.exit(0);
+                }
+            }
+            WindowEvent::Destroyed => {
+                let app = window.app_handle();
+                let state = app.state::<AppState>();
+                forget_window_work(&state, window.label());
+                state.folder_backends.stop_unused(app, window.label());
+            }
+            _ => {}
+        })
+        .build(context);
+
+    let app = match app {
+        Ok(app) => app,
+        Err(err) => ExitCode::Failure.exit(err),
+    };
+
+    app.run(move |app_handle, event| match event {
+        // The last window has closed, but CARTA keeps running without one.
+        RunEvent::ExitRequested {
+            code: None, api, ..
+        } if !quits_on_last_window_close(app_handle) => {
+            api.prevent_exit();
+        }
+        RunEvent::ExitRequested { .. } => {
+            let state = app_handle.state::<AppState>();
+            if state.settings.lock().unwrap().restore_session {
+                session::save(app_handle);
+            }
+            state.tasks.abort_all();
+            state.sleep_inhibitor.release_all();
+            state.folder_backends.shutdown_all();
+            shutdown_backend(&state);
+            instance::release();
+        }
+        #[cfg(target_os = "macos")]
+        RunEvent::Opened { urls } => {
+            let state = app_handle.state::<AppState>();
+            if let Some(urls) = defer_opened_urls(&state, urls) {
+                handle_opened_urls(app_handle, &state, urls);
+            }
+        }
+        // Clicking the Dock icon while every window is hidden to the tray, or
+        // closed while CARTA keeps running.
+        // During startup, `finish_startup` opens the first window itself.
+        #[cfg(target_os = "macos")]
+        RunEvent::Reopen {
+            has_visible_windows: false,
+            ..
+        } => {
+            let state = app_handle.state::<AppState>();
+            let starting = state.deferred_opened_urls.lock().unwrap().is_some();
+            if !starting {
+                tray::show_all_windows(app_handle);
+            }
+        }
+        _ => {}
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> CliArgs {
+        parse_cli_args_from(args.iter().map(|arg| (*arg).to_string()))
+    }
+
+    #[test]
+    fn parse_cli_args_respects_double_dash() {
+        let parsed = parse_args(&["--", "--inspect", "-weird"]);
+        assert_eq!(parsed.input_path.as_deref(), Some("--inspect"));
+        assert_eq!(parsed.extra_args, vec!["-weird"]);
+        assert!(!parsed.inspect);
+    }
+
+    #[test]
+    fn parse_cli_args_recognizes_kiosk() {
+        let parsed = parse_args(&["--kiosk", "file.fits"]);
+        assert!(parsed.kiosk);
+        assert_eq!(parsed.input_path.as_deref(), Some("file.fits"));
+        assert!(parsed.extra_args.is_empty());
+    }
+
+    #[test]
+    fn parse_cli_args_collects_env() {
+        let parsed = parse_args(&["--env", "CARTA_X=1", "--env=OMP_PROC_BIND=a=b", "file.fits"]);
+        assert_eq!(
+            parsed.env,
+            [
+                ("CARTA_X".to_string(), "1".to_string()),
+                ("OMP_PROC_BIND".to_string(), "a=b".to_string()),
+            ]
+        );
+        assert_eq!(parsed.input_path.as_deref(), Some("file.fits"));
+        assert!(parse_args(&["--env"]).env_error.is_some());
+        assert!(parse_args(&["--env", "NOVALUE"]).env_error.is_some());
+        assert!(parse_args(&["--env", "1X=a"]).env_error.is_some());
+    }
+
+    #[test]
+    fn parse_cli_args_recognizes_wsl_distro() {
+        let parsed = parse_args(&["--wsl-distro", "Ubuntu-24.04", "file.fits"]);
+        assert_eq!(parsed.wsl_distro.as_deref(), Some("Ubuntu-24.04"));
+        assert_eq!(parsed.input_path.as_deref(), Some("file.fits"));
+        assert_eq!(
+            parse_args(&["--wsl-distro=Debian"]).wsl_distro.as_deref(),
+            Some("Debian")
+        );
+        assert!(parse_args(&["--wsl-distro"]).wsl_distro_error.is_some());
+        assert!(parse_args(&["--wsl-distro="]).wsl_distro_error.is_some());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn host_path_resolves_relative_paths_under_top_level() {
+        let top_level = Path::new("/data");
+        assert_eq!(
+            host_path(top_level, "alma/m51.fits"),
+            Some(PathBuf::from("/data/alma/m51.fits"))
+        );
+        assert_eq!(
+            host_path(top_level, "/scratch/export.png"),
+            Some(PathBuf::from("/scratch/export.png"))
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn wsl_to_win_path_maps_drives_and_distro_files() {
+        assert_eq!(
+            wsl_to_win_path("/mnt/c/data/m51.fits", None).as_deref(),
+            Some(r"C:\data\m51.fits")
+        );
+        assert_eq!(
+            wsl_to_win_path("/home/astro/m51.fits", Some("Ubuntu")).as_deref(),
+            Some(r"\\wsl.localhost\Ubuntu\home\astro\m51.fits")
+        );
+        assert_eq!(wsl_to_win_path("/home/astro", None), None);
+        assert_eq!(
+            wsl_to_win_path(&win_to_wsl_path(r"D:\données").unwrap(), None).as_deref(),
+            Some(r"D:\données")
+        );
+    }
+
+    #[test]
+    fn version_report_lists_each_part() {
+        assert!(parse_args(&["--version", "--json"]).json);
+        let report = VersionReport {
+            carta: "5.0.3".to_string(),
+            tauri: "2.9.5",
+            webview: None,
+            backend: Some("5.0.3".to_string()),
+            frontend: Some("5.0.2".to_string()),
+        };
+        assert_eq!(
+            report.text(),
+            "CARTA 5.0.3\nTauri 2.9.5\nWeb view unknown\nBackend 5.0.3\nFrontend 5.0.2\n"
+        );
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["backend"], "5.0.3");
+        assert_eq!(json["webview"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn parse_backend_version_finds_dotted_number() {
+        assert_eq!(parse_backend_version("5.0.3\n").as_deref(), Some("5.0.3"));
+        assert_eq!(
+            parse_backend_version("carta_backend v4.1.0-beta.1").as_deref(),
+            Some("4.1.0-beta.1")
+        );
+        assert_eq!(parse_backend_version("unknown option"), None);
+    }
+
+    #[test]
+    fn same_release_compares_major_and_minor() {
+        assert!(same_release("5.0.2", "5.0.3"));
+        assert!(same_release("5.1.0-beta.1", "5.1.0"));
+        assert!(!same_release("4.1.0", "5.0.3"));
+        assert!(!same_release("5.1.0", "5.0.3"));
+    }
+
+    #[test]
+    fn frontend_version_reads_package_json() {
+        let dir = std::env::temp_dir().join(format!("carta-frontend-{}", uuid::Uuid::new_v4()));
+        let build = dir.join("build");
+        fs::create_dir_all(&build).unwrap();
+        assert_eq!(frontend_version(&build), None);
+        fs::write(
+            dir.join("package.json"),
+            r#"{"name": "carta-frontend", "version": "5.0.2"}"#,
+        )
+        .unwrap();
+        assert_eq!(frontend_version(&build).as_deref(), Some("5.0.2"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_cli_args_recognizes_controller() {
+        let parsed = parse_args(&["--controller", "https://carta.example.edu/"]);
+        assert_eq!(
+            parsed.controller.as_ref().map(tauri::Url::as_str),
+            Some("https://carta.example.edu/")
+        );
+        assert_eq!(parsed.input_path, None);
+        assert!(
+            parse_args(&["--controller=http://localhost:8000"])
+                .controller
+                .is_some()
+        );
+        assert!(parse_args(&["--controller"]).controller_error.is_some());
+        assert!(
+            parse_args(&["--controller=carta.example.edu"])
+                .controller_error
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn forwarded_env_keeps_user_carta_vars_and_overrides() {
+        let vars = [
+            ("CARTA_TIMEOUT", "5"),
+            ("CARTA_AUTH_TOKEN", "user"),
+            ("CARTA_BACKEND_DIR", r"C:\carta"),
+            ("PATH", "/usr/bin"),
+            ("CARTA_LEVEL", "old"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let overrides = [("CARTA_LEVEL".to_string(), "new".to_string())];
+        assert_eq!(
+            forwarded_env(vars, &overrides),
+            [
+                ("CARTA_TIMEOUT".to_string(), "5".to_string()),
+                ("CARTA_LEVEL".to_string(), "new".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn wslenv_with_adds_the_variable_once() {
+        assert_eq!(wslenv_with(None, ENV_AUTH_TOKEN), "CARTA_AUTH_TOKEN");
+        assert_eq!(
+            wslenv_with(Some("USERPROFILE/p:CARTA_AUTH_TOKEN/p"), ENV_AUTH_TOKEN),
+            "USERPROFILE/p:CARTA_AUTH_TOKEN"
+        );
+    }
+
+    #[test]
+    fn parse_cli_args_recognizes_backend_timeout() {
+        assert_eq!(
+            parse_args(&["--backend-timeout", "120"]).backend_timeout,
+            Some(120)
+        );
+        assert_eq!(
+            parse_args(&["--backend-timeout=90", "file.fits"]).backend_timeout,
+            Some(90)
+        );
+        assert!(
+            parse_args(&["--backend-timeout", "0"])
+                .backend_timeout_error
+                .is_some()
+        );
+        assert!(
+            parse_args(&["--backend-timeout=soon"])
+                .backend_timeout_error
+                .is_some()
+        );
+        assert!(
+            parse_args(&["--backend-timeout"])
+                .backend_timeout_error
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn parse_cli_args_recognizes_layout() {
+        let parsed = parse_args(&["--layout", "classroom", "file.fits"]);
+        assert_eq!(parsed.layout.as_deref(), Some("classroom"));
+        assert_eq!(parsed.input_path.as_deref(), Some("file.fits"));
+        assert_eq!(
+            parse_args(&["--layout=Spectral Analysis"])
+                .layout
+                .as_deref(),
+            Some("Spectral Analysis")
+        );
+        assert!(parse_args(&["--layout"]).layout_error.is_some());
+        assert!(parse_args(&["--layout="]).layout_error.is_some());
+    }
+
+    #[test]
+    fn with_layout_adds_encoded_query_parameter() {
+        assert_eq!(
+            with_layout("http://localhost:3002/?token=abc", "Spectral Analysis"),
+            "http://localhost:3002/?token=abc&layout=Spectral+Analysis"
+        );
+        assert_eq!(
+            with_layout("http://localhost:3002/", "classroom"),
+            "http://localhost:3002/?layout=classroom"
+        );
+    }
+
+    #[test]
+    fn parse_cli_args_recognizes_new_window() {
+        let parsed = parse_args(&["--new-window", "file.fits"]);
+        assert!(parsed.new_window);
+        assert_eq!(parsed.input_path.as_deref(), Some("file.fits"));
+        assert!(parsed.extra_args.is_empty());
+    }
+
+    #[test]
+    fn parse_cli_args_recognizes_launcher_network_flags() {
+        let parsed = parse_args(&["--share-lan", "--insecure-no-auth", "file.fits"]);
+        assert!(parsed.share_lan);
+        assert!(parsed.insecure_no_auth);
+        assert_eq!(parsed.input_path.as_deref(), Some("file.fits"));
+        assert!(parsed.extra_args.is_empty());
+    }
+
+    #[test]
+    fn parse_cli_args_recognizes_diagnostic_flags() {
+        let parsed = parse_args(&["--verbose", "--print-backend-command", "--verbosity=5"]);
+        assert!(parsed.verbose);
+        assert!(parsed.print_backend_command);
+        assert!(parsed.smoke_test.is_none());
+        assert_eq!(parsed.extra_args, vec!["--verbosity=5"]);
+    }
+
+    #[test]
+    fn parse_cli_args_recognizes_smoke_test() {
+        let parsed = parse_args(&["--smoke-test"]);
+        assert_eq!(parsed.smoke_test, Some(smoke::SmokeTest::Backend));
+        let parsed = parse_args(&["--smoke-test-webview", "file.fits"]);
+        assert_eq!(parsed.smoke_test, Some(smoke::SmokeTest::Webview));
+        assert_eq!(parsed.input_path.as_deref(), Some("file.fits"));
+    }
+
+    #[test]
+    fn decode_wsl_output_reads_utf16() {
+        let bytes: Vec<u8> = "Ubuntu\r\nDebian\r\n\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let decoded = decode_wsl_output(&bytes);
+        assert_eq!(decoded.lines().collect::<Vec<_>>(), ["Ubuntu", "Debian"]);
+    }
+
+    #[test]
+    fn listening_ports_reads_proc_net_tcp() {
+        let tables = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0BB9 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1
+   1: 0100007F:D6E2 0100007F:0BB9 01 00000000:00000000 00:00000000 00000000  1000        0 2
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 3
+";
+        assert_eq!(listening_ports(tables), HashSet::from([3001, 8080]));
     }
 
-    let app = builder
-        .setup(move |app| {
-            if cli.help || cli.version {
-                match run_backend_help(app.handle(), cli.version) {
-                    Ok(()) => std::process::exit(0),
-                    Err(err) => {
-                        eprintln!("{}", err);
-                        std::process::exit(1);
-                    }
-                }
-            }
+    #[test]
+    fn port_is_free_detects_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(!port_is_free(port));
+        drop(listener);
+        assert!(port_is_free(port));
+    }
 
-            let state = app.state::<AppState>();
-            let result: AppResult<()> = (|| {
-                validate_backend_args(&extra_args)?;
+    #[test]
+    fn command_line_quotes_only_where_needed() {
+        let mut cmd = Command::new("/opt/carta/bin/carta_backend");
+        cmd.arg("/home/me/My Data")
+            .arg("--port=3002")
+            .arg("--top_level_folder=/")
+            .arg("it's");
+        assert_eq!(
+            command_line(&cmd),
+            "/opt/carta/bin/carta_backend '/home/me/My Data' --port=3002 --top_level_folder=/ 'it'\"'\"'s'"
+        );
+        assert_eq!(shell_quote(""), "''");
+    }
 
-                spawn_backend(app.handle(), &state, &base_dir, &extra_args)?;
-                if let Err(err) =
-                    wait_for_backend(&state, Duration::from_secs(BACKEND_TIMEOUT_SECS))
-                {
-                    shutdown_backend(&state);
-                    return Err(err);
-                }
+    #[test]
+    fn parse_cli_args_reads_profile_name() {
+        let parsed = parse_args(&["--profile", "survey-a", "file.fits"]);
+        assert_eq!(parsed.profile.as_deref(), Some("survey-a"));
+        assert_eq!(parsed.input_path.as_deref(), Some("file.fits"));
 
-                #[cfg(not(target_os = "macos"))]
-                {
-                    create_window(
-                        app.handle(),
-                        &state,
-                        new_window_label(),
-                        Some(&initial_window_url),
-                    )
-                    .map_err(|err| AppError(err.to_string()))?;
-                }
-                Ok(())
-            })();
+        let parsed = parse_args(&["--profile=lab_2"]);
+        assert_eq!(parsed.profile.as_deref(), Some("lab_2"));
 
-            if let Err(err) = result {
-                eprintln!("{}", err);
-                shutdown_backend(&state);
-                std::process::exit(1);
-            }
-            Ok(())
-        })
-        .on_window_event(|window, event| match event {
-            WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
-                save_window_bounds(window.app_handle(), window);
-            }
-            WindowEvent::CloseRequested { .. } => {
-                let app = window.app_handle();
-                let state = app.state::<AppState>();
-                remove_window_label(&state, window.label());
-                save_window_bounds(app, window);
-                if app.webview_windows().len() <= 1 {
-                    app.exit(0);
-                }
-            }
-            _ => {}
-        })
-        .build(tauri::generate_context!());
+        let parsed = parse_args(&["--profile", "../other"]);
+        assert!(parsed.profile.is_none());
+        assert!(parsed.profile_error.is_some());
+    }
 
-    let app = match app {
-        Ok(app) => app,
-        Err(err) => {
-            eprintln!("{}", err);
-            std::process::exit(1);
-        }
-    };
+    #[test]
+    fn parse_cli_args_keeps_data_profile_apart() {
+        let parsed = parse_args(&["--data-profile", "survey-a", "--profile=dev"]);
+        assert_eq!(parsed.data_profile.as_deref(), Some("survey-a"));
+        assert_eq!(parsed.profile.as_deref(), Some("dev"));
+
+        let parsed = parse_args(&["--data-profile=../other"]);
+        assert!(parsed.data_profile.is_none());
+        assert!(parsed.data_profile_error.is_some());
+    }
 
-    app.run(move |app_handle, event| match event {
-        RunEvent::ExitRequested { .. } => {
-            let state = app_handle.state::<AppState>();
-            shutdown_backend(&state);
-        }
-        #[cfg(target_os = "macos")]
-        RunEvent::Ready => {
-            let state = app_handle.state::<AppState>();
-            if app_handle.webview_windows().is_empty() {
-                let _ = create_window(
-                    app_handle,
-                    &state,
-                    new_window_label(),
-                    Some(&initial_window_url),
-                );
-            }
-        }
-        #[cfg(target_os = "macos")]
-        RunEvent::Opened { urls } => {
-            let state = app_handle.state::<AppState>();
-            handle_opened_urls(app_handle, &state, urls);
-        }
-        _ => {}
-    });
-}
+    #[test]
+    fn launch_profile_rejects_unknown_names() {
+        let mut profiles = BTreeMap::new();
+        assert!(launch_profile(&profiles, "dev").is_err());
+        profiles.insert("dev".to_string(), LaunchProfile::default());
+        profiles.insert("stable".to_string(), LaunchProfile::default());
+        assert!(launch_profile(&profiles, "dev").is_ok());
+        assert_eq!(
+            launch_profile(&profiles, "dve").unwrap_err(),
+            "No launch profile named dve (configured: dev, stable)"
+        );
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn parse_cli_args_reads_display_backend() {
+        assert_eq!(parse_args(&[]).display_backend, None);
+        assert_eq!(
+            parse_args(&["--x11"]).display_backend,
+            Some(DisplayBackend::X11)
+        );
+        assert_eq!(
+            parse_args(&["--wayland", "file.fits"]).display_backend,
+            Some(DisplayBackend::Wayland)
+        );
+    }
 
-    fn parse_args(args: &[&str]) -> CliArgs {
-        parse_cli_args_from(args.iter().map(|arg| (*arg).to_string()))
+    #[test]
+    fn parse_cli_args_reads_window_state() {
+        assert_eq!(parse_args(&[]).window_state, None);
+        assert_eq!(
+            parse_args(&["--maximized", "file.fits"]).window_state,
+            Some(StartWindowState::Maximized)
+        );
+        assert_eq!(
+            parse_args(&["--minimized"]).window_state,
+            Some(StartWindowState::Minimized)
+        );
+        assert_eq!(
+            parse_args(&["--fullscreen"]).window_state,
+            Some(StartWindowState::Fullscreen)
+        );
     }
 
     #[test]
-    fn parse_cli_args_respects_double_dash() {
-        let parsed = parse_args(&["--", "--inspect", "-weird"]);
-        assert_eq!(parsed.input_path.as_deref(), Some("--inspect"));
-        assert_eq!(parsed.extra_args, vec!["-weird"]);
-        assert!(!parsed.inspect);
+    fn parse_cli_args_reads_monitor() {
+        assert_eq!(parse_args(&["--monitor", "1"]).monitor, Some(1));
+        assert_eq!(parse_args(&["--monitor=0", "file.fits"]).monitor, Some(0));
+        assert!(parse_args(&["--monitor", "left"]).monitor_error.is_some());
+        assert!(parse_args(&["--monitor"]).monitor_error.is_some());
+    }
+
+    #[test]
+    fn parse_cli_args_reads_bind_address() {
+        assert_eq!(
+            parse_args(&["--bind-address", "::1"]).bind_address,
+            Some(IpAddr::from(std::net::Ipv6Addr::LOCALHOST))
+        );
+        assert_eq!(
+            parse_args(&["--bind-address=192.168.1.20", "file.fits"]).bind_address,
+            Some(IpAddr::from([192, 168, 1, 20]))
+        );
+        assert!(
+            parse_args(&["--bind-address", "0.0.0.0"])
+                .bind_address_error
+                .is_some()
+        );
+        assert!(
+            parse_args(&["--bind-address", "localhost"])
+                .bind_address_error
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn backends_are_reached_at_their_host_address() {
+        let args = |values: &[&str]| values.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(host_arg(&args(&["--verbosity=4"])), None);
+        assert_eq!(
+            host_arg(&args(&["--host", "::1", "--host=192.168.1.20"])),
+            Some("192.168.1.20")
+        );
+        assert_eq!(
+            reach_address("[::1]"),
+            Some(IpAddr::from(std::net::Ipv6Addr::LOCALHOST))
+        );
+        assert_eq!(reach_address("0.0.0.0"), None);
+        assert_eq!(reach_address("localhost"), None);
+    }
+
+    #[test]
+    fn parse_cli_args_reads_scale_factor() {
+        assert_eq!(
+            parse_args(&["--scale-factor", "1.25"]).scale_factor,
+            Some(1.25)
+        );
+        assert_eq!(
+            parse_args(&["--scale-factor=2", "file.fits"]).scale_factor,
+            Some(2.0)
+        );
+        assert!(
+            parse_args(&["--scale-factor", "0"])
+                .scale_factor_error
+                .is_some()
+        );
+        assert!(
+            parse_args(&["--scale-factor=NaN"])
+                .scale_factor_error
+                .is_some()
+        );
+        assert!(parse_args(&["--scale-factor"]).scale_factor_error.is_some());
+    }
+
+    #[test]
+    fn second_instance_action_leaves_own_sessions_alone() {
+        let action =
+            |args: &[&str]| second_instance_action(&parse_args(args), &[], SecondInstance::Focus);
+        assert_eq!(action(&["m51.fits"]), SecondInstance::Focus);
+        assert_eq!(
+            second_instance_action(
+                &parse_args(&["m51.fits", "m82.fits"]),
+                &["m82.fits"],
+                SecondInstance::Focus
+            ),
+            SecondInstance::Focus
+        );
+        assert_eq!(action(&["m51.fits", "m82.fits"]), SecondInstance::Separate);
+        assert_eq!(action(&["--new-window"]), SecondInstance::NewWindow);
+        assert_eq!(action(&["--port", "3002"]), SecondInstance::Separate);
+        assert_eq!(action(&["--version"]), SecondInstance::Separate);
+        assert_eq!(action(&["--verbosity=5"]), SecondInstance::Separate);
+        assert_eq!(
+            second_instance_action(&parse_args(&[]), &[], SecondInstance::Separate),
+            SecondInstance::Separate
+        );
     }
 
     #[test]
@@ -1800,7 +6509,7 @@ mod tests {
 
     #[test]
     fn backend_validation_rejects_unknown_option_with_suggestion() {
-        let err = validate_backend_args(&vec!["--log_protocol".to_string()]).unwrap_err();
+        let err = validate_backend_args(&["--log_protocol".to_string()]).unwrap_err();
         assert!(
             err.to_string()
                 .contains("Unsupported backend option: --log_protocol")
@@ -1813,26 +6522,302 @@ mod tests {
 
     #[test]
     fn backend_validation_consumes_required_values() {
-        assert!(validate_backend_args(&vec!["--verbosity".to_string(), "5".to_string()]).is_ok());
+        assert!(validate_backend_args(&["--verbosity".to_string(), "5".to_string()]).is_ok());
         assert!(
-            validate_backend_args(&vec!["--http_url_prefix".to_string(), "/x".to_string()]).is_ok()
+            validate_backend_args(&["--http_url_prefix".to_string(), "/x".to_string()]).is_ok()
         );
-        assert!(validate_backend_args(&vec!["-p".to_string(), "3003".to_string()]).is_ok());
-        assert!(validate_backend_args(&vec!["--port=3003".to_string()]).is_ok());
+        assert!(validate_backend_args(&["-p".to_string(), "3003".to_string()]).is_ok());
+        assert!(validate_backend_args(&["--port=3003".to_string()]).is_ok());
     }
 
     #[test]
     fn backend_validation_errors_on_missing_value() {
-        let err = validate_backend_args(&vec!["--verbosity".to_string()]).unwrap_err();
+        let err = validate_backend_args(&["--verbosity".to_string()]).unwrap_err();
         assert!(err.to_string().contains("requires a value"));
     }
 
     #[test]
     fn backend_validation_errors_on_value_for_flag() {
-        let err = validate_backend_args(&vec!["--no_log=1".to_string()]).unwrap_err();
+        let err = validate_backend_args(&["--no_log=1".to_string()]).unwrap_err();
         assert!(err.to_string().contains("does not take a value"));
     }
 
+    #[test]
+    fn app_error_maps_to_exit_code() {
+        let err = AppError::ResourceNotFound {
+            resource: Resource::Frontend,
+            path: PathBuf::from("/opt/frontend"),
+            set_by: Some(ENV_FRONTEND_DIR),
+        };
+        assert_eq!(
+            err.to_string(),
+            "/opt/frontend not found (set by CARTA_FRONTEND_DIR)"
+        );
+        assert_eq!(err.exit_code(), Some(ExitCode::BackendMissing));
+        let err = validate_backend_args(&["--no_log=1".to_string()]).unwrap_err();
+        assert_eq!(err.exit_code(), Some(ExitCode::Usage));
+        assert_eq!(AppError::Other("failed".to_string()).exit_code(), None);
+    }
+
+    #[test]
+    fn missing_or_unrunnable_backend_is_not_retried() {
+        for kind in [io::ErrorKind::NotFound, io::ErrorKind::PermissionDenied] {
+            assert!(!AppError::Io(kind.into()).is_transient());
+        }
+        assert!(AppError::Io(io::ErrorKind::TimedOut.into()).is_transient());
+        assert!(AppError::Io(io::ErrorKind::AddrInUse.into()).is_transient());
+    }
+
+    #[test]
+    fn backend_rejecting_its_arguments_is_not_retried() {
+        let status = process::FakeRunner::with_results(&[(1, "")])
+            .output(&mut Command::new("carta_backend"))
+            .unwrap()
+            .status;
+        assert!(!AppError::BackendExited(status).is_transient());
+    }
+
+    #[test]
+    fn read_window_state_resets_corrupt_file() {
+        let dir = std::env::temp_dir().join(format!("carta-state-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(WINDOW_STATE_FILE);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, b"{\"width\": 12").unwrap();
+
+        let state = read_window_state(&path).unwrap();
+        assert_eq!(state.bounds.width, DEFAULT_WINDOW_WIDTH);
+        assert_eq!(state.bounds.height, DEFAULT_WINDOW_HEIGHT);
+
+        let rewritten: WindowState = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(rewritten.version, WINDOW_STATE_VERSION);
+        assert_eq!(rewritten.bounds.width, DEFAULT_WINDOW_WIDTH);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn newer_window_state_is_recognized() {
+        let dir = std::env::temp_dir().join(format!("carta-state-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(WINDOW_STATE_FILE);
+        fs::create_dir_all(&dir).unwrap();
+        assert!(!is_newer_window_state(&path));
+
+        let newer = serde_json::json!({ "version": WINDOW_STATE_VERSION + 1 });
+        fs::write(&path, newer.to_string()).unwrap();
+        assert!(is_newer_window_state(&path));
+        assert!(read_window_state(&path).is_none());
+
+        let current = WindowState::new(WindowBounds::default());
+        fs::write(&path, serde_json::to_vec(&current).unwrap()).unwrap();
+        assert!(!is_newer_window_state(&path));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn migrate_window_state_upgrades_unversioned_bounds() {
+        let legacy = serde_json::json!({ "width": 800, "height": 600, "x": 10, "y": 20 });
+        let state = migrate_window_state(legacy).unwrap();
+        assert_eq!(state.version, WINDOW_STATE_VERSION);
+        assert_eq!((state.bounds.width, state.bounds.height), (800, 600));
+        assert_eq!((state.bounds.x, state.bounds.y), (10, 20));
+        assert!(!state.bounds.devtools_open);
+        assert!(state.setups.is_empty());
+    }
+
+    #[test]
+    fn window_state_keeps_bounds_per_monitor_setup() {
+        let laptop = monitor_setup_key([PhysicalSize::new(2880, 1800)]).unwrap();
+        let docked =
+            monitor_setup_key([PhysicalSize::new(3840, 2160), PhysicalSize::new(2880, 1800)])
+                .unwrap();
+        assert_eq!(docked, "2880x1800,3840x2160");
+        assert_eq!(monitor_setup_key([]), None);
+
+        let bounds = |width| WindowBounds {
+            width,
+            ..WindowBounds::default()
+        };
+        let state = || {
+            let mut state = WindowState::new(bounds(1600));
+            state.setups.insert(laptop.clone(), bounds(1200));
+            state.setups.insert(docked.clone(), bounds(1600));
+            state
+        };
+        assert_eq!(state().bounds_for(Some(&laptop)).width, 1200);
+        assert_eq!(state().bounds_for(Some(&docked)).width, 1600);
+        assert_eq!(state().bounds_for(Some("1920x1080")).width, 1600);
+        assert_eq!(state().bounds_for(None).width, 1600);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn build_window_url_encodes_non_ascii_and_reserved_names() {
+        let base_url = "http://localhost:3000/?token=abc";
+        let top_level = Path::new("/home/張三");
+        let files = [
+            top_level.join("données/m51 #2.fits"),
+            top_level.join("R&D/a+b.fits"),
+        ];
+        assert_eq!(
+            build_window_url(base_url, &files[..1], top_level).as_deref(),
+            Some("http://localhost:3000/?token=abc&file=donn%C3%A9es/m51%20%232.fits")
+        );
+        let url = build_window_url(base_url, &files, top_level).unwrap();
+        let url = tauri::Url::parse(&url).unwrap();
+        let (_, value) = url.query_pairs().find(|(name, _)| name == "files").unwrap();
+        assert_eq!(value, "données/m51 #2.fits,R&D/a+b.fits");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn build_window_url_skips_names_that_are_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let top_level = Path::new("/data");
+        let latin1 = top_level.join(std::ffi::OsStr::from_bytes(b"caf\xe9.fits"));
+        assert_eq!(
+            build_window_url("http://localhost:3000/?token=abc", &[latin1], top_level),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_private_file_atomic_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("carta-private-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("instance.json");
+        write_private_file_atomic(&path, b"{}").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn url_token_reads_either_kind_of_window_url() {
+        let served = tauri::Url::parse("http://localhost:3002/?token=abc&file=m51.fits").unwrap();
+        assert_eq!(url_token(&served).as_deref(), Some("abc"));
+        let from_app =
+            tauri::Url::parse(&frontend_protocol::window_url(3002, Some("abc"))).unwrap();
+        assert_eq!(url_token(&from_app).as_deref(), Some("abc"));
+        let no_auth = tauri::Url::parse("http://localhost:3002/").unwrap();
+        assert_eq!(url_token(&no_auth), None);
+    }
+
+    #[test]
+    fn duplicate_window_url_keeps_query_for_same_backend() {
+        let base = "http://localhost:3002/?token=abc";
+        let current = tauri::Url::parse("http://localhost:3002/?token=abc&file=m51.fits").unwrap();
+        assert_eq!(
+            duplicate_window_url(base, &[], &current).as_deref(),
+            Some("http://localhost:3002/?token=abc&file=m51.fits")
+        );
+        let other = tauri::Url::parse("http://localhost:4000/?token=xyz").unwrap();
+        assert_eq!(duplicate_window_url(base, &[], &other), None);
+        assert_eq!(
+            duplicate_window_url(base, &[4000], &other).as_deref(),
+            Some("http://localhost:4000/?token=xyz")
+        );
+
+        let base = frontend_protocol::window_url(3002, Some("abc"));
+        let current = tauri::Url::parse(&format!("{}&file=m51.fits", base)).unwrap();
+        assert_eq!(
+            duplicate_window_url(&base, &[], &current),
+            Some(current.to_string())
+        );
+        let other = tauri::Url::parse(&frontend_protocol::window_url(4000, Some("xyz"))).unwrap();
+        assert_eq!(duplicate_window_url(&base, &[], &other), None);
+        assert_eq!(
+            duplicate_window_url(&base, &[4000], &other),
+            Some(other.to_string())
+        );
+    }
+
+    #[test]
+    fn cycle_window_index_wraps_in_both_directions() {
+        assert_eq!(cycle_window_index(0, None, true), None);
+        assert_eq!(cycle_window_index(3, Some(2), true), Some(0));
+        assert_eq!(cycle_window_index(3, Some(0), false), Some(2));
+        assert_eq!(cycle_window_index(3, None, true), Some(0));
+        assert_eq!(cycle_window_index(3, None, false), Some(2));
+    }
+
+    #[test]
+    fn opens_externally_only_for_remote_web_links() {
+        let url = |value: &str| tauri::Url::parse(value).unwrap();
+        assert!(!opens_externally(&url("http://localhost:3002/?token=abc")));
+        assert!(!opens_externally(&url("http://127.0.0.1:3002/docs")));
+        assert!(!opens_externally(&url(
+            "http://carta-frontend.localhost/?socketUrl=ws%3A%2F%2Flocalhost%3A3002"
+        )));
+        assert!(!opens_externally(&url("carta-frontend://localhost/")));
+        assert!(!opens_externally(&url("about:blank")));
+        assert!(opens_externally(&url("https://carta.readthedocs.io/")));
+        assert!(opens_externally(&url("mailto:support@carta.edu")));
+    }
+
+    #[test]
+    fn window_title_uses_file_name() {
+        assert_eq!(window_title(None, None), "CARTA");
+        assert_eq!(
+            window_title(Some("/data/m51.fits"), None),
+            "CARTA — m51.fits"
+        );
+        assert_eq!(
+            window_title(Some(r"C:\data\cube.image\"), None),
+            "CARTA — cube.image"
+        );
+        assert_eq!(
+            window_title(Some("m51.fits"), Some("~/data/alma")),
+            "CARTA — m51.fits — ~/data/alma"
+        );
+        assert_eq!(window_title(None, Some("~")), "CARTA — ~");
+        assert_eq!(file_from_document_title("CARTA"), None);
+        assert_eq!(
+            file_from_document_title("CARTA - m51.fits"),
+            Some("m51.fits")
+        );
+        assert_eq!(file_from_document_title("m51.fits"), Some("m51.fits"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tilde_path_shortens_home() {
+        let home = Some(Path::new("/home/astro"));
+        assert_eq!(
+            tilde_path(Path::new("/home/astro/data/alma"), home),
+            "~/data/alma"
+        );
+        assert_eq!(tilde_path(Path::new("/home/astro"), home), "~");
+        assert_eq!(
+            tilde_path(Path::new("/home/astronomy"), home),
+            "/home/astronomy"
+        );
+        assert_eq!(tilde_path(Path::new("/data"), None), "/data");
+    }
+
+    #[test]
+    fn progress_bar_state_maps_task_progress() {
+        let state = progress_bar_state(TaskProgress::Running, Some(0.426));
+        assert!(matches!(state.status, Some(ProgressBarStatus::Normal)));
+        assert_eq!(state.progress, Some(43));
+        let state = progress_bar_state(TaskProgress::Running, None);
+        assert!(matches!(
+            state.status,
+            Some(ProgressBarStatus::Indeterminate)
+        ));
+        assert_eq!(state.progress, None);
+        let state = progress_bar_state(TaskProgress::Error, Some(7.0));
+        assert!(matches!(state.status, Some(ProgressBarStatus::Error)));
+        assert_eq!(state.progress, Some(100));
+        let state = progress_bar_state(TaskProgress::Done, Some(1.0));
+        assert!(matches!(state.status, Some(ProgressBarStatus::None)));
+        assert_eq!(state.progress, None);
+    }
+
     #[cfg(any(target_os = "macos", target_os = "linux"))]
     #[test]
     fn resolve_casa_path_uses_space_free_path() {
@@ -1883,7 +6868,7 @@ mod tests {
     #[test]
     fn resolve_casa_path_uses_space_free_path() {
         assert!(
-            wsl_bash_output("true").is_ok(),
+            wsl_shell_output("true").is_ok(),
             "WSL is required to run this test"
         );
 
@@ -1908,7 +6893,7 @@ mod tests {
 elif [ -e \"$link\" ]; then echo __NONLINK__; else echo __MISSING__; fi\n",
             bash_escape(&link_path)
         );
-        let Ok(output) = wsl_bash_output(&link_probe) else {
+        let Ok(output) = wsl_shell_output(&link_probe) else {
             let _ = fs::remove_dir_all(&base_dir);
             return;
         };
@@ -1943,11 +6928,53 @@ if [ \"$target\" = \"$expected\" ]; then rm -f \"$link\"; fi; fi\n",
                 bash_escape(&link_path),
                 bash_escape(&wsl_path)
             );
-            let _ = wsl_bash_output(&cleanup);
+            let _ = wsl_shell_output(&cleanup);
         }
         let _ = fs::remove_dir_all(&base_dir);
     }
 
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn link_wsl_etc_path_reuses_link_and_refuses_other_files() {
+        let wsl_path = "/mnt/c/Program Files/CARTA/backend/etc";
+        let processes =
+            process::FakeRunner::with_results(&[(0, "/mnt/c/Program Files/CARTA/backend/etc\n")]);
+        let link = link_wsl_etc_path(&processes, wsl_path).unwrap();
+        assert_eq!(link, format!("{}/{}", SYMLINK_BASE, SYMLINK_NAME));
+        assert_eq!(processes.commands().len(), 1);
+
+        // `readlink` finds nothing, `test -e` succeeds and `test -L` fails.
+        let processes = process::FakeRunner::with_results(&[(1, ""), (0, ""), (1, "")]);
+        assert!(matches!(
+            link_wsl_etc_path(&processes, wsl_path),
+            Err(AppError::SymlinkConflict(_))
+        ));
+        assert!(processes.commands().iter().all(|cmd| !cmd.contains(" ln ")));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn backend_port_is_free_asks_wsl() {
+        let tables = "   0: 0100007F:0BB9 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1\n";
+        let processes = process::FakeRunner::with_results(&[(0, tables)]);
+        assert!(!backend_port_is_free(&processes, 3001));
+        assert!(processes.commands()[0].contains("/proc/net/tcp"));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn detect_wsl_shell_falls_back_to_sh() {
+        let processes = process::FakeRunner::with_results(&[(0, "/usr/bin/bash\n"), (1, "")]);
+        assert_eq!(detect_wsl_shell(&processes), "bash");
+        assert_eq!(detect_wsl_shell(&processes), "sh");
+        assert!(
+            processes
+                .commands()
+                .iter()
+                .all(|cmd| cmd.contains("command -v bash"))
+        );
+    }
+
     #[cfg(target_os = "windows")]
     #[test]
     fn wsl_parent_path_handles_root_and_nested() {
@@ -1967,6 +6994,23 @@ if [ \"$target\" = \"$expected\" ]; then rm -f \"$link\"; fi; fi\n",
         assert!(!wsl_path_is_within("/mnt/c/Users", "/mnt/c/User"));
     }
 
+    #[test]
+    fn resolve_base_directory_replaces_home_only_for_desktop_launches() {
+        let Some(home) = home_dir().filter(|home| home.is_dir() && !should_default_to_home(home))
+        else {
+            return;
+        };
+        let default_dir = std::env::temp_dir();
+        assert_eq!(
+            resolve_base_directory(home.clone(), None, Some(&default_dir), true).unwrap(),
+            default_dir
+        );
+        assert_eq!(
+            resolve_base_directory(home.clone(), None, Some(&default_dir), false).unwrap(),
+            home
+        );
+    }
+
     #[cfg(target_os = "windows")]
     #[test]
     fn normalize_backend_args_converts_wsl_relevant_flags() {
@@ -2012,6 +7056,16 @@ if [ \"$target\" = \"$expected\" ]; then rm -f \"$link\"; fi; fi\n",
         );
     }
 
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn win_to_wsl_path_keeps_non_ascii_names() {
+        assert_eq!(
+            win_to_wsl_path(r"C:\Users\張三\データ").as_deref(),
+            Some("/mnt/c/Users/張三/データ")
+        );
+        assert_eq!(win_to_wsl_path("張:"), None);
+    }
+
     #[cfg(target_os = "windows")]
     #[test]
     fn build_window_url_windows_requires_top_level_prefix() {
@@ -0,0 +1,262 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{AppState, process::ProcessRunner, tray};
+
+const MONITOR_INTERVAL_SECS: u64 = 5;
+
+/// Snapshot of the backend process returned to the frontend.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub(crate) struct BackendStatus {
+    pub(crate) running: bool,
+    /// Backend PID; inside the WSL distro on Windows.
+    pub(crate) pid: Option<u32>,
+    pub(crate) cpu_percent: Option<f64>,
+    pub(crate) memory_bytes: Option<u64>,
+}
+
+impl BackendStatus {
+    pub(crate) fn summary(&self) -> String {
+        if !self.running {
+            return "CARTA backend: not running".to_string();
+        }
+        let cpu = self
+            .cpu_percent
+            .map(|cpu| format!("CPU {:.0}%", cpu))
+            .unwrap_or_else(|| "CPU ?".to_string());
+        let memory = self
+            .memory_bytes
+            .map(|bytes| format!("{:.1} GB", bytes as f64 / 1e9))
+            .unwrap_or_else(|| "? GB".to_string());
+        format!("CARTA backend: {} · {}", cpu, memory)
+    }
+}
+
+enum CpuReading {
+    /// Total CPU time consumed so far, in seconds.
+    Cumulative(f64),
+    /// Utilisation already averaged by the OS (`ps` on macOS).
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    Percent(f64),
+}
+
+struct RawUsage {
+    pid: u32,
+    cpu: CpuReading,
+    rss_bytes: Option<u64>,
+}
+
+/// Samples backend CPU/memory; keeps the previous cumulative CPU reading so
+/// utilisation can be computed as a rate between calls.
+#[derive(Default)]
+pub(crate) struct ResourceMonitor {
+    last: Mutex<Option<(u32, f64, Instant)>>,
+}
+
+impl ResourceMonitor {
+    pub(crate) fn sample(&self, state: &AppState) -> BackendStatus {
+        let pid = {
+            let mut backend = state.backend.lock().unwrap();
+            let Some(child) = backend.as_mut() else {
+                return BackendStatus::default();
+            };
+            if !matches!(child.try_wait(), Ok(None)) {
+                return BackendStatus::default();
+            }
+            child.id()
+        };
+
+        let Some(usage) = read_usage(&*state.processes, pid, state.backend_port()) else {
+            return BackendStatus {
+                running: true,
+                ..BackendStatus::default()
+            };
+        };
+
+        let now = Instant::now();
+        let cpu_percent = match usage.cpu {
+            CpuReading::Percent(percent) => Some(percent),
+            CpuReading::Cumulative(seconds) => {
+                let mut last = self.last.lock().unwrap();
+                let percent = match *last {
+                    Some((last_pid, last_seconds, at)) if last_pid == usage.pid => {
+                        let elapsed = now.duration_since(at).as_secs_f64();
+                        (elapsed > 0.0).then(|| (seconds - last_seconds).max(0.0) / elapsed * 100.0)
+                    }
+                    _ => None,
+                };
+                *last = Some((usage.pid, seconds, now));
+                percent
+            }
+        };
+
+        BackendStatus {
+            running: true,
+            pid: Some(usage.pid),
+            cpu_percent,
+            memory_bytes: usage.rss_bytes,
+        }
+    }
+}
+
+/// Periodically refreshes the tray tooltip with the backend status while the
+/// tray icon is enabled.
+pub(crate) fn start_tray_updates(app: &AppHandle) {
+    let handle = app.clone();
+    app.state::<AppState>().tasks.spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(MONITOR_INTERVAL_SECS)).await;
+            if !tray::is_visible(&handle) {
+                continue;
+            }
+            // Sampling reads `/proc` or runs `ps`.
+            let app = handle.clone();
+            let status = tauri::async_runtime::spawn_blocking(move || {
+                let state = app.state::<AppState>();
+                state.resource_monitor.sample(&state)
+            })
+            .await;
+            if let Ok(status) = status {
+                tray::set_tooltip(&handle, &status.summary());
+            }
+        }
+    });
+}
+
+/// Total utime + stime (clock ticks) from a `/proc/<pid>/stat` line.
+pub(crate) fn parse_proc_stat_ticks(stat: &str) -> Option<u64> {
+    // The command name is parenthesised and may contain spaces, so split after it.
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    // Fields 14 and 15 of the full line; `rest` starts at field 3.
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Resident set size in bytes from `/proc/<pid>/status`.
+pub(crate) fn parse_vm_rss_bytes(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(target_os = "linux")]
+fn read_usage(_processes: &dyn ProcessRunner, pid: u32, _port: u16) -> Option<RawUsage> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    // SAFETY: sysconf has no preconditions.
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    let ticks_per_sec = if ticks_per_sec > 0 {
+        ticks_per_sec as f64
+    } else {
+        100.0
+    };
+    Some(RawUsage {
+        pid,
+        cpu: CpuReading::Cumulative(parse_proc_stat_ticks(&stat)? as f64 / ticks_per_sec),
+        rss_bytes: parse_vm_rss_bytes(&status),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn read_usage(processes: &dyn ProcessRunner, pid: u32, _port: u16) -> Option<RawUsage> {
+    ps_usage(processes, pid)
+}
+
+/// CPU and memory as `ps` reports them, for macOS where there is no `/proc`.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn ps_usage(processes: &dyn ProcessRunner, pid: u32) -> Option<RawUsage> {
+    let output = processes
+        .output(std::process::Command::new("ps").args(["-o", "%cpu=,rss=", "-p", &pid.to_string()]))
+        .ok()?;
+    // `ps` exits with 1 when the process is gone.
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut fields = text.split_whitespace();
+    let cpu: f64 = fields.next()?.parse().ok()?;
+    let rss_kib: u64 = fields.next()?.parse().ok()?;
+    Some(RawUsage {
+        pid,
+        cpu: CpuReading::Percent(cpu),
+        rss_bytes: Some(rss_kib * 1024),
+    })
+}
+
+/// The spawned child is `wsl.exe`, so find the real backend inside the distro
+/// by the `--port=` argument the launcher passed to it and read its `/proc`.
+#[cfg(target_os = "windows")]
+fn read_usage(processes: &dyn ProcessRunner, _pid: u32, port: u16) -> Option<RawUsage> {
+    use std::os::windows::process::CommandExt;
+
+    let script = format!(
+        "pid=$(pgrep -n -f -- '--port={port}( |$)') || exit 1; \
+echo $pid; head -n1 /proc/$pid/stat; getconf CLK_TCK; cat /proc/$pid/status"
+    );
+    let mut cmd = crate::wsl_shell_command(processes, &script);
+    cmd.creation_flags(crate::CREATE_NO_WINDOW);
+    let output = processes.output(&mut cmd).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut lines = text.lines();
+    let pid: u32 = lines.next()?.trim().parse().ok()?;
+    let ticks = parse_proc_stat_ticks(lines.next()?)?;
+    let ticks_per_sec: f64 = lines.next()?.trim().parse().ok()?;
+    let status: String = lines.collect::<Vec<_>>().join("\n");
+    Some(RawUsage {
+        pid,
+        cpu: CpuReading::Cumulative(ticks as f64 / ticks_per_sec.max(1.0)),
+        rss_bytes: parse_vm_rss_bytes(&status),
+    })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn read_usage(_processes: &dyn ProcessRunner, _pid: u32, _port: u16) -> Option<RawUsage> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::FakeRunner;
+
+    #[test]
+    fn parse_proc_stat_ticks_handles_spaces_in_command_name() {
+        let stat = "4242 (carta backend) S 1 4242 4242 0 -1 4194560 1500 0 0 0 \
+                    250 75 0 0 20 0 8 0 12345 1000000 2000 18446744073709551615";
+        assert_eq!(parse_proc_stat_ticks(stat), Some(325));
+    }
+
+    #[test]
+    fn ps_usage_reads_cpu_and_rss() {
+        let processes = FakeRunner::with_results(&[(0, "  12.5  20480\n")]);
+        let usage = ps_usage(&processes, 4242).unwrap();
+        assert_eq!(usage.pid, 4242);
+        assert!(matches!(usage.cpu, CpuReading::Percent(cpu) if cpu == 12.5));
+        assert_eq!(usage.rss_bytes, Some(20480 * 1024));
+        assert_eq!(processes.commands(), ["ps -o %cpu=,rss= -p 4242"]);
+    }
+
+    #[test]
+    fn ps_usage_needs_a_running_process() {
+        let processes = FakeRunner::with_results(&[(1, "")]);
+        assert!(ps_usage(&processes, 4242).is_none());
+        let processes = FakeRunner::with_results(&[(0, "garbled\n")]);
+        assert!(ps_usage(&processes, 4242).is_none());
+    }
+
+    #[test]
+    fn parse_vm_rss_bytes_reads_kib() {
+        let status = "Name:\tcarta_backend\nVmPeak:\t  900 kB\nVmRSS:\t  2048 kB\n";
+        assert_eq!(parse_vm_rss_bytes(status), Some(2048 * 1024));
+        assert_eq!(parse_vm_rss_bytes("Name:\tx\n"), None);
+    }
+}
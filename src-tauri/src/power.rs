@@ -0,0 +1,215 @@
+use std::{collections::HashMap, sync::Mutex};
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use std::process::{Child, Command, Stdio};
+
+#[cfg(target_os = "linux")]
+const INHIBIT_REASON: &str = "CARTA backend operation in progress";
+
+/// Reference-counted power-management inhibition. The frontend takes a hold for
+/// each long-running operation, the menu toggle takes a manual hold, and the
+/// system is kept awake while any hold is active. Holds belong to the window
+/// that took them, so a window that closes or reloads cannot leave one behind.
+#[derive(Default)]
+pub(crate) struct SleepInhibitor {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    holds: Holds,
+    manual: bool,
+    guard: Option<PlatformGuard>,
+}
+
+impl SleepInhibitor {
+    pub(crate) fn acquire(&self, window: &str) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.holds.add(window);
+        inner.update();
+        id
+    }
+
+    pub(crate) fn release(&self, id: u64) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let removed = inner.holds.remove(id);
+        inner.update();
+        removed
+    }
+
+    /// Drops every hold `window` took, e.g. once it closes or leaves its page.
+    pub(crate) fn release_window(&self, window: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.holds.remove_window(window);
+        inner.update();
+    }
+
+    pub(crate) fn set_manual(&self, enabled: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.manual = enabled;
+        inner.update();
+    }
+
+    pub(crate) fn is_manual(&self) -> bool {
+        self.inner.lock().unwrap().manual
+    }
+
+    /// Whether an operation holds off sleep, the manual hold aside.
+    pub(crate) fn has_holds(&self) -> bool {
+        !self.inner.lock().unwrap().holds.is_empty()
+    }
+
+    pub(crate) fn release_all(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.holds = Holds::default();
+        inner.manual = false;
+        inner.update();
+    }
+}
+
+impl Inner {
+    fn update(&mut self) {
+        let wanted = self.manual || !self.holds.is_empty();
+        match (wanted, self.guard.is_some()) {
+            (true, false) => match PlatformGuard::acquire() {
+                Ok(guard) => self.guard = Some(guard),
+                Err(err) => eprintln!("Warning: failed to prevent system sleep: {}", err),
+            },
+            (false, true) => self.guard = None,
+            _ => {}
+        }
+    }
+}
+
+/// Operation holds by id, with the label of the window that took each.
+#[derive(Default)]
+struct Holds {
+    next_id: u64,
+    windows: HashMap<u64, String>,
+}
+
+impl Holds {
+    fn add(&mut self, window: &str) -> u64 {
+        self.next_id += 1;
+        self.windows.insert(self.next_id, window.to_string());
+        self.next_id
+    }
+
+    fn remove(&mut self, id: u64) -> bool {
+        self.windows.remove(&id).is_some()
+    }
+
+    fn remove_window(&mut self, window: &str) {
+        self.windows.retain(|_, label| label != window);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+}
+
+/// Holds an OS inhibition until dropped. macOS and Linux keep a helper process
+/// (`caffeinate`, `systemd-inhibit`) alive; Windows parks a thread that has set
+/// its execution state, since that state is per-thread.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+struct PlatformGuard(Child);
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl PlatformGuard {
+    fn acquire() -> std::io::Result<Self> {
+        #[cfg(target_os = "macos")]
+        let mut cmd = {
+            // -w ties the assertion to the launcher in case it dies without cleanup.
+            let mut cmd = Command::new("caffeinate");
+            cmd.arg("-i").arg("-w").arg(std::process::id().to_string());
+            cmd
+        };
+        #[cfg(target_os = "linux")]
+        let mut cmd = {
+            let mut cmd = Command::new("systemd-inhibit");
+            cmd.arg("--what=idle:sleep")
+                .arg("--who=CARTA")
+                .arg(format!("--why={}", INHIBIT_REASON))
+                .arg("--mode=block")
+                .args(["sleep", "infinity"]);
+            crate::set_parent_death_signal(&mut cmd);
+            cmd
+        };
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        Ok(Self(cmd.spawn()?))
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl Drop for PlatformGuard {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[cfg(target_os = "windows")]
+struct PlatformGuard {
+    // Dropping the sender wakes the parked thread, which clears the state.
+    _release: std::sync::mpsc::Sender<()>,
+}
+
+#[cfg(target_os = "windows")]
+impl PlatformGuard {
+    fn acquire() -> std::io::Result<Self> {
+        use windows_sys::Win32::System::Power::{
+            ES_CONTINUOUS, ES_SYSTEM_REQUIRED, SetThreadExecutionState,
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        std::thread::Builder::new()
+            .name("carta-sleep-inhibitor".into())
+            .spawn(move || {
+                // SAFETY: plain Win32 call that only affects this thread's state.
+                unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+                // Blocks until the guard is dropped and the sender disconnects.
+                let _ = rx.recv();
+                // SAFETY: as above; clears the requirement set by this thread.
+                unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+            })?;
+        Ok(Self { _release: tx })
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+struct PlatformGuard;
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+impl PlatformGuard {
+    fn acquire() -> std::io::Result<Self> {
+        Err(std::io::Error::other("unsupported platform"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closing_a_window_drops_its_holds() {
+        let mut holds = Holds::default();
+        holds.add("carta-1");
+        holds.add("carta-1");
+        let other = holds.add("carta-2");
+
+        holds.remove_window("carta-1");
+        assert!(!holds.is_empty());
+        assert!(holds.remove(other));
+        assert!(holds.is_empty());
+    }
+
+    #[test]
+    fn release_ignores_unknown_ids() {
+        let mut holds = Holds::default();
+        let id = holds.add("carta-1");
+        holds.remove_window("carta-1");
+        assert!(!holds.remove(id));
+    }
+}
@@ -0,0 +1,320 @@
+use std::path::Path;
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::{AppState, process::ProcessRunner};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+/// Below this much available RAM, loading a large cube is likely to fail.
+const MIN_AVAILABLE_MEMORY_BYTES: u64 = 2 * GIB;
+/// WSL defaults to half of host RAM; below this the VM cap itself is the problem.
+#[cfg(target_os = "windows")]
+const MIN_WSL_TOTAL_MEMORY_BYTES: u64 = 4 * GIB;
+/// Below this much free space on the temp or data filesystem, warn.
+const MIN_FREE_DISK_BYTES: u64 = 2 * GIB;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MemoryInfo {
+    pub(crate) total_bytes: u64,
+    pub(crate) available_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DiskInfo {
+    pub(crate) mount_point: String,
+    pub(crate) available_bytes: u64,
+}
+
+/// Checks memory and disk space before the backend starts and warns the user
+/// when large-cube work is likely to fail. Never blocks startup.
+pub(crate) fn run(app: &AppHandle, base_dir: &Path) {
+    let warnings = check(&*app.state::<AppState>().processes, base_dir);
+    if warnings.is_empty() {
+        return;
+    }
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    app.dialog()
+        .message(warnings.join("\n\n"))
+        .title("CARTA")
+        .kind(MessageDialogKind::Warning)
+        .show(|_| {});
+}
+
+fn check(processes: &dyn ProcessRunner, base_dir: &Path) -> Vec<String> {
+    let (memory, disks) = probe(processes, base_dir);
+    let mut warnings = Vec::new();
+
+    if let Some(memory) = memory {
+        #[cfg(target_os = "windows")]
+        if memory.total_bytes < MIN_WSL_TOTAL_MEMORY_BYTES {
+            warnings.push(format!(
+                "WSL is limited to {} of memory. Raise the `memory` setting in \
+                 %UserProfile%\\.wslconfig and run `wsl --shutdown` to allow larger images.",
+                format_gib(memory.total_bytes)
+            ));
+        }
+        if memory.available_bytes < MIN_AVAILABLE_MEMORY_BYTES {
+            warnings.push(format!(
+                "Only {} of {} memory is available. Large images may fail to load; \
+                 consider closing other applications.",
+                format_gib(memory.available_bytes),
+                format_gib(memory.total_bytes)
+            ));
+        }
+    }
+
+    let mut seen = Vec::new();
+    for disk in disks {
+        if seen.contains(&disk.mount_point) {
+            continue;
+        }
+        if disk.available_bytes < MIN_FREE_DISK_BYTES {
+            warnings.push(format!(
+                "Only {} of disk space is free on {}. Moment generation and \
+                 other temporary files may fail.",
+                format_gib(disk.available_bytes),
+                disk.mount_point
+            ));
+        }
+        seen.push(disk.mount_point);
+    }
+
+    warnings
+}
+
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / GIB as f64)
+}
+
+#[cfg(target_os = "linux")]
+fn probe(processes: &dyn ProcessRunner, base_dir: &Path) -> (Option<MemoryInfo>, Vec<DiskInfo>) {
+    let memory = std::fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| parse_meminfo(&meminfo));
+    (
+        memory,
+        df(processes, &[std::env::temp_dir().as_path(), base_dir]),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn probe(processes: &dyn ProcessRunner, base_dir: &Path) -> (Option<MemoryInfo>, Vec<DiskInfo>) {
+    (
+        macos_memory(processes),
+        df(processes, &[std::env::temp_dir().as_path(), base_dir]),
+    )
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn macos_memory(processes: &dyn ProcessRunner) -> Option<MemoryInfo> {
+    let total = command_stdout(processes, "sysctl", &["-n", "hw.memsize"])
+        .and_then(|text| text.trim().parse::<u64>().ok());
+    let available = command_stdout(processes, "vm_stat", &[]).and_then(|text| parse_vm_stat(&text));
+    total
+        .zip(available)
+        .map(|(total_bytes, available_bytes)| MemoryInfo {
+            total_bytes,
+            available_bytes,
+        })
+}
+
+/// The backend runs inside WSL, so both memory and disk are measured there:
+/// `/proc/meminfo` reflects the VM's `.wslconfig` cap, not host RAM.
+#[cfg(target_os = "windows")]
+fn probe(processes: &dyn ProcessRunner, base_dir: &Path) -> (Option<MemoryInfo>, Vec<DiskInfo>) {
+    let mut df_args = String::from("/tmp");
+    if let Ok(base) = crate::to_wsl_path_str(&base_dir.to_string_lossy()) {
+        df_args.push(' ');
+        df_args.push_str(&crate::bash_escape(&base));
+    }
+    let script = format!("cat /proc/meminfo; echo {DF_MARKER}; df -Pk {df_args} 2>/dev/null");
+    let mut cmd = crate::wsl_shell_command(processes, &script);
+    cmd.creation_flags(crate::CREATE_NO_WINDOW);
+    let Ok(output) = processes.output(&mut cmd) else {
+        return (None, Vec::new());
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (meminfo, df_output) = text.split_once(DF_MARKER).unwrap_or((text.as_ref(), ""));
+    (parse_meminfo(meminfo), parse_df(df_output))
+}
+
+#[cfg(target_os = "windows")]
+const DF_MARKER: &str = "--carta-df--";
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn probe(_processes: &dyn ProcessRunner, _base_dir: &Path) -> (Option<MemoryInfo>, Vec<DiskInfo>) {
+    (None, Vec::new())
+}
+
+/// `df` still lists the paths it could read when another one fails, so its
+/// exit status is not checked.
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn df(processes: &dyn ProcessRunner, paths: &[&Path]) -> Vec<DiskInfo> {
+    processes
+        .output(std::process::Command::new("df").arg("-Pk").args(paths))
+        .map(|output| parse_df(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn command_stdout(processes: &dyn ProcessRunner, program: &str, args: &[&str]) -> Option<String> {
+    let output = processes
+        .output(std::process::Command::new(program).args(args))
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `MemTotal` and `MemAvailable` from `/proc/meminfo`.
+pub(crate) fn parse_meminfo(meminfo: &str) -> Option<MemoryInfo> {
+    let field = |name: &str| -> Option<u64> {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        let kib: u64 = line[name.len()..].split_whitespace().next()?.parse().ok()?;
+        Some(kib * 1024)
+    };
+    Some(MemoryInfo {
+        total_bytes: field("MemTotal:")?,
+        available_bytes: field("MemAvailable:")?,
+    })
+}
+
+/// Free + inactive + speculative pages from macOS `vm_stat`, in bytes.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub(crate) fn parse_vm_stat(vm_stat: &str) -> Option<u64> {
+    // First line: "Mach Virtual Memory Statistics: (page size of 16384 bytes)"
+    let page_size: u64 = vm_stat
+        .lines()
+        .next()?
+        .split("page size of ")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    let pages = |name: &str| -> Option<u64> {
+        let line = vm_stat.lines().find(|line| line.starts_with(name))?;
+        line[name.len()..].trim().trim_end_matches('.').parse().ok()
+    };
+    let free = pages("Pages free:")?;
+    let inactive = pages("Pages inactive:").unwrap_or(0);
+    let speculative = pages("Pages speculative:").unwrap_or(0);
+    Some((free + inactive + speculative) * page_size)
+}
+
+/// Parses POSIX `df -Pk` output into available bytes per mount point.
+pub(crate) fn parse_df(output: &str) -> Vec<DiskInfo> {
+    output
+        .lines()
+        .skip_while(|line| !line.starts_with("Filesystem"))
+        .skip(1)
+        .filter_map(|line| {
+            // Filesystem names can contain spaces, so anchor on the capacity column.
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let capacity = fields.iter().position(|field| field.ends_with('%'))?;
+            let available_kib: u64 = fields.get(capacity.checked_sub(1)?)?.parse().ok()?;
+            Some(DiskInfo {
+                mount_point: fields.get(capacity + 1..)?.join(" "),
+                available_bytes: available_kib * 1024,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::FakeRunner;
+
+    #[test]
+    fn parse_meminfo_reads_total_and_available() {
+        let meminfo =
+            "MemTotal:       16318480 kB\nMemFree:         1024 kB\nMemAvailable:    2048 kB\n";
+        assert_eq!(
+            parse_meminfo(meminfo),
+            Some(MemoryInfo {
+                total_bytes: 16318480 * 1024,
+                available_bytes: 2048 * 1024,
+            })
+        );
+        assert_eq!(parse_meminfo("MemTotal: 1 kB\n"), None);
+    }
+
+    #[test]
+    fn parse_vm_stat_counts_reclaimable_pages() {
+        let vm_stat = "Mach Virtual Memory Statistics: (page size of 16384 bytes)\n\
+                       Pages free:                               10.\n\
+                       Pages active:                            500.\n\
+                       Pages inactive:                           20.\n\
+                       Pages speculative:                         2.\n";
+        assert_eq!(parse_vm_stat(vm_stat), Some(32 * 16384));
+    }
+
+    #[test]
+    fn parse_df_handles_spaces_in_names() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/sda1         10000000   9000000    500000      95% /\n\
+                      map auto_home     20000000         0  20000000       0% /Volumes/My Data\n";
+        assert_eq!(
+            parse_df(output),
+            vec![
+                DiskInfo {
+                    mount_point: "/".to_string(),
+                    available_bytes: 500000 * 1024,
+                },
+                DiskInfo {
+                    mount_point: "/Volumes/My Data".to_string(),
+                    available_bytes: 20000000 * 1024,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn df_reads_the_runner_output() {
+        let processes = FakeRunner::with_results(&[(
+            1,
+            "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+             /dev/sda1         10000000   9000000    500000      95% /\n",
+        )]);
+        let disks = df(&processes, &[Path::new("/tmp"), Path::new("/missing")]);
+        assert_eq!(
+            disks,
+            vec![DiskInfo {
+                mount_point: "/".to_string(),
+                available_bytes: 500000 * 1024,
+            }]
+        );
+        assert_eq!(processes.commands(), ["df -Pk /tmp /missing"]);
+
+        let processes = FakeRunner::with_results(&[(1, "")]);
+        assert!(df(&processes, &[Path::new("/missing")]).is_empty());
+    }
+
+    #[test]
+    fn macos_memory_needs_both_commands() {
+        let vm_stat = "Mach Virtual Memory Statistics: (page size of 4096 bytes)\n\
+                       Pages free:                              100.\n";
+        let processes = FakeRunner::with_results(&[(0, "17179869184\n"), (0, vm_stat)]);
+        assert_eq!(
+            macos_memory(&processes),
+            Some(MemoryInfo {
+                total_bytes: 17179869184,
+                available_bytes: 100 * 4096,
+            })
+        );
+        assert_eq!(processes.commands(), ["sysctl -n hw.memsize", "vm_stat"]);
+
+        let processes = FakeRunner::with_results(&[(1, ""), (0, vm_stat)]);
+        assert_eq!(macos_memory(&processes), None);
+        let processes = FakeRunner::with_results(&[(0, "lots\n"), (0, vm_stat)]);
+        assert_eq!(macos_memory(&processes), None);
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    io,
+    process::{Child, Command, ExitStatus, Output},
+};
+
+/// Runs the external programs the launcher depends on: the backend, its
+/// `--help`/`--version`, the system tools behind the preflight checks,
+/// resource monitor and update check, and on Windows every `wsl.exe` call
+/// and the `reg.exe` and PowerShell commands of setup.
+/// Code that builds commands takes a runner so tests can check what would
+/// run, and script the results, without a backend or WSL.
+pub(crate) trait ProcessRunner: Send + Sync {
+    fn output(&self, cmd: &mut Command) -> io::Result<Output>;
+
+    /// Only the Windows commands need just the status.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    fn status(&self, cmd: &mut Command) -> io::Result<ExitStatus> {
+        self.output(cmd).map(|output| output.status)
+    }
+
+    fn spawn(&self, cmd: &mut Command) -> io::Result<Child>;
+}
+
+/// Runs commands for real.
+pub(crate) struct SystemRunner;
+
+impl ProcessRunner for SystemRunner {
+    fn output(&self, cmd: &mut Command) -> io::Result<Output> {
+        cmd.output()
+    }
+
+    fn status(&self, cmd: &mut Command) -> io::Result<ExitStatus> {
+        cmd.status()
+    }
+
+    fn spawn(&self, cmd: &mut Command) -> io::Result<Child> {
+        cmd.spawn()
+    }
+}
+
+#[cfg(test)]
+pub(crate) use fake::FakeRunner;
+
+#[cfg(test)]
+mod fake {
+    use std::{collections::VecDeque, sync::Mutex};
+
+    use super::*;
+
+    /// Records each command line and answers with scripted results, in order.
+    /// Commands run after the script is used up succeed with no output.
+    #[derive(Default)]
+    pub(crate) struct FakeRunner {
+        results: Mutex<VecDeque<(i32, String)>>,
+        commands: Mutex<Vec<String>>,
+    }
+
+    impl FakeRunner {
+        pub(crate) fn with_results(results: &[(i32, &str)]) -> Self {
+            Self {
+                results: Mutex::new(
+                    results
+                        .iter()
+                        .map(|&(code, stdout)| (code, stdout.to_string()))
+                        .collect(),
+                ),
+                commands: Mutex::default(),
+            }
+        }
+
+        /// The commands run so far, as `crate::command_line` prints them.
+        pub(crate) fn commands(&self) -> Vec<String> {
+            self.commands.lock().unwrap().clone()
+        }
+    }
+
+    impl ProcessRunner for FakeRunner {
+        fn output(&self, cmd: &mut Command) -> io::Result<Output> {
+            self.commands.lock().unwrap().push(crate::command_line(cmd));
+            let (code, stdout) = self.results.lock().unwrap().pop_front().unwrap_or_default();
+            Ok(Output {
+                status: exit_status(code),
+                stdout: stdout.into_bytes(),
+                stderr: Vec::new(),
+            })
+        }
+
+        /// There is no process to hand back.
+        fn spawn(&self, cmd: &mut Command) -> io::Result<Child> {
+            self.commands.lock().unwrap().push(crate::command_line(cmd));
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "FakeRunner cannot spawn processes",
+            ))
+        }
+    }
+
+    #[cfg(unix)]
+    fn exit_status(code: i32) -> ExitStatus {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw(code << 8)
+    }
+
+    #[cfg(windows)]
+    fn exit_status(code: i32) -> ExitStatus {
+        use std::os::windows::process::ExitStatusExt;
+        ExitStatus::from_raw(code as u32)
+    }
+}
@@ -0,0 +1,225 @@
+use tauri::Url;
+
+use crate::settings::{ProxyMode, ProxySettings};
+
+/// Hosts that are always reached directly: the local backend must never be
+/// sent through a proxy.
+const LOOPBACK_HOSTS: &[&str] = &["localhost", "127.0.0.1", "::1"];
+const SOCKS_PORT: u16 = 1080;
+
+/// An HTTP or SOCKS5 proxy for outgoing web requests, resolved once at startup.
+/// WebView2 shares one browser process per profile and rejects later windows
+/// created with different proxy arguments, so changes apply after a restart.
+/// WKWebView only takes a per-webview proxy on macOS 14+, so on macOS the
+/// system proxy configuration is always used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+pub(crate) struct Proxy {
+    url: Url,
+    /// `NO_PROXY`-style host suffixes reached directly.
+    bypass: Vec<String>,
+}
+
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+impl Proxy {
+    /// `None` leaves networking to the platform's own proxy configuration.
+    pub(crate) fn from_settings(settings: &ProxySettings) -> Option<Self> {
+        match settings.mode {
+            ProxyMode::System => None,
+            ProxyMode::Environment => {
+                let url = ["https_proxy", "http_proxy", "all_proxy"]
+                    .into_iter()
+                    .find_map(env_var)?;
+                Self::parse(&url, env_var("no_proxy").as_deref())
+            }
+            ProxyMode::Manual => {
+                let url = settings.url.as_deref().filter(|url| !url.trim().is_empty());
+                let Some(url) = url else {
+                    eprintln!("Warning: manual proxy mode is set but no proxy URL is configured");
+                    return None;
+                };
+                Self::parse(url, settings.no_proxy.as_deref())
+            }
+        }
+    }
+
+    fn parse(url: &str, no_proxy: Option<&str>) -> Option<Self> {
+        let url = match parse_proxy_url(url) {
+            Ok(url) => url,
+            Err(err) => {
+                eprintln!("Warning: ignoring proxy {}: {}", url, err);
+                return None;
+            }
+        };
+        let bypass: Vec<String> = no_proxy
+            .unwrap_or_default()
+            .split(',')
+            .map(|host| host.trim().to_ascii_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+        // `NO_PROXY=*` turns the proxy off entirely.
+        if bypass.iter().any(|host| host == "*") {
+            return None;
+        }
+        let bypass = bypass
+            .into_iter()
+            .map(|host| host.trim_start_matches('*').to_string())
+            .collect();
+        Some(Self { url, bypass })
+    }
+
+    /// Hosts to reach directly, always including loopback, as `*.domain`
+    /// patterns both WebKitGTK and Chromium understand.
+    fn bypass_patterns(&self) -> Vec<String> {
+        LOOPBACK_HOSTS
+            .iter()
+            .map(ToString::to_string)
+            .chain(self.bypass.iter().map(|host| match host.strip_prefix('.') {
+                Some(domain) => format!("*.{}", domain),
+                None => host.clone(),
+            }))
+            .collect()
+    }
+}
+
+/// Lowercase first, as curl does, then the uppercase spelling.
+fn env_var(name: &str) -> Option<String> {
+    [name.to_string(), name.to_ascii_uppercase()]
+        .into_iter()
+        .find_map(|name| std::env::var(name).ok())
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Accepts `host:port` or an `http://` / `socks5://` URL; the webviews support
+/// no other proxy kinds.
+fn parse_proxy_url(value: &str) -> Result<Url, String> {
+    let value = value.trim();
+    let value = if value.contains("://") {
+        value.to_string()
+    } else {
+        format!("http://{}", value)
+    };
+    let mut url = Url::parse(&value).map_err(|err| err.to_string())?;
+    match url.scheme() {
+        "http" => {}
+        "socks5" | "socks5h" => {
+            url = Url::parse(&value.replacen("socks5h://", "socks5://", 1))
+                .map_err(|err| err.to_string())?;
+            if url.port().is_none() {
+                let _ = url.set_port(Some(SOCKS_PORT));
+            }
+        }
+        scheme => return Err(format!("unsupported proxy scheme \"{}\"", scheme)),
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err("missing proxy host".to_string());
+    }
+    Ok(url)
+}
+
+/// The same proxy for `curl`, which otherwise only reads the environment.
+pub(crate) fn curl_args(proxy: &Proxy) -> Vec<String> {
+    let mut args = vec!["--proxy".to_string(), proxy.url.to_string()];
+    if !proxy.bypass.is_empty() {
+        args.push("--noproxy".to_string());
+        args.push(proxy.bypass.join(","));
+    }
+    args
+}
+
+/// WebView2 takes the proxy as browser arguments.
+#[cfg(target_os = "windows")]
+pub(crate) fn browser_args(proxy: &Proxy) -> String {
+    let host = proxy.url.host_str().unwrap_or_default();
+    let port = proxy.url.port_or_known_default().unwrap_or_default();
+    format!(
+        "--proxy-server={}://{}:{} --proxy-bypass-list={}",
+        proxy.url.scheme(),
+        host,
+        port,
+        proxy.bypass_patterns().join(";")
+    )
+}
+
+/// WebKitGTK keeps proxy settings on the shared website data manager. Set
+/// directly rather than through the builder so loopback and `NO_PROXY` hosts
+/// are excluded.
+#[cfg(target_os = "linux")]
+pub(crate) fn apply_to_webview(window: &tauri::WebviewWindow, proxy: &Proxy) {
+    use webkit2gtk::{
+        NetworkProxyMode, NetworkProxySettings, WebContextExt, WebViewExt, WebsiteDataManagerExt,
+    };
+
+    let url = proxy.url.to_string();
+    let ignore_hosts = proxy.bypass_patterns();
+    let result = window.with_webview(move |webview| {
+        let ignore_hosts: Vec<&str> = ignore_hosts.iter().map(String::as_str).collect();
+        let Some(manager) = webview
+            .inner()
+            .context()
+            .and_then(|context| context.website_data_manager())
+        else {
+            return;
+        };
+        let mut settings = NetworkProxySettings::new(Some(&url), &ignore_hosts);
+        manager.set_network_proxy_settings(NetworkProxyMode::Custom, Some(&mut settings));
+    });
+    if let Err(err) = result {
+        eprintln!("Warning: failed to apply proxy settings: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_proxy_url_fills_scheme_and_port() {
+        let url = |value: &str| parse_proxy_url(value).map(|url| url.to_string());
+        assert_eq!(
+            url("proxy.example.org:3128").as_deref(),
+            Ok("http://proxy.example.org:3128/")
+        );
+        assert_eq!(
+            url("http://proxy.example.org").as_deref(),
+            Ok("http://proxy.example.org/")
+        );
+        assert_eq!(
+            url("socks5h://127.0.0.1").as_deref(),
+            Ok("socks5://127.0.0.1:1080")
+        );
+        assert!(url("https://proxy.example.org:443").is_err());
+        assert!(url("http://").is_err());
+    }
+
+    #[test]
+    fn bypass_patterns_always_include_loopback() {
+        let proxy = Proxy::parse("proxy.example.org:3128", Some(".nrao.edu, *.alma.cl,,")).unwrap();
+        assert_eq!(
+            proxy.bypass_patterns(),
+            ["localhost", "127.0.0.1", "::1", "*.nrao.edu", "*.alma.cl"]
+        );
+        let proxy = Proxy::parse("proxy.example.org:3128", None).unwrap();
+        assert_eq!(proxy.bypass_patterns(), LOOPBACK_HOSTS);
+        assert_eq!(Proxy::parse("proxy.example.org:3128", Some("*")), None);
+    }
+
+    #[test]
+    fn curl_args_pass_proxy_and_bypass_list() {
+        let proxy = Proxy::parse("socks5://127.0.0.1", Some(".nrao.edu, alma.cl")).unwrap();
+        assert_eq!(
+            curl_args(&proxy),
+            [
+                "--proxy",
+                "socks5://127.0.0.1:1080",
+                "--noproxy",
+                ".nrao.edu,alma.cl"
+            ]
+        );
+        let proxy = Proxy::parse("proxy.example.org:3128", None).unwrap();
+        assert_eq!(
+            curl_args(&proxy),
+            ["--proxy", "http://proxy.example.org:3128/"]
+        );
+    }
+}
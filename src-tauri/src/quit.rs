@@ -0,0 +1,43 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{AppState, shutdown_backend};
+
+/// Quits CARTA, asking first when a window has reported work in progress and
+/// the preference for it is on.
+pub(crate) fn quit(app: &AppHandle) {
+    confirm(app, |app| {
+        shutdown_backend(&app.state::<AppState>());
+        app.exit(0);
+    });
+}
+
+/// Whether quitting now should be confirmed: an operation still holds off
+/// sleep, or a window shows progress that has not finished.
+pub(crate) fn needs_confirmation(app: &AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().unwrap().confirm_quit_when_busy;
+    enabled && (state.sleep_inhibitor.has_holds() || !state.busy_windows.lock().unwrap().is_empty())
+}
+
+/// Runs `quit` now, or once confirmed when `needs_confirmation`.
+pub(crate) fn confirm(app: &AppHandle, quit: impl FnOnce(&AppHandle) + Send + 'static) {
+    if !needs_confirmation(app) {
+        quit(app);
+        return;
+    }
+    let handle = app.clone();
+    app.dialog()
+        .message("An operation is still running. Quitting CARTA stops it; quit anyway?")
+        .title("CARTA")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Quit".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            if confirmed {
+                quit(&handle);
+            }
+        });
+}
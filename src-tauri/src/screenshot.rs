@@ -0,0 +1,230 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{Manager, WebviewWindow};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{AppError, AppResult};
+
+const DEFAULT_FILE_NAME: &str = "CARTA Screenshot.png";
+
+/// Asks for a destination, then writes the window's contents there as a PNG.
+pub(crate) fn save_window_screenshot(window: &WebviewWindow) {
+    let app = window.app_handle().clone();
+    let window = window.clone();
+    app.dialog()
+        .file()
+        .add_filter("PNG image", &["png"])
+        .set_file_name(DEFAULT_FILE_NAME)
+        .save_file(move |target| {
+            let Some(path) = target.and_then(|target| target.into_path().ok()) else {
+                return;
+            };
+            let png = with_png_extension(path.clone());
+            // The dialog only asked about replacing the name as typed.
+            if png != path && png.exists() {
+                confirm_replace(window, png);
+            } else {
+                save(&window, &png);
+            }
+        });
+}
+
+fn save(window: &WebviewWindow, path: &Path) {
+    if let Err(err) = capture(window, path) {
+        report_error(window, &err);
+    }
+}
+
+fn confirm_replace(window: WebviewWindow, path: PathBuf) {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    window
+        .app_handle()
+        .dialog()
+        .message(format!(
+            "\"{}\" already exists. Do you want to replace it?",
+            name
+        ))
+        .title("CARTA")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Replace".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |replace| {
+            if replace {
+                save(&window, &path);
+            }
+        });
+}
+
+fn report_error(window: &WebviewWindow, err: &AppError) {
+    eprintln!("Warning: failed to save screenshot: {}", err);
+    window
+        .app_handle()
+        .dialog()
+        .message(format!("Failed to save screenshot: {}", err))
+        .title("CARTA")
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+fn with_png_extension(mut path: PathBuf) -> PathBuf {
+    let is_png = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+    if !is_png {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".png");
+        path.set_file_name(name);
+    }
+    path
+}
+
+/// `screencapture` grabs the window from the window server, so it works even
+/// while the save panel is still fading out.
+#[cfg(target_os = "macos")]
+fn capture(window: &WebviewWindow, path: &Path) -> AppResult<()> {
+    use objc2::runtime::AnyObject;
+
+    let ns_window = window
+        .ns_window()
+        .map_err(|err| AppError::Other(err.to_string()))?
+        .cast::<AnyObject>();
+    // SAFETY: `ns_window` is the live NSWindow behind `window`; `windowNumber`
+    // has no side effects.
+    let window_number: isize = unsafe { objc2::msg_send![ns_window, windowNumber] };
+    let status = std::process::Command::new("screencapture")
+        .arg("-x")
+        .arg("-o")
+        .arg(format!("-l{}", window_number))
+        .arg(path)
+        .status()?;
+    if !status.success() {
+        return Err(AppError::Other(format!(
+            "screencapture exited with {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// Renders the page with WebKit's own snapshot API, which is unaffected by
+/// overlapping windows or Wayland capture restrictions.
+#[cfg(target_os = "linux")]
+fn capture(window: &WebviewWindow, path: &Path) -> AppResult<()> {
+    use webkit2gtk::{SnapshotOptions, SnapshotRegion, WebViewExt};
+
+    let path = path.to_path_buf();
+    let reporter = window.clone();
+    window
+        .with_webview(move |webview| {
+            webview.inner().snapshot(
+                SnapshotRegion::Visible,
+                SnapshotOptions::NONE,
+                None::<&webkit2gtk::gio::Cancellable>,
+                move |result| {
+                    if let Err(err) = write_snapshot(result, &path) {
+                        report_error(&reporter, &err);
+                    }
+                },
+            );
+        })
+        .map_err(|err| AppError::Other(err.to_string()))
+}
+
+#[cfg(target_os = "linux")]
+fn write_snapshot(
+    result: Result<cairo::Surface, webkit2gtk::glib::Error>,
+    path: &Path,
+) -> AppResult<()> {
+    let surface = result.map_err(|err| AppError::Other(err.to_string()))?;
+    let image = cairo::ImageSurface::try_from(surface)
+        .map_err(|_| AppError::from("snapshot is not an image surface"))?;
+    let mut file = std::fs::File::create(path)?;
+    image
+        .write_to_png(&mut file)
+        .map_err(|err| AppError::Other(err.to_string()))
+}
+
+/// Renders the page with WebView2's own preview capture, which, like the
+/// WebKit snapshot, is unaffected by overlapping windows or the closing save
+/// dialog.
+#[cfg(target_os = "windows")]
+fn capture(window: &WebviewWindow, path: &Path) -> AppResult<()> {
+    use webview2_com::{
+        CapturePreviewCompletedHandler,
+        Microsoft::Web::WebView2::Win32::COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_PNG,
+    };
+    use windows::{
+        Win32::{
+            Storage::FileSystem::FILE_ATTRIBUTE_NORMAL,
+            System::Com::{STGM_CREATE, STGM_WRITE},
+            UI::Shell::SHCreateStreamOnFileEx,
+        },
+        core::HSTRING,
+    };
+
+    let path = HSTRING::from(path);
+    let reporter = window.clone();
+    window
+        .with_webview(move |webview| {
+            let completed = reporter.clone();
+            // SAFETY: runs on the main thread, which owns the webview; the
+            // handler keeps the stream alive until the capture is written.
+            let result = unsafe {
+                SHCreateStreamOnFileEx(
+                    &path,
+                    (STGM_CREATE | STGM_WRITE).0,
+                    FILE_ATTRIBUTE_NORMAL.0,
+                    true,
+                    None,
+                )
+                .and_then(|stream| {
+                    let target = stream.clone();
+                    let handler = CapturePreviewCompletedHandler::create(Box::new(move |result| {
+                        // Releasing the stream closes the file.
+                        drop(target);
+                        if let Err(err) = result.ok() {
+                            report_error(&completed, &AppError::Other(err.to_string()));
+                        }
+                        Ok(())
+                    }));
+                    webview.controller().CoreWebView2()?.CapturePreview(
+                        COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_PNG,
+                        &stream,
+                        &handler,
+                    )
+                })
+            };
+            if let Err(err) = result {
+                report_error(&reporter, &AppError::Other(err.to_string()));
+            }
+        })
+        .map_err(|err| AppError::Other(err.to_string()))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn capture(_window: &WebviewWindow, _path: &Path) -> AppResult<()> {
+    Err("unsupported platform".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_png_extension_appends_when_missing() {
+        assert_eq!(
+            with_png_extension(PathBuf::from("/tmp/layout")),
+            PathBuf::from("/tmp/layout.png")
+        );
+        assert_eq!(
+            with_png_extension(PathBuf::from("/tmp/layout.PNG")),
+            PathBuf::from("/tmp/layout.PNG")
+        );
+        assert_eq!(
+            with_png_extension(PathBuf::from("/tmp/m51.fits")),
+            PathBuf::from("/tmp/m51.fits.png")
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::{ffi::CStr, os::raw::c_char, path::PathBuf, sync::OnceLock};
+
+use objc2::{
+    ClassType, class, define_class, msg_send,
+    rc::Retained,
+    runtime::{AnyClass, AnyObject, NSObject},
+};
+use tauri::{AppHandle, Manager};
+
+use crate::{AppState, defer_opened_urls, handle_opened_urls};
+
+/// Where the "Open in CARTA" service sends its files, set by `register`.
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+#[link(name = "AppKit", kind = "framework")]
+unsafe extern "C" {
+    fn NSUpdateDynamicServices();
+}
+
+define_class!(
+    // SAFETY: NSObject has no subclassing requirements, and `ServiceProvider`
+    // does not implement `Drop`.
+    #[unsafe(super(NSObject))]
+    #[name = "CARTAServiceProvider"]
+    struct ServiceProvider;
+
+    impl ServiceProvider {
+        // The `NSMessage` of the service in `Info.plist`.
+        #[unsafe(method(openInCarta:userData:error:))]
+        fn open_in_carta(
+            &self,
+            pasteboard: &AnyObject,
+            _user_data: *mut AnyObject,
+            _error: *mut *mut AnyObject,
+        ) {
+            if let Some(app) = APP.get() {
+                open_files(app, file_urls(pasteboard));
+            }
+        }
+    }
+);
+
+/// Provides the "Open in CARTA" service declared in `Info.plist`, which sends
+/// the files selected in Finder here. Files that are not images are left
+/// out, as when they are dropped on the Dock icon. Called on the main thread.
+pub(crate) fn register(app: &AppHandle) {
+    if APP.set(app.clone()).is_err() {
+        return;
+    }
+    // SAFETY: called on the main thread, where NSApplication is set up by
+    // now; the provider is leaked, so it outlives NSApp's reference to it.
+    unsafe {
+        let provider: Retained<ServiceProvider> = msg_send![ServiceProvider::class(), new];
+        let ns_app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        let _: () = msg_send![ns_app, setServicesProvider: &*provider];
+        std::mem::forget(provider);
+        // Picks up the service right after CARTA is installed or updated.
+        NSUpdateDynamicServices();
+    }
+}
+
+/// The file URLs on the pasteboard a service was sent.
+fn file_urls(pasteboard: &AnyObject) -> Vec<tauri::Url> {
+    let url_class = class!(NSURL) as *const AnyClass as *const AnyObject;
+    // SAFETY: `pasteboard` is the NSPasteboard of the service call, which is
+    // delivered on the main thread, and the strings are copied before it
+    // returns.
+    unsafe {
+        let classes: *mut AnyObject = msg_send![class!(NSArray), arrayWithObject: url_class];
+        let urls: *mut AnyObject = msg_send![
+            pasteboard,
+            readObjectsForClasses: classes,
+            options: std::ptr::null_mut::<AnyObject>()
+        ];
+        if urls.is_null() {
+            return Vec::new();
+        }
+        let count: usize = msg_send![urls, count];
+        (0..count)
+            .filter_map(|index| {
+                let url: *mut AnyObject = msg_send![urls, objectAtIndex: index];
+                let is_file: bool = msg_send![url, isFileURL];
+                if !is_file {
+                    return None;
+                }
+                let path: *mut AnyObject = msg_send![url, path];
+                let utf8: *const c_char = msg_send![path, UTF8String];
+                if utf8.is_null() {
+                    return None;
+                }
+                let path = PathBuf::from(CStr::from_ptr(utf8).to_string_lossy().into_owned());
+                tauri::Url::from_file_path(path).ok()
+            })
+            .collect()
+    }
+}
+
+fn open_files(app: &AppHandle, urls: Vec<tauri::Url>) {
+    if urls.is_empty() {
+        return;
+    }
+    let state = app.state::<AppState>();
+    if let Some(urls) = defer_opened_urls(&state, urls) {
+        handle_opened_urls(app, &state, urls);
+    }
+}
@@ -0,0 +1,196 @@
+use std::{fs, path::PathBuf};
+
+use tauri::{AppHandle, LogicalPosition, LogicalSize, Manager, Url, WebviewWindow};
+
+use crate::{
+    AppState, WindowBounds, create_window, folders, frontend_protocol, is_session_window,
+    new_window_label, write_file_atomic,
+};
+
+const SESSION_FILE: &str = "session.json";
+/// Parameters for the backend, which a restored window gets from its new one.
+const BACKEND_PARAMS: [&str; 2] = ["token", frontend_protocol::SOCKET_URL_PARAM];
+
+/// A window open when the last session ended.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct SavedWindow {
+    /// The folder its backend served. Restored on the launch backend when
+    /// that serves the same folder again, else on a backend started for it;
+    /// unset in older sessions for windows on the launch backend.
+    folder: Option<PathBuf>,
+    /// The frontend's query parameters, such as the files it opened with.
+    query: Vec<(String, String)>,
+    bounds: WindowBounds,
+}
+
+/// Keeps the open windows for `restore`; called as the app exits.
+pub(crate) fn save(app: &AppHandle) {
+    let Some(path) = session_path(app) else {
+        return;
+    };
+    let state = app.state::<AppState>();
+    let mut labels = state.window_order.lock().unwrap().clone();
+    let windows = app.webview_windows();
+    labels.extend(
+        windows
+            .keys()
+            .filter(|label| is_session_window(label) && !labels.contains(label))
+            .cloned()
+            .collect::<Vec<_>>(),
+    );
+    let saved: Vec<SavedWindow> = labels
+        .iter()
+        .filter_map(|label| windows.get(label))
+        .filter_map(|window| saved_window(app, window))
+        .collect();
+    let result = serde_json::to_vec_pretty(&saved)
+        .map_err(std::io::Error::other)
+        .and_then(|payload| write_file_atomic(&path, &payload));
+    if let Err(err) = result {
+        eprintln!("Warning: failed to save {}: {}", path.display(), err);
+    }
+}
+
+fn saved_window(app: &AppHandle, window: &WebviewWindow) -> Option<SavedWindow> {
+    let url = window.url().ok()?;
+    let state = app.state::<AppState>();
+    let port = frontend_protocol::backend_port(&url)?;
+    let folder = if port == state.backend_port() {
+        state.base_dir.clone()
+    } else {
+        state.folder_backends.base_dir_for_port(port)?
+    };
+    let (pos, size, scale) = (
+        window.outer_position().ok()?,
+        window.inner_size().ok()?,
+        window.scale_factor().ok()?,
+    );
+    Some(SavedWindow {
+        folder: Some(folder),
+        query: frontend_query(&url),
+        bounds: WindowBounds::new(pos, size, scale, false).clamp_min_size(),
+    })
+}
+
+/// Reopens the windows kept by `save`, starting backends for folders other
+/// than the launch backend's, such as when the app was launched on a
+/// different folder this time. Returns how many windows opened.
+pub(crate) async fn restore(app: &AppHandle) -> usize {
+    let Some(path) = session_path(app) else {
+        return 0;
+    };
+    let saved = match fs::read(&path) {
+        Ok(contents) => {
+            serde_json::from_slice::<Vec<SavedWindow>>(&contents).unwrap_or_else(|err| {
+                eprintln!(
+                    "Warning: ignoring invalid session in {}: {}",
+                    path.display(),
+                    err
+                );
+                Vec::new()
+            })
+        }
+        Err(_) => return 0,
+    };
+    let state = app.state::<AppState>();
+    let mut restored = 0;
+    for window in saved {
+        let base_url = match &window.folder {
+            None => state.window_url(),
+            Some(folder) if !folder.is_dir() => {
+                eprintln!(
+                    "Warning: not restoring a window for missing folder {}",
+                    folder.display()
+                );
+                continue;
+            }
+            Some(folder) => match folders::folder_window_url(app, folder).await {
+                Ok(url) => url,
+                Err(err) => {
+                    eprintln!("Warning: failed to restore a window: {}", err);
+                    continue;
+                }
+            },
+        };
+        let url = with_query(&base_url, &window.query);
+        match create_window(app, &state, new_window_label(), Some(&url)) {
+            Ok(created) => {
+                let bounds = &window.bounds;
+                let _ = created.set_size(LogicalSize::new(bounds.width, bounds.height));
+                let _ = created.set_position(LogicalPosition::new(bounds.x, bounds.y));
+                restored += 1;
+            }
+            Err(err) => eprintln!("Warning: failed to restore a window: {}", err),
+        }
+    }
+    restored
+}
+
+fn session_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join(SESSION_FILE))
+}
+
+fn frontend_query(url: &Url) -> Vec<(String, String)> {
+    url.query_pairs()
+        .filter(|(name, _)| !BACKEND_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect()
+}
+
+/// `base_url`, which carries the backend's token, with `query` added.
+fn with_query(base_url: &str, query: &[(String, String)]) -> String {
+    let Ok(mut url) = Url::parse(base_url) else {
+        return base_url.to_string();
+    };
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_query_drops_old_token_and_gets_new_one() {
+        let old = Url::parse("http://localhost:3002/?token=old&file=m51.fits").unwrap();
+        let query = frontend_query(&old);
+        assert_eq!(query, [("file".to_string(), "m51.fits".to_string())]);
+        let old = Url::parse(&format!(
+            "{}&file=m51.fits",
+            frontend_protocol::window_url(3002, Some("old"))
+        ))
+        .unwrap();
+        assert_eq!(frontend_query(&old), query);
+        assert_eq!(
+            with_query("http://localhost:4000/?token=new", &query),
+            "http://localhost:4000/?token=new&file=m51.fits"
+        );
+        assert_eq!(
+            with_query("http://localhost:4000/", &query),
+            "http://localhost:4000/?file=m51.fits"
+        );
+        assert_eq!(
+            with_query("http://localhost:4000/?token=new", &[]),
+            "http://localhost:4000/?token=new"
+        );
+    }
+
+    #[test]
+    fn saved_windows_round_trip_through_json() {
+        let saved = vec![SavedWindow {
+            folder: Some(PathBuf::from("/data/run 2")),
+            query: vec![("files".to_string(), "a.fits,b.fits".to_string())],
+            bounds: WindowBounds::default(),
+        }];
+        let json = serde_json::to_vec(&saved).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Vec<SavedWindow>>(&json).unwrap(),
+            saved
+        );
+    }
+}
@@ -0,0 +1,580 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::write_file_atomic;
+
+pub(crate) const SETTINGS_FILE: &str = "settings.json";
+/// Older files are dropped first.
+const MAX_RECENT_FILES: usize = 10;
+
+/// Theme applied to native window chrome (title bar, menus).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ThemePreference {
+    /// Follow the OS light/dark setting, including live changes.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    pub(crate) fn as_theme(self) -> Option<tauri::Theme> {
+        match self {
+            Self::System => None,
+            Self::Light => Some(tauri::Theme::Light),
+            Self::Dark => Some(tauri::Theme::Dark),
+        }
+    }
+}
+
+/// Which display server GTK connects to on Linux.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DisplayBackend {
+    /// GTK's own choice, or `GDK_BACKEND` when set.
+    #[default]
+    Auto,
+    /// XWayland on Wayland sessions.
+    X11,
+    Wayland,
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+impl DisplayBackend {
+    pub(crate) fn gdk_backend(self) -> Option<&'static str> {
+        match self {
+            Self::Auto => None,
+            Self::X11 => Some("x11"),
+            Self::Wayland => Some("wayland"),
+        }
+    }
+}
+
+/// How the first window starts: as saved, maximized, minimized or fullscreen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum StartWindowState {
+    #[default]
+    Normal,
+    Maximized,
+    /// Iconified; in the tray instead when windows hide there.
+    Minimized,
+    /// Fullscreen only, without the restrictions of kiosk mode.
+    Fullscreen,
+}
+
+/// What launching CARTA again does while it is already running.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum SecondInstance {
+    /// Start another session with its own backend.
+    #[default]
+    Separate,
+    /// Open a window in the running session, as `--new-window` does.
+    NewWindow,
+    /// Bring the running session's window to the front. Files given still
+    /// open, in a new window.
+    Focus,
+}
+
+/// Whether closing the last window quits CARTA.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum LastWindowClose {
+    /// Quit on Windows and Linux; keep running on macOS, as apps there do,
+    /// until Quit or the Dock icon opens a window again.
+    #[default]
+    PlatformDefault,
+    Quit,
+    /// Off macOS, only while the tray icon is shown.
+    KeepRunning,
+}
+
+impl LastWindowClose {
+    pub(crate) fn quits(self) -> bool {
+        match self {
+            Self::PlatformDefault => !cfg!(target_os = "macos"),
+            Self::Quit => true,
+            Self::KeepRunning => false,
+        }
+    }
+}
+
+/// The shell the WSL launcher scripts run in (Windows).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct WslShell {
+    /// `bash`, `sh` or another POSIX shell; unset uses bash when the
+    /// distribution has it, else `sh`.
+    pub(crate) program: Option<String>,
+    /// Read the user's login profile first. Off for profiles that hang or
+    /// prompt.
+    pub(crate) login: bool,
+}
+
+impl Default for WslShell {
+    fn default() -> Self {
+        Self {
+            program: None,
+            login: true,
+        }
+    }
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+impl WslShell {
+    /// The command line running `script` with `program`.
+    pub(crate) fn args(&self, program: &str, script: &str) -> [String; 3] {
+        let flags = if self.login { "-lc" } else { "-c" };
+        [program.to_string(), flags.to_string(), script.to_string()]
+    }
+}
+
+/// Which `carta_backend` to run when both the bundled one and one installed
+/// on the system are available (macOS and Linux).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BackendChoice {
+    /// The bundled backend, or the system one when the bundle has none.
+    #[default]
+    Auto,
+    Bundled,
+    /// Falls back to the bundled backend when none is installed.
+    System,
+}
+
+/// What to do when the backend exits without being asked to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RestartPolicy {
+    #[default]
+    Off,
+    /// Restart after a short fixed delay.
+    OnCrash,
+    /// Restart with an exponentially growing delay between attempts.
+    Backoff,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct RestartSettings {
+    pub(crate) policy: RestartPolicy,
+    /// Consecutive restarts to attempt before giving up.
+    pub(crate) max_attempts: u32,
+}
+
+impl Default for RestartSettings {
+    fn default() -> Self {
+        Self {
+            policy: RestartPolicy::Off,
+            max_attempts: 3,
+        }
+    }
+}
+
+impl RestartSettings {
+    const BASE_DELAY: Duration = Duration::from_secs(1);
+    const MAX_DELAY: Duration = Duration::from_secs(60);
+
+    /// Delay before restart number `attempt` (zero-based), or `None` when the
+    /// policy is off or the attempts are used up.
+    pub(crate) fn delay_for_attempt(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        match self.policy {
+            RestartPolicy::Off => None,
+            RestartPolicy::OnCrash => Some(Self::BASE_DELAY),
+            RestartPolicy::Backoff => Some(
+                Self::BASE_DELAY
+                    .saturating_mul(1 << attempt.min(16))
+                    .min(Self::MAX_DELAY),
+            ),
+        }
+    }
+}
+
+/// Where outgoing web requests get their proxy from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ProxyMode {
+    /// Leave it to the OS proxy configuration.
+    #[default]
+    System,
+    /// `https_proxy`, `http_proxy` or `all_proxy`, with `no_proxy`.
+    Environment,
+    Manual,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct ProxySettings {
+    pub(crate) mode: ProxyMode,
+    /// `http://` or `socks5://` proxy URL for manual mode.
+    pub(crate) url: Option<String>,
+    /// Comma-separated hosts reached directly in manual mode, like `no_proxy`.
+    pub(crate) no_proxy: Option<String>,
+}
+
+/// Backend session timeouts, in seconds. `None` keeps the backend's own
+/// default; values already given on the command line win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct BackendTimeouts {
+    /// `--idle_timeout`: how long an idle session is kept.
+    pub(crate) idle_secs: Option<u64>,
+    /// `--initial_timeout`: how long to wait for the first connection.
+    pub(crate) initial_secs: Option<u64>,
+    /// `--exit_timeout`: how long to keep running after the last disconnect.
+    pub(crate) exit_secs: Option<u64>,
+}
+
+impl BackendTimeouts {
+    /// The largest value the backend's integer options accept.
+    pub(crate) const NEVER: u64 = i32::MAX as u64;
+
+    /// Backend arguments for the timeouts not already set in `explicit`.
+    pub(crate) fn args(&self, explicit: &[String]) -> Vec<String> {
+        [
+            ("--idle_timeout", self.idle_secs),
+            ("--initial_timeout", self.initial_secs),
+            ("--exit_timeout", self.exit_secs),
+        ]
+        .into_iter()
+        .filter(|(name, _)| {
+            !explicit
+                .iter()
+                .any(|arg| arg == name || arg.starts_with(&format!("{}=", name)))
+        })
+        .filter_map(|(name, secs)| Some(format!("{}={}", name, secs?.min(Self::NEVER))))
+        .collect()
+    }
+}
+
+impl Default for BackendTimeouts {
+    /// The server default drops idle sessions after a few hours; a desktop
+    /// session should last as long as its window is open.
+    fn default() -> Self {
+        Self {
+            idle_secs: Some(Self::NEVER),
+            initial_secs: None,
+            exit_secs: None,
+        }
+    }
+}
+
+/// How much of the machine the backend uses: less on a shared workstation,
+/// more threads on a large one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct BackendLimits {
+    /// Run at reduced CPU priority, and I/O priority on Linux, as with
+    /// `nice`/`ionice`. On Windows this applies inside WSL, where the
+    /// backend runs.
+    pub(crate) low_priority: bool,
+    /// `--omp_threads`: the most threads the backend computes with.
+    pub(crate) omp_threads: Option<u32>,
+    /// `--threads`: how many threads handle frontend requests.
+    pub(crate) event_threads: Option<u32>,
+}
+
+impl BackendLimits {
+    /// Backend arguments for the limits not already set in `explicit`.
+    pub(crate) fn args(&self, explicit: &[String]) -> Vec<String> {
+        let given = |names: &[&str]| {
+            explicit.iter().any(|arg| {
+                names.iter().any(|name| {
+                    arg == name
+                        || arg
+                            .strip_prefix(name)
+                            .is_some_and(|rest| rest.starts_with('='))
+                })
+            })
+        };
+        let mut args = Vec::new();
+        if let Some(threads) = self.omp_threads
+            && !given(&["--omp_threads", "-t"])
+        {
+            args.push(format!("--omp_threads={}", threads.max(1)));
+        }
+        if let Some(threads) = self.event_threads
+            && !given(&["--threads"])
+        {
+            args.push(format!("--threads={}", threads.max(1)));
+        }
+        args
+    }
+}
+
+/// Components to run instead of the bundled ones, selected with `--profile`,
+/// e.g. a locally built frontend during development.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct LaunchProfile {
+    /// Laid out like the bundled `backend/` folder, as for `CARTA_BACKEND_DIR`.
+    pub(crate) backend_dir: Option<PathBuf>,
+    /// A built frontend, as for `CARTA_FRONTEND_DIR`.
+    pub(crate) frontend_dir: Option<PathBuf>,
+    /// Passed to the backend before any given on the command line.
+    pub(crate) backend_args: Vec<String>,
+}
+
+/// Launcher preferences persisted in the app config directory. Every field has
+/// a default so that files written by older versions keep loading.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    /// Open DevTools for every new window, as if `--inspect` had been passed.
+    pub(crate) always_open_devtools: bool,
+    /// Language tag overriding the system locale for native menus, e.g. `ja`.
+    pub(crate) language: Option<String>,
+    pub(crate) theme: ThemePreference,
+    /// Show a tray/menu-bar icon for restoring windows and quitting.
+    pub(crate) tray_icon: bool,
+    /// With the tray icon enabled, hide windows on close/minimize instead of
+    /// exiting, keeping the backend session warm.
+    pub(crate) minimize_to_tray: bool,
+    /// Automatic backend restart, for unattended deployments.
+    pub(crate) restart: RestartSettings,
+    /// Folder that frontend downloads are saved to; unset shows a save dialog.
+    pub(crate) download_dir: Option<PathBuf>,
+    /// Proxy for the webviews; read at startup.
+    pub(crate) proxy: ProxySettings,
+    /// Applied whenever a backend starts.
+    pub(crate) backend_timeouts: BackendTimeouts,
+    /// Applied whenever a backend starts.
+    pub(crate) backend_limits: BackendLimits,
+    /// Render webviews without GPU acceleration; read at startup.
+    pub(crate) disable_gpu: bool,
+    /// Linux only; read at startup.
+    pub(crate) display_backend: DisplayBackend,
+    /// Launch profiles by name; read at startup.
+    pub(crate) profiles: BTreeMap<String, LaunchProfile>,
+    /// Keep anonymous launch reports. Off unless the user opts in.
+    pub(crate) telemetry: bool,
+    /// The first-run setup wizard has been shown.
+    pub(crate) setup_complete: bool,
+    /// Opened instead of the home folder when started from the desktop.
+    pub(crate) default_data_dir: Option<PathBuf>,
+    /// Windows only: the WSL distribution running the backend, unless
+    /// `CARTA_WSL_DISTRO` is set; saved by `--wsl-distro` and the first-run
+    /// picker, read at startup.
+    pub(crate) wsl_distro: Option<String>,
+    /// Windows only; read at startup.
+    pub(crate) wsl_shell: WslShell,
+    /// Images CARTA was started with, most recent first; listed in the
+    /// Windows Jump List.
+    pub(crate) recent_files: Vec<PathBuf>,
+    /// Reopen the last session's windows when started without a path.
+    pub(crate) restore_session: bool,
+    /// Applied whenever a backend starts.
+    pub(crate) backend_choice: BackendChoice,
+    /// Load the frontend from the app, leaving backends only their WebSocket;
+    /// read at startup.
+    pub(crate) frontend_from_app: bool,
+    /// A `carta_controller` deployment to sign in to instead of starting a
+    /// backend, unless `--controller` is given; read at startup.
+    pub(crate) controller_url: Option<String>,
+    /// Identity-provider hosts, with their subdomains, that controller
+    /// windows sign in through; other sites open in the browser.
+    pub(crate) controller_sign_in_hosts: Vec<String>,
+    /// SHA-256 fingerprints of certificates the user trusted, by host, for
+    /// sites the system does not trust, such as a self-signed controller.
+    pub(crate) trusted_certificates: BTreeMap<String, String>,
+    /// Ask before quitting, or closing the last window, while a window reports
+    /// a long operation such as an export.
+    pub(crate) confirm_quit_when_busy: bool,
+    /// How the first window starts, unless `--maximized`, `--minimized` or
+    /// `--fullscreen` is given; read at startup.
+    pub(crate) start_window_state: StartWindowState,
+    /// The monitor the first window starts on, by its index in the system's
+    /// list, unless `--monitor` is given; read at startup.
+    pub(crate) start_monitor: Option<usize>,
+    /// Device scale factor for the webviews, e.g. `1.25` where the display's is
+    /// detected wrongly, unless `--scale-factor` is given; read at startup.
+    pub(crate) scale_factor: Option<f64>,
+    /// What a launch does while CARTA is running; `--new-window` always opens
+    /// a window there. Read at startup.
+    pub(crate) second_instance: SecondInstance,
+    /// Look up the latest CARTA release at startup and mention newer ones.
+    pub(crate) check_for_updates: bool,
+    /// The release last mentioned, which is not mentioned again.
+    pub(crate) notified_release: Option<String>,
+    /// Whether closing the last window quits, or CARTA and its backend keep
+    /// running without windows.
+    pub(crate) last_window_close: LastWindowClose,
+    /// The address backends listen on and are reached at, e.g. `::1` where
+    /// IPv4 loopback is disabled, unless `--bind-address` or a backend
+    /// `--host` is given. All interfaces is `--share-lan`, and ignored here.
+    /// Read at startup.
+    pub(crate) bind_address: Option<IpAddr>,
+    /// A system-wide shortcut for a new window, e.g. `CmdOrCtrl+Alt+N`, which
+    /// works while another app has the focus.
+    pub(crate) new_window_shortcut: Option<String>,
+}
+
+impl Settings {
+    /// Moves `files` to the front of the recent files, the first one first.
+    pub(crate) fn add_recent_files(&mut self, files: &[PathBuf]) {
+        self.recent_files.retain(|recent| !files.contains(recent));
+        self.recent_files.splice(0..0, files.iter().cloned());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    pub(crate) fn load(path: &Path) -> Self {
+        match fs::read(path) {
+            Ok(contents) => serde_json::from_slice(&contents).unwrap_or_else(|err| {
+                eprintln!(
+                    "Warning: ignoring invalid settings in {}: {}",
+                    path.display(),
+                    err
+                );
+                Self::default()
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => {
+                eprintln!("Warning: failed to read {}: {}", path.display(), err);
+                Self::default()
+            }
+        }
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> io::Result<()> {
+        let payload = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        write_file_atomic(path, &payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_fill_missing_fields_with_defaults() {
+        let settings: Settings = serde_json::from_str("{}").unwrap();
+        assert!(!settings.always_open_devtools);
+        assert!(settings.language.is_none());
+        assert_eq!(settings.theme, ThemePreference::System);
+        assert!(!settings.telemetry);
+    }
+
+    #[test]
+    fn theme_preference_uses_lowercase_names() {
+        let settings: Settings = serde_json::from_str(r#"{"theme": "dark"}"#).unwrap();
+        assert_eq!(settings.theme, ThemePreference::Dark);
+        assert_eq!(settings.theme.as_theme(), Some(tauri::Theme::Dark));
+    }
+
+    #[test]
+    fn backend_choice_defaults_to_auto() {
+        let settings: Settings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.backend_choice, BackendChoice::Auto);
+        let settings: Settings = serde_json::from_str(r#"{"backend_choice": "system"}"#).unwrap();
+        assert_eq!(settings.backend_choice, BackendChoice::System);
+    }
+
+    #[test]
+    fn restart_delays_follow_policy_and_attempt_limit() {
+        let mut restart: RestartSettings =
+            serde_json::from_str(r#"{"policy": "backoff", "max_attempts": 8}"#).unwrap();
+        assert_eq!(restart.delay_for_attempt(0), Some(Duration::from_secs(1)));
+        assert_eq!(restart.delay_for_attempt(3), Some(Duration::from_secs(8)));
+        assert_eq!(restart.delay_for_attempt(7), Some(Duration::from_secs(60)));
+        assert_eq!(restart.delay_for_attempt(8), None);
+
+        restart.policy = RestartPolicy::OnCrash;
+        assert_eq!(restart.delay_for_attempt(5), Some(Duration::from_secs(1)));
+        restart.policy = RestartPolicy::Off;
+        assert_eq!(restart.delay_for_attempt(0), None);
+    }
+
+    #[test]
+    fn recent_files_move_to_front_and_are_capped() {
+        let mut settings = Settings::default();
+        let file = |n: usize| PathBuf::from(format!("/data/{}.fits", n));
+        settings.add_recent_files(&(0..MAX_RECENT_FILES).map(file).collect::<Vec<_>>());
+        settings.add_recent_files(&[file(99), file(3)]);
+        assert_eq!(settings.recent_files.len(), MAX_RECENT_FILES);
+        assert_eq!(settings.recent_files[..3], [file(99), file(3), file(0)]);
+        assert!(!settings.recent_files.contains(&file(MAX_RECENT_FILES - 1)));
+    }
+
+    #[test]
+    fn settings_round_trip_through_file() {
+        let dir = std::env::temp_dir().join(format!("carta-settings-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(SETTINGS_FILE);
+
+        let settings = Settings {
+            always_open_devtools: true,
+            ..Settings::default()
+        };
+        settings.save(&path).unwrap();
+        assert!(Settings::load(&path).always_open_devtools);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn launch_profiles_load_by_name() {
+        let settings: Settings = serde_json::from_str(
+            r#"{"profiles": {"dev": {"frontend_dir": "/src/carta-frontend/build", "backend_args": ["--verbosity=5"]}}}"#,
+        )
+        .unwrap();
+        let dev = &settings.profiles["dev"];
+        assert_eq!(
+            dev.frontend_dir.as_deref(),
+            Some(Path::new("/src/carta-frontend/build"))
+        );
+        assert!(dev.backend_dir.is_none());
+        assert_eq!(dev.backend_args, ["--verbosity=5"]);
+    }
+
+    #[test]
+    fn backend_timeouts_skip_unset_and_explicit_options() {
+        let timeouts = BackendTimeouts::default();
+        assert_eq!(timeouts.args(&[]), ["--idle_timeout=2147483647"]);
+        assert!(timeouts.args(&["--idle_timeout=60".to_string()]).is_empty());
+
+        let timeouts: BackendTimeouts =
+            serde_json::from_str(r#"{"idle_secs": null, "exit_secs": 30}"#).unwrap();
+        assert_eq!(
+            timeouts.args(&["--exit_timeout".to_string(), "5".to_string()]),
+            Vec::<String>::new()
+        );
+        assert_eq!(timeouts.args(&[]), ["--exit_timeout=30"]);
+    }
+
+    #[test]
+    fn wsl_shell_runs_login_shell_unless_turned_off() {
+        let shell = WslShell::default();
+        assert_eq!(shell.args("bash", "true"), ["bash", "-lc", "true"]);
+        let shell: WslShell = serde_json::from_str(r#"{"program": "sh", "login": false}"#).unwrap();
+        assert_eq!(shell.program.as_deref(), Some("sh"));
+        assert_eq!(shell.args("sh", "true"), ["sh", "-c", "true"]);
+    }
+
+    #[test]
+    fn backend_limits_leave_explicit_thread_count() {
+        assert!(BackendLimits::default().args(&[]).is_empty());
+        let limits = BackendLimits {
+            low_priority: true,
+            omp_threads: Some(4),
+            event_threads: Some(0),
+        };
+        assert_eq!(limits.args(&[]), ["--omp_threads=4", "--threads=1"]);
+        assert_eq!(
+            limits.args(&["-t".to_string(), "8".to_string()]),
+            ["--threads=1"]
+        );
+        assert_eq!(
+            limits.args(&["--omp_threads=8".to_string(), "--threads=2".to_string()]),
+            Vec::<String>::new()
+        );
+    }
+}
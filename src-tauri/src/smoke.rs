@@ -0,0 +1,131 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    sync::mpsc,
+    time::Duration,
+};
+
+use tauri::{AppHandle, Url, WebviewUrl, WebviewWindowBuilder, webview::PageLoadEvent};
+
+use crate::{AppError, AppResult, ExitCode, backend_host, bind_address};
+
+const WINDOW_LABEL: &str = "smoke-test";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const LOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What `--smoke-test` checks once the backend accepts connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SmokeTest {
+    /// The backend serves the frontend's index page.
+    Backend,
+    /// As `Backend`, and the page also loads in a hidden webview.
+    Webview,
+}
+
+/// Checks the running backend at `url` and exits the app with the result,
+/// without showing any window.
+pub(crate) fn run(app: &AppHandle, test: SmokeTest, url: &str) {
+    let result = Url::parse(url)
+        .map_err(|err| AppError::Other(err.to_string()))
+        .and_then(|url| fetch_index(&url).map(|()| url));
+    match (result, test) {
+        (Ok(url), SmokeTest::Webview) => {
+            if let Err(err) = load_in_webview(app, url) {
+                finish(app, Err(err));
+            }
+        }
+        (result, _) => finish(app, result.map(|_| ())),
+    }
+}
+
+fn finish(app: &AppHandle, result: AppResult<()>) {
+    match result {
+        Ok(()) => {
+            println!("Smoke test passed");
+            app.exit(0);
+        }
+        Err(err) => {
+            eprintln!("Smoke test failed: {}", err);
+            app.exit(ExitCode::Failure as i32);
+        }
+    }
+}
+
+/// A plain HTTP/1.0 request, so the check needs no webview or HTTP client.
+fn fetch_index(url: &Url) -> AppResult<()> {
+    let port = url.port_or_known_default().unwrap_or(80);
+    let mut stream = TcpStream::connect((bind_address(), port))?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    write!(
+        stream,
+        "GET {} HTTP/1.0\r\nHost: {}:{}\r\n\r\n",
+        target,
+        backend_host(),
+        port
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    check_response(&response)
+}
+
+fn check_response(response: &str) -> AppResult<()> {
+    let status = response.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(AppError::Other(format!(
+            "frontend request returned \"{}\"",
+            status.trim()
+        )));
+    }
+    let (_, body) = response.split_once("\r\n\r\n").unwrap_or_default();
+    if !body.to_ascii_lowercase().contains("<html") {
+        return Err("frontend request returned no HTML page".into());
+    }
+    Ok(())
+}
+
+fn load_in_webview(app: &AppHandle, url: Url) -> AppResult<()> {
+    let (loaded_tx, loaded_rx) = mpsc::channel();
+    WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::External(url))
+        .visible(false)
+        .on_page_load(move |_, payload| {
+            if payload.event() == PageLoadEvent::Finished {
+                let _ = loaded_tx.send(());
+            }
+        })
+        .build()
+        .map_err(|err| AppError::Other(err.to_string()))?;
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let result = loaded_rx.recv_timeout(LOAD_TIMEOUT).map_err(|_| {
+            AppError::Other(format!(
+                "frontend did not load within {}s",
+                LOAD_TIMEOUT.as_secs()
+            ))
+        });
+        finish(&app, result);
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_response_requires_ok_html() {
+        assert!(
+            check_response(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n<!doctype html><html>"
+            )
+            .is_ok()
+        );
+        assert!(check_response("HTTP/1.1 404 Not Found\r\n\r\nnot found").is_err());
+        assert!(check_response("HTTP/1.1 200 OK\r\n\r\n").is_err());
+        assert!(check_response("").is_err());
+    }
+}
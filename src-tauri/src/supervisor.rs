@@ -0,0 +1,370 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    process::ExitStatus,
+    sync::{Mutex, atomic::Ordering},
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager, Url, async_runtime::JoinHandle};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use tokio::sync::oneshot;
+
+use crate::{
+    AppError, AppResult, AppState, connection_lost, forget_launch_backend_work, free_launch_port,
+    frontend_protocol, is_session_window, settings::RestartPolicy, shutdown_backend, spawn_backend,
+    wait_for_backend,
+};
+
+const POLL_INTERVAL_MS: u64 = 1000;
+/// A backend that stays up this long is considered healthy again, and the
+/// restart attempt counter starts over.
+const STABLE_AFTER_SECS: u64 = 300;
+/// This many crashes within the window stops automatic restarts.
+const CRASH_LOOP_LIMIT: usize = 3;
+const CRASH_LOOP_WINDOW_SECS: u64 = 60;
+const LOG_TAIL_LINES: usize = 500;
+/// How much of the backend's stderr the crash-loop dialog shows.
+const CRASH_REPORT_LINES: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct LogLine {
+    pub(crate) stream: OutputStream,
+    pub(crate) text: String,
+}
+
+/// The last lines the backends wrote, kept for crash reports and the
+/// frontend. Survives restarts, so a crash's output is still there after.
+#[derive(Default)]
+pub(crate) struct BackendLog(Mutex<VecDeque<LogLine>>);
+
+impl BackendLog {
+    pub(crate) fn push(&self, stream: OutputStream, text: String) {
+        let mut lines = self.0.lock().unwrap();
+        if lines.len() == LOG_TAIL_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(LogLine { stream, text });
+    }
+
+    /// The last `count` lines, oldest first.
+    pub(crate) fn tail(&self, count: usize) -> Vec<LogLine> {
+        let lines = self.0.lock().unwrap();
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(count))
+            .cloned()
+            .collect()
+    }
+
+    fn stderr_tail(&self, count: usize) -> String {
+        let lines = self.0.lock().unwrap();
+        let stderr = lines
+            .iter()
+            .filter(|line| line.stream == OutputStream::Stderr)
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>();
+        stderr[stderr.len().saturating_sub(count)..].join("\n")
+    }
+}
+
+/// Tasks on Tauri's async runtime that run until the app exits, unless they
+/// finish first. They are aborted on exit, before the backends are stopped.
+#[derive(Default)]
+pub(crate) struct BackgroundTasks(Mutex<Vec<JoinHandle<()>>>);
+
+impl BackgroundTasks {
+    pub(crate) fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let mut tasks = self.0.lock().unwrap();
+        tasks.retain(|task| !task.inner().is_finished());
+        tasks.push(tauri::async_runtime::spawn(task));
+    }
+
+    pub(crate) fn abort_all(&self) {
+        for task in self.0.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
+
+/// Recent crash times, to tell an occasional crash from a crash loop.
+#[derive(Default)]
+struct CrashHistory(VecDeque<Instant>);
+
+impl CrashHistory {
+    /// Records a crash and reports whether the crash-loop limit has been hit.
+    fn record(&mut self, at: Instant) -> bool {
+        let window = Duration::from_secs(CRASH_LOOP_WINDOW_SECS);
+        self.0.retain(|&crash| at.duration_since(crash) < window);
+        self.0.push_back(at);
+        self.0.len() >= CRASH_LOOP_LIMIT
+    }
+}
+
+/// Watches the backend and restarts it according to the `restart` setting.
+pub(crate) fn start(app: &AppHandle) {
+    let handle = app.clone();
+    app.state::<AppState>().tasks.spawn(async move {
+        let app = handle;
+        let state = app.state::<AppState>();
+        let mut attempts = 0;
+        let mut started = Instant::now();
+        let mut crashes = CrashHistory::default();
+        loop {
+            tokio::time::sleep(Duration::from_millis(POLL_INTERVAL_MS)).await;
+            let Some(status) = take_exited_backend(&state) else {
+                continue;
+            };
+            eprintln!(
+                "Warning: backend exited unexpectedly with status: {}",
+                status
+            );
+            if started.elapsed() >= Duration::from_secs(STABLE_AFTER_SECS) {
+                attempts = 0;
+            }
+            forget_launch_backend_work(&app);
+            connection_lost::show(&app);
+
+            state.backend_restarting.store(true, Ordering::Relaxed);
+            let mut crashed_at = Instant::now();
+            loop {
+                let restart = state.settings.lock().unwrap().restart;
+                if restart.policy != RestartPolicy::Off && crashes.record(crashed_at) {
+                    report_crash_loop(&app, &state);
+                    break;
+                }
+                let Some(delay) = restart.delay_for_attempt(attempts) else {
+                    eprintln!(
+                        "Warning: not restarting the backend (policy: {:?}, attempts: {})",
+                        restart.policy, attempts
+                    );
+                    break;
+                };
+                attempts += 1;
+                tokio::time::sleep(delay).await;
+                started = Instant::now();
+                let port = state.backend_port();
+                match restart_backend(&app).await {
+                    Ok(()) => {
+                        eprintln!("Backend restarted (attempt {})", attempts);
+                        reload_windows(&app, port);
+                        break;
+                    }
+                    Err(err) => {
+                        eprintln!("Warning: failed to restart backend: {}", err);
+                        crashed_at = Instant::now();
+                    }
+                }
+            }
+            state.backend_restarting.store(false, Ordering::Relaxed);
+        }
+    });
+}
+
+/// Removes and returns the exit status of a backend that has exited on its
+/// own. `shutdown_backend` takes the child before killing it, so deliberate
+/// shutdowns never show up here.
+fn take_exited_backend(state: &AppState) -> Option<ExitStatus> {
+    let mut backend = state.backend.lock().unwrap();
+    let status = backend.as_mut()?.try_wait().ok()??;
+    *backend = None;
+    Some(status)
+}
+
+/// Starts the launch backend again, on another port if its own was taken, and
+/// waits until it accepts connections.
+/// The spawn happens on the main thread, which outlives the backend (see
+/// `set_parent_death_signal`); runtime worker threads make no such promise.
+pub(crate) async fn restart_backend(app: &AppHandle) -> AppResult<()> {
+    let (spawned_tx, spawned_rx) = oneshot::channel();
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        let state = handle.state::<AppState>();
+        let backend_args = state.backend_args.lock().unwrap().clone();
+        let _ = spawned_tx.send(
+            free_launch_port(&state)
+                .and_then(|()| spawn_backend(&handle, &state, &state.base_dir, &backend_args)),
+        );
+    })
+    .map_err(|err| AppError::Other(err.to_string()))?;
+    spawned_rx
+        .await
+        .map_err(|_| AppError::from("Backend spawn was cancelled."))??;
+
+    let state = app.state::<AppState>();
+    if let Err(err) = wait_for_backend(&state, state.backend_timeout).await {
+        shutdown_backend(&state);
+        return Err(err);
+    }
+    Ok(())
+}
+
+fn report_crash_loop(app: &AppHandle, state: &AppState) {
+    let summary = format!(
+        "The CARTA backend crashed {} times within {} seconds, so automatic restarts \
+         have been stopped. Check the data directory and backend options, then restart CARTA.",
+        CRASH_LOOP_LIMIT, CRASH_LOOP_WINDOW_SECS
+    );
+    eprintln!("Error: {}", summary);
+    let stderr = state.backend_log.stderr_tail(CRASH_REPORT_LINES);
+    let message = if stderr.is_empty() {
+        summary
+    } else {
+        format!("{}\n\nLast backend output:\n{}", summary, stderr)
+    };
+    app.dialog()
+        .message(message)
+        .title("CARTA")
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+/// The token is unchanged, so loading each window's page again reconnects it
+/// to the launch backend; windows still on `old_port`, where the backend was
+/// before `free_launch_port` moved it, are pointed at the new port. Folder
+/// windows have their own backends.
+pub(crate) fn reload_windows(app: &AppHandle, old_port: u16) {
+    let state = app.state::<AppState>();
+    let window_url = state.window_url();
+    for (label, window) in app.webview_windows() {
+        if !is_session_window(&label) {
+            continue;
+        }
+        let Some(url) = reload_url(
+            window.url().ok(),
+            old_port,
+            state.backend_port(),
+            &window_url,
+        ) else {
+            continue;
+        };
+        if let Err(err) = window.navigate(url) {
+            eprintln!("Warning: failed to reload window {}: {}", label, err);
+        }
+    }
+}
+
+/// Where a window goes once the launch backend is back: its own page, the
+/// page it showed before the connection-lost page, or the launch page when it
+/// was left on an error page without a backend, moved from `old_port` to
+/// `launch_port`. `None` for windows on other backends.
+fn reload_url(
+    current: Option<Url>,
+    old_port: u16,
+    launch_port: u16,
+    window_url: &str,
+) -> Option<Url> {
+    let moved = |url: Url| match frontend_protocol::backend_port(&url) {
+        Some(port) if port == old_port => frontend_protocol::with_backend_port(&url, launch_port),
+        _ => url,
+    };
+    match current.as_ref().and_then(frontend_protocol::backend_port) {
+        Some(port) if port == launch_port || port == old_port => current.map(moved),
+        Some(_) => None,
+        None => current
+            .as_ref()
+            .and_then(connection_lost::return_url)
+            .map(moved)
+            .or_else(|| Url::parse(window_url).ok()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crash_history_detects_rapid_crashes_only() {
+        let start = Instant::now();
+        let mut crashes = CrashHistory::default();
+        assert!(!crashes.record(start));
+        assert!(!crashes.record(start + Duration::from_secs(45)));
+        // The first crash has aged out of the window.
+        assert!(!crashes.record(start + Duration::from_secs(90)));
+        assert!(crashes.record(start + Duration::from_secs(100)));
+    }
+
+    #[test]
+    fn reload_url_returns_launch_windows_to_their_page() {
+        let launch = "http://localhost:3002/?token=abc";
+        let url = |value: &str| Url::parse(value).ok();
+        let with_file = url("http://localhost:3002/?token=abc&file=m51.fits");
+        assert_eq!(reload_url(with_file.clone(), 3002, 3002, launch), with_file);
+        assert_eq!(
+            reload_url(url("http://localhost:4000/?token=xyz"), 3002, 3002, launch),
+            None
+        );
+        assert_eq!(
+            reload_url(url("about:blank"), 3002, 3002, launch),
+            url(launch)
+        );
+        assert_eq!(reload_url(None, 3002, 3002, launch), url(launch));
+        let lost = Url::parse(&format!(
+            "{}connection-lost?return=http%3A%2F%2Flocalhost%3A3002%2F%3Ffile%3Dm51.fits",
+            crate::custom_scheme_origin(connection_lost::SCHEME)
+        ))
+        .ok();
+        assert_eq!(
+            reload_url(lost, 3002, 3002, launch),
+            url("http://localhost:3002/?file=m51.fits")
+        );
+    }
+
+    #[test]
+    fn reload_url_follows_the_backend_to_its_new_port() {
+        let launch = "http://localhost:3005/?token=abc";
+        let url = |value: &str| Url::parse(value).ok();
+        assert_eq!(
+            reload_url(
+                url("http://localhost:3002/?token=abc&file=m51.fits"),
+                3002,
+                3005,
+                launch
+            ),
+            url("http://localhost:3005/?token=abc&file=m51.fits")
+        );
+        assert_eq!(
+            reload_url(url("http://localhost:4000/?token=xyz"), 3002, 3005, launch),
+            None
+        );
+        let lost = Url::parse(&format!(
+            "{}connection-lost?return=http%3A%2F%2Flocalhost%3A3002%2F%3Ffile%3Dm51.fits",
+            crate::custom_scheme_origin(connection_lost::SCHEME)
+        ))
+        .ok();
+        assert_eq!(
+            reload_url(lost, 3002, 3005, launch),
+            url("http://localhost:3005/?file=m51.fits")
+        );
+    }
+
+    #[test]
+    fn backend_log_keeps_most_recent_lines() {
+        let log = BackendLog::default();
+        for i in 0..LOG_TAIL_LINES + 2 {
+            let stream = if i % 2 == 0 {
+                OutputStream::Stderr
+            } else {
+                OutputStream::Stdout
+            };
+            log.push(stream, format!("line {}", i));
+        }
+        let tail = log.tail(usize::MAX);
+        assert_eq!(tail.len(), LOG_TAIL_LINES);
+        assert_eq!(tail[0].text, "line 2");
+        let last = log.tail(1);
+        assert_eq!(last[0].text, format!("line {}", LOG_TAIL_LINES + 1));
+        assert_eq!(last[0].stream, OutputStream::Stdout);
+
+        let stderr = log.stderr_tail(CRASH_REPORT_LINES);
+        assert_eq!(stderr.lines().count(), CRASH_REPORT_LINES);
+        assert!(stderr.ends_with(&format!("line {}", LOG_TAIL_LINES)));
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::OnceLock,
+};
+
+use crate::{
+    BACKEND_FILENAME, parse_backend_version,
+    process::{ProcessRunner, SystemRunner},
+};
+
+/// Searched after `PATH`, which GUI launches often leave minimal.
+#[cfg(target_os = "macos")]
+const COMMON_DIRS: &[&str] = &["/opt/homebrew/bin", "/usr/local/bin", "/opt/carta/bin"];
+#[cfg(target_os = "linux")]
+const COMMON_DIRS: &[&str] = &[
+    "/usr/bin",
+    "/usr/local/bin",
+    "/opt/carta/bin",
+    "/opt/carta-backend/bin",
+];
+
+/// A `carta_backend` installed outside the app bundle.
+pub(crate) struct SystemBackend {
+    pub(crate) path: PathBuf,
+    pub(crate) version: String,
+}
+
+/// The first compatible `carta_backend` on `PATH` or in a common install
+/// location. Searched once; the choice is logged.
+pub(crate) fn find() -> Option<&'static SystemBackend> {
+    static FOUND: OnceLock<Option<SystemBackend>> = OnceLock::new();
+    FOUND
+        .get_or_init(|| {
+            let found = candidates().into_iter().find_map(|path| {
+                let version = version_of(&SystemRunner, &path)?;
+                if is_compatible(&version, env!("CARGO_PKG_VERSION")) {
+                    Some(SystemBackend { path, version })
+                } else {
+                    eprintln!(
+                        "Warning: skipping {} (version {}, expected {}.x)",
+                        path.display(),
+                        version,
+                        major(env!("CARGO_PKG_VERSION"))
+                    );
+                    None
+                }
+            });
+            match &found {
+                Some(backend) => eprintln!(
+                    "Found system carta_backend {} (version {})",
+                    backend.path.display(),
+                    backend.version
+                ),
+                None => eprintln!("Warning: no compatible carta_backend found on PATH"),
+            }
+            found
+        })
+        .as_ref()
+}
+
+fn candidates() -> Vec<PathBuf> {
+    let path_dirs = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    for dir in path_dirs
+        .into_iter()
+        .chain(COMMON_DIRS.iter().map(PathBuf::from))
+    {
+        let candidate = dir.join(BACKEND_FILENAME);
+        if candidate.is_file() && !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+fn version_of(processes: &dyn ProcessRunner, path: &Path) -> Option<String> {
+    let output = processes.output(Command::new(path).arg("--version")).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_backend_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn major(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version)
+}
+
+/// The frontend and backend protocol only stay compatible within a major version.
+fn is_compatible(version: &str, app_version: &str) -> bool {
+    major(version) == major(app_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::FakeRunner;
+
+    #[test]
+    fn version_of_runs_backend_with_version_flag() {
+        let processes = FakeRunner::with_results(&[(0, "5.0.3\n"), (1, "")]);
+        let path = Path::new("/opt/carta/bin/carta_backend");
+        assert_eq!(version_of(&processes, path).as_deref(), Some("5.0.3"));
+        assert_eq!(version_of(&processes, path), None);
+        assert_eq!(
+            processes.commands(),
+            [
+                "/opt/carta/bin/carta_backend --version",
+                "/opt/carta/bin/carta_backend --version"
+            ]
+        );
+    }
+
+    #[test]
+    fn is_compatible_compares_major_version() {
+        assert!(is_compatible("5.1.0", "5.0.3"));
+        assert!(!is_compatible("4.1.0", "5.0.3"));
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use crate::{ExitCode, write_file_atomic};
+
+const QUEUE_FILE: &str = "telemetry.json";
+/// Older reports are dropped first.
+const MAX_QUEUED: usize = 20;
+
+static RECORDER: OnceLock<Recorder> = OnceLock::new();
+
+/// Anonymous launch reports, kept only when the user opts in. Reports hold
+/// nothing that identifies the user, machine, or files: just the platform,
+/// the app version, how the launch ended, and how long startup took.
+struct Recorder {
+    enabled: AtomicBool,
+    queue_path: Option<PathBuf>,
+    start: Instant,
+    /// This launch's report, recorded once, whether or not it was kept.
+    current: Mutex<Option<LaunchReport>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct LaunchReport {
+    os: String,
+    arch: String,
+    version: String,
+    /// `started`, or the failure category matching the exit code.
+    outcome: String,
+    startup_ms: u64,
+}
+
+/// Starts the startup clock; call as early in `run` as possible.
+pub(crate) fn init(enabled: bool, config_dir: Option<PathBuf>) {
+    RECORDER.get_or_init(|| Recorder {
+        enabled: AtomicBool::new(enabled),
+        queue_path: config_dir.map(|dir| dir.join(QUEUE_FILE)),
+        start: Instant::now(),
+        current: Mutex::new(None),
+    });
+}
+
+/// The session is up: the backend answered and windows can open.
+pub(crate) fn record_started() {
+    record("started");
+}
+
+pub(crate) fn record_failure(code: ExitCode) {
+    record(match code {
+        ExitCode::Failure => "failure",
+        ExitCode::Usage => "usage",
+        ExitCode::InvalidInput => "invalid-input",
+        ExitCode::BackendMissing => "backend-missing",
+        ExitCode::BackendFailed => "backend-failed",
+        ExitCode::PortUnavailable => "port-unavailable",
+    });
+}
+
+/// Only the first outcome of a launch counts.
+fn record(outcome: &str) {
+    let Some(recorder) = RECORDER.get() else {
+        return;
+    };
+    let mut current = recorder.current.lock().unwrap();
+    if current.is_some() {
+        return;
+    }
+    let report = LaunchReport {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        outcome: outcome.to_string(),
+        startup_ms: recorder.start.elapsed().as_millis() as u64,
+    };
+    if recorder.enabled.load(Ordering::Relaxed) {
+        let mut queue = recorder.load_queue();
+        queue.push(report.clone());
+        recorder.save_queue(&queue);
+    }
+    *current = Some(report);
+}
+
+/// Turning telemetry off also discards the reports kept so far.
+pub(crate) fn set_enabled(enabled: bool) {
+    let Some(recorder) = RECORDER.get() else {
+        return;
+    };
+    let was_enabled = recorder.enabled.swap(enabled, Ordering::Relaxed);
+    if was_enabled && !enabled {
+        recorder.save_queue(&[]);
+    } else if !was_enabled
+        && enabled
+        && let Some(report) = recorder.current.lock().unwrap().clone()
+    {
+        let mut queue = recorder.load_queue();
+        queue.push(report);
+        recorder.save_queue(&queue);
+    }
+}
+
+/// Exactly what would be sent: the kept reports, or while telemetry is off,
+/// the report this launch would have added.
+pub(crate) fn preview() -> Vec<LaunchReport> {
+    let Some(recorder) = RECORDER.get() else {
+        return Vec::new();
+    };
+    if recorder.enabled.load(Ordering::Relaxed) {
+        recorder.load_queue()
+    } else {
+        recorder.current.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+impl Recorder {
+    fn load_queue(&self) -> Vec<LaunchReport> {
+        self.queue_path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_queue(&self, queue: &[LaunchReport]) {
+        let Some(path) = &self.queue_path else {
+            return;
+        };
+        let queue = &queue[queue.len().saturating_sub(MAX_QUEUED)..];
+        let result = serde_json::to_vec_pretty(queue)
+            .map_err(std::io::Error::other)
+            .and_then(|payload| write_file_atomic(path, &payload));
+        if let Err(err) = result {
+            eprintln!("Warning: failed to save {}: {}", path.display(), err);
+        }
+    }
+}
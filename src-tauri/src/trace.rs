@@ -0,0 +1,90 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use tauri::{AppHandle, Manager};
+
+const LOG_FILE: &str = "startup.log";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static START: OnceLock<Instant> = OnceLock::new();
+static SINK: Mutex<Sink> = Mutex::new(Sink::Buffer(Vec::new()));
+
+/// Where trace lines go besides stderr. Lines logged before the app's log
+/// directory is known are kept until it is.
+enum Sink {
+    Buffer(Vec<String>),
+    File(File),
+    None,
+}
+
+/// Logs a startup decision when `--verbose` is on; free otherwise.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::trace::enabled() {
+            $crate::trace::log(&format!($($arg)*));
+        }
+    };
+}
+pub(crate) use trace;
+
+pub(crate) fn enable() {
+    START.get_or_init(Instant::now);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub(crate) fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn log(message: &str) {
+    let elapsed = START.get_or_init(Instant::now).elapsed();
+    let line = format!("[verbose +{:.3}s] {}", elapsed.as_secs_f64(), message);
+    eprintln!("{}", line);
+    match &mut *SINK.lock().unwrap() {
+        Sink::Buffer(lines) => lines.push(line),
+        Sink::File(file) => {
+            let _ = writeln!(file, "{}", line);
+        }
+        Sink::None => {}
+    }
+}
+
+/// Starts `startup.log` in the app log directory, replacing the previous
+/// launch's, and writes out the lines logged so far.
+pub(crate) fn open_log_file(app: &AppHandle) {
+    if !enabled() {
+        return;
+    }
+    let file = app.path().app_log_dir().ok().and_then(|dir| {
+        fs::create_dir_all(&dir).ok()?;
+        let path = dir.join(LOG_FILE);
+        match File::create(&path) {
+            Ok(file) => {
+                eprintln!("Writing startup trace to {}", path.display());
+                Some(file)
+            }
+            Err(err) => {
+                eprintln!("Warning: failed to create {}: {}", path.display(), err);
+                None
+            }
+        }
+    });
+    let mut sink = SINK.lock().unwrap();
+    let buffered = match std::mem::replace(&mut *sink, Sink::None) {
+        Sink::Buffer(lines) => lines,
+        _ => Vec::new(),
+    };
+    if let Some(mut file) = file {
+        for line in buffered {
+            let _ = writeln!(file, "{}", line);
+        }
+        *sink = Sink::File(file);
+    }
+}
@@ -0,0 +1,134 @@
+use tauri::{
+    AppHandle, Manager,
+    menu::{MenuBuilder, MenuItem},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+};
+
+use crate::{
+    AppState, MENU_COPY_AUTH_TOKEN, MENU_NEW_WINDOW, copy_auth_token, create_window,
+    i18n::{Message, tr},
+    new_window_label, open_new_window, quit, ui_language,
+};
+
+const TRAY_ID: &str = "carta-tray";
+const MENU_TRAY_SHOW: &str = "tray_show";
+const MENU_TRAY_QUIT: &str = "tray_quit";
+
+/// Creates or removes the tray icon to match the `tray_icon` preference.
+pub(crate) fn sync_tray(app: &AppHandle) {
+    let enabled = app.state::<AppState>().settings.lock().unwrap().tray_icon;
+    let existing = app.tray_by_id(TRAY_ID);
+    match (enabled, existing) {
+        (true, None) => {
+            if let Err(err) = build_tray(app) {
+                eprintln!("Warning: failed to create tray icon: {}", err);
+            }
+        }
+        (false, Some(_)) => {
+            let _ = app.remove_tray_by_id(TRAY_ID);
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn is_visible(app: &AppHandle) -> bool {
+    app.tray_by_id(TRAY_ID).is_some()
+}
+
+pub(crate) fn set_tooltip(app: &AppHandle, text: &str) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(text));
+    }
+}
+
+/// Whether closing or minimizing a window should hide it to the tray instead.
+pub(crate) fn hides_to_tray(app: &AppHandle) -> bool {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    settings.tray_icon && settings.minimize_to_tray && is_visible(app)
+}
+
+fn build_tray(app: &AppHandle) -> tauri::Result<()> {
+    let language = ui_language(app);
+    let show = MenuItem::with_id(
+        app,
+        MENU_TRAY_SHOW,
+        tr(language, Message::ShowWindows),
+        true,
+        None::<&str>,
+    )?;
+    let new_window = MenuItem::with_id(
+        app,
+        MENU_NEW_WINDOW,
+        tr(language, Message::NewWindow),
+        true,
+        None::<&str>,
+    )?;
+    let copy_token = MenuItem::with_id(
+        app,
+        MENU_COPY_AUTH_TOKEN,
+        tr(language, Message::CopyAuthToken),
+        true,
+        None::<&str>,
+    )?;
+    let quit = MenuItem::with_id(
+        app,
+        MENU_TRAY_QUIT,
+        tr(language, Message::Quit),
+        true,
+        None::<&str>,
+    )?;
+    let menu = MenuBuilder::new(app)
+        .item(&show)
+        .item(&new_window)
+        .item(&copy_token)
+        .separator()
+        .item(&quit)
+        .build()?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("CARTA")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            MENU_TRAY_SHOW => show_all_windows(app),
+            MENU_NEW_WINDOW => {
+                let state = app.state::<AppState>();
+                open_new_window(app, &state);
+            }
+            MENU_COPY_AUTH_TOKEN => copy_auth_token(app),
+            MENU_TRAY_QUIT => quit::quit(app),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_all_windows(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon().cloned() {
+        builder = builder.icon(icon);
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Restores every window hidden to the tray, or opens a new one if none exist.
+pub(crate) fn show_all_windows(app: &AppHandle) {
+    let windows = app.webview_windows();
+    if windows.is_empty() {
+        let state = app.state::<AppState>();
+        let _ = create_window(app, &state, new_window_label(), None);
+        return;
+    }
+    for window in windows.values() {
+        let _ = window.show();
+        let _ = window.unminimize();
+    }
+    if let Some(window) = windows.values().next() {
+        let _ = window.set_focus();
+    }
+}
@@ -0,0 +1,193 @@
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::process::ProcessRunner;
+use crate::proxy::{self, Proxy};
+use crate::{AppState, backend_version, open_externally, update_settings};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+/// GitHub's latest non-prerelease CARTA release.
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/CARTAvis/carta/releases/latest";
+const TIMEOUT_SECS: &str = "10";
+
+#[derive(Debug, PartialEq, Eq)]
+struct Release {
+    version: String,
+    url: String,
+}
+
+/// With the `check_for_updates` setting on, looks up the latest release once
+/// the session is up and mentions it when it is newer than the backend in
+/// use, once per release. Uses `curl`, which ships with every supported
+/// system, through the configured proxy; failures only show in the log.
+pub(crate) fn start(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if !state.settings.lock().unwrap().check_for_updates {
+        return;
+    }
+    let proxy = state.proxy.lock().unwrap().clone();
+    let handle = app.clone();
+    state.tasks.spawn(async move {
+        let app = handle.clone();
+        let checked = tauri::async_runtime::spawn_blocking(move || {
+            let release = latest_release(&*app.state::<AppState>().processes, proxy.as_ref())?;
+            // A system backend need not match the app's version, so ask the
+            // backend itself; this runs it, possibly through WSL.
+            let current = backend_version(&app)?;
+            Some((release, current))
+        })
+        .await;
+        let Ok(Some((release, current))) = checked else {
+            return;
+        };
+        if !is_newer(&release.version, &current) {
+            return;
+        }
+        let state = handle.state::<AppState>();
+        let notified = state.settings.lock().unwrap().notified_release.clone();
+        if notified.as_deref() != Some(release.version.as_str()) {
+            notify(&handle, release, &current);
+        }
+    });
+}
+
+fn latest_release(processes: &dyn ProcessRunner, proxy: Option<&Proxy>) -> Option<Release> {
+    let mut cmd = std::process::Command::new("curl");
+    cmd.args([
+        "--fail",
+        "--silent",
+        "--show-error",
+        "--location",
+        "--max-time",
+        TIMEOUT_SECS,
+        "--header",
+        "Accept: application/vnd.github+json",
+        LATEST_RELEASE_URL,
+    ]);
+    if let Some(proxy) = proxy {
+        cmd.args(proxy::curl_args(proxy));
+    }
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(crate::CREATE_NO_WINDOW);
+    let output = match processes.output(&mut cmd) {
+        Ok(output) => output,
+        Err(err) => {
+            eprintln!("Warning: failed to run curl for the update check: {}", err);
+            return None;
+        }
+    };
+    if !output.status.success() {
+        eprintln!(
+            "Warning: update check failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+    parse_release(&output.stdout)
+}
+
+fn parse_release(body: &[u8]) -> Option<Release> {
+    let release = serde_json::from_slice::<serde_json::Value>(body).ok()?;
+    let version = release.get("tag_name")?.as_str()?.trim_start_matches('v');
+    let url = release.get("html_url")?.as_str()?;
+    Some(Release {
+        version: version.to_string(),
+        url: url.to_string(),
+    })
+}
+
+/// Compares the numbers of dotted versions. A release is newer than its own
+/// prereleases, and prereleases are never offered.
+fn is_newer(latest: &str, current: &str) -> bool {
+    fn numbers(version: &str) -> Vec<u64> {
+        version
+            .split('-')
+            .next()
+            .unwrap_or(version)
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    !latest.contains('-') && (numbers(latest), true) > (numbers(current), !current.contains('-'))
+}
+
+/// A dialog that does not block the windows; Later keeps it from coming back
+/// until the next release.
+fn notify(app: &AppHandle, release: Release, current: &str) {
+    update_settings(app, |settings| {
+        settings.notified_release = Some(release.version.clone())
+    });
+    let Release { version, url } = release;
+    app.dialog()
+        .message(format!(
+            "A newer CARTA is available: version {} (you have {}).",
+            version, current
+        ))
+        .title("CARTA")
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Download".to_string(),
+            "Later".to_string(),
+        ))
+        .show(move |download| {
+            if download && let Ok(url) = Url::parse(&url) {
+                open_externally(&url);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::FakeRunner;
+
+    #[test]
+    fn parse_release_reads_tag_and_page() {
+        let body = br#"{"tag_name": "v5.1.0", "html_url": "https://github.com/CARTAvis/carta/releases/tag/v5.1.0", "prerelease": false}"#;
+        assert_eq!(
+            parse_release(body),
+            Some(Release {
+                version: "5.1.0".to_string(),
+                url: "https://github.com/CARTAvis/carta/releases/tag/v5.1.0".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_release(br#"{"message": "API rate limit exceeded"}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn latest_release_reads_curl_output() {
+        let body = r#"{"tag_name": "v5.1.0", "html_url": "https://github.com/CARTAvis/carta/releases/tag/v5.1.0"}"#;
+        let processes = FakeRunner::with_results(&[(0, body)]);
+        assert_eq!(
+            latest_release(&processes, None).map(|release| release.version),
+            Some("5.1.0".to_string())
+        );
+        let commands = processes.commands();
+        assert!(commands[0].starts_with("curl --fail "));
+        assert!(commands[0].ends_with(LATEST_RELEASE_URL));
+    }
+
+    #[test]
+    fn latest_release_ignores_failed_requests() {
+        // curl exits with 22 for an HTTP error under --fail.
+        let processes = FakeRunner::with_results(&[(22, "")]);
+        assert_eq!(latest_release(&processes, None), None);
+        let processes = FakeRunner::with_results(&[(0, "<html>rate limited</html>")]);
+        assert_eq!(latest_release(&processes, None), None);
+    }
+
+    #[test]
+    fn is_newer_compares_version_numbers() {
+        assert!(is_newer("5.1.0", "5.0.3"));
+        assert!(is_newer("5.0.10", "5.0.9"));
+        assert!(is_newer("5.0.3", "5.0.3-beta.1"));
+        assert!(!is_newer("5.0.3", "5.0.3"));
+        assert!(!is_newer("4.1.0", "5.0.3"));
+        assert!(!is_newer("6.0.0-beta.1", "5.0.3"));
+    }
+}
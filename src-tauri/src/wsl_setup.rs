@@ -0,0 +1,158 @@
+use std::{os::windows::process::CommandExt, path::Path, process::Command, sync::atomic::Ordering};
+
+use crate::{
+    AppError, AppResult, CREATE_NO_WINDOW, ERROR_DIALOGS, ExitCode, process::ProcessRunner,
+    wsl_distros,
+};
+
+const INSTALL: &str = "Install WSL";
+const QUIT: &str = "Quit";
+/// Starts CARTA once at the next sign-in, after the restart WSL needs.
+const RUN_ONCE_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\RunOnce";
+const RUN_ONCE_VALUE: &str = "CARTA";
+
+/// Checks that WSL and a Linux distribution are there before the backend is
+/// spawned, which would otherwise fail with only "program not found". From
+/// the desktop, offers to install them; the install needs a restart, so this
+/// then exits, and CARTA opens again after it to continue.
+pub(crate) fn ensure_ready(processes: &dyn ProcessRunner) -> AppResult<()> {
+    let ready = wsl_distros(processes).and_then(|distros| {
+        if distros.is_empty() {
+            Err("no Linux distribution is installed".into())
+        } else {
+            Ok(())
+        }
+    });
+    let Err(err) = ready else {
+        return Ok(());
+    };
+    eprintln!("Warning: WSL is not ready: {}", err);
+    if !ERROR_DIALOGS.load(Ordering::Relaxed) {
+        return Err(AppError::Other(
+            "CARTA runs its backend in WSL, which is not set up. Run `wsl --install` in an \
+             administrator terminal, restart Windows, then start CARTA again."
+                .to_string(),
+        ));
+    }
+
+    let choice = rfd::MessageDialog::new()
+        .set_title("CARTA")
+        .set_description(
+            "CARTA runs its backend in the Windows Subsystem for Linux (WSL), which is not \
+             set up on this computer.\n\nInstall it now? Windows asks for administrator \
+             permission, downloads Ubuntu, and needs a restart afterwards. CARTA opens \
+             again once you sign back in.",
+        )
+        .set_level(rfd::MessageLevel::Warning)
+        .set_buttons(rfd::MessageButtons::OkCancelCustom(
+            INSTALL.to_string(),
+            QUIT.to_string(),
+        ))
+        .show();
+    if choice != rfd::MessageDialogResult::Custom(INSTALL.to_string()) {
+        std::process::exit(ExitCode::BackendMissing as i32);
+    }
+
+    if let Err(err) = install(processes) {
+        ExitCode::BackendMissing.exit(format!("Failed to install WSL: {}", err));
+    }
+    let reopen = std::env::current_exe()
+        .map_err(AppError::from)
+        .and_then(|exe| reopen_after_restart(processes, &exe));
+    if let Err(err) = reopen {
+        eprintln!(
+            "Warning: CARTA will not open by itself after the restart: {}",
+            err
+        );
+    }
+    rfd::MessageDialog::new()
+        .set_title("CARTA")
+        .set_description(
+            "WSL is installing. Restart Windows to finish; if Ubuntu asks for a user name \
+             and password, set them, and CARTA continues its setup.",
+        )
+        .set_level(rfd::MessageLevel::Info)
+        .set_buttons(rfd::MessageButtons::Ok)
+        .show();
+    std::process::exit(0);
+}
+
+/// Runs `wsl --install` elevated and waits for it. Declining the UAC prompt
+/// fails here as well.
+fn install(processes: &dyn ProcessRunner) -> AppResult<()> {
+    let mut cmd = Command::new("powershell.exe");
+    cmd.args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        "$p = Start-Process -FilePath wsl.exe -ArgumentList '--install' -Verb RunAs \
+             -Wait -PassThru; exit $p.ExitCode",
+    ])
+    .creation_flags(CREATE_NO_WINDOW);
+    let status = processes
+        .status(&mut cmd)
+        .map_err(|err| AppError::Other(format!("Failed to run PowerShell: {}", err)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::Other(format!(
+            "wsl --install exited with {}",
+            status
+        )))
+    }
+}
+
+/// Has Windows start `exe` once at the next sign-in.
+fn reopen_after_restart(processes: &dyn ProcessRunner, exe: &Path) -> AppResult<()> {
+    let mut cmd = Command::new("reg.exe");
+    cmd.args([
+        "add",
+        RUN_ONCE_KEY,
+        "/v",
+        RUN_ONCE_VALUE,
+        "/t",
+        "REG_SZ",
+        "/f",
+        "/d",
+    ])
+    .arg(format!("\"{}\"", exe.display()))
+    .creation_flags(CREATE_NO_WINDOW);
+    let status = processes
+        .status(&mut cmd)
+        .map_err(|err| AppError::Other(format!("Failed to run reg.exe: {}", err)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::Other(format!("reg.exe exited with {}", status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::FakeRunner;
+
+    #[test]
+    fn install_runs_wsl_install_elevated_and_reports_failure() {
+        let processes = FakeRunner::with_results(&[(0, ""), (1, "")]);
+        assert!(install(&processes).is_ok());
+        assert!(install(&processes).is_err());
+        let commands = processes.commands();
+        assert_eq!(commands.len(), 2);
+        assert!(commands[0].starts_with("powershell.exe -NoProfile -NonInteractive -Command "));
+        assert!(commands[0].contains("-FilePath wsl.exe"));
+        assert!(commands[0].contains("-Verb RunAs -Wait"));
+    }
+
+    #[test]
+    fn reopen_after_restart_adds_a_run_once_entry() {
+        let processes = FakeRunner::with_results(&[(0, ""), (1, "")]);
+        let exe = Path::new(r"C:\Program Files\CARTA\carta.exe");
+        assert!(reopen_after_restart(&processes, exe).is_ok());
+        assert!(reopen_after_restart(&processes, exe).is_err());
+        assert_eq!(
+            processes.commands()[0],
+            r#"reg.exe add 'HKCU\Software\Microsoft\Windows\CurrentVersion\RunOnce' /v CARTA /t REG_SZ /f /d '"C:\Program Files\CARTA\carta.exe"'"#
+        );
+    }
+}